@@ -0,0 +1,555 @@
+//! ActivityPub `Flag` activities and HTTP signatures
+//!
+//! Incoming `Flag` activities are verified using the
+//! [HTTP signatures](https://datatracker.ietf.org/doc/html/draft-cavage-http-signatures-12)
+//! draft used by most fediverse software (`rsa-sha256`, with a `Digest` header).
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs1v15::{Signature, SigningKey, VerifyingKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePublicKey, LineEnding};
+use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use rsa::{RsaPrivateKey, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+use crate::db::Report;
+
+/// How far the `Date` header of a signed request may be from the current time
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(60 * 60);
+
+/// An `object` value, which may be a single id or a list of ids
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ObjectRefs {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl ObjectRefs {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            ObjectRefs::One(id) => vec![id.clone()],
+            ObjectRefs::Many(ids) => ids.clone(),
+        }
+    }
+}
+
+/// An incoming (or outgoing) activity, only the fields we use are included
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Activity {
+    #[serde(rename = "@context", default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<serde_json::Value>,
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "type")]
+    pub activity_type: String,
+    pub actor: String,
+    pub object: ObjectRefs,
+    #[serde(default)]
+    pub content: String,
+}
+
+impl Activity {
+    /// Build a `Flag` activity for a [`Report`]
+    ///
+    /// # Arguments:
+    /// * `base_url` - the public URL of this server
+    /// * `report` - the [`Report`] to flag
+    pub fn flag(base_url: &str, report: &Report) -> Activity {
+        Activity {
+            context: Some(serde_json::Value::String(
+                "https://www.w3.org/ns/activitystreams".to_string(),
+            )),
+            id: format!("{base_url}/flags/{}", dorsal::utility::uuid()),
+            activity_type: String::from("Flag"),
+            actor: actor_id(base_url),
+            object: ObjectRefs::Many(vec![report.address.clone()]),
+            content: report.content.clone(),
+        }
+    }
+}
+
+/// Get the id of the instance actor
+pub fn actor_id(base_url: &str) -> String {
+    format!("{base_url}/actor")
+}
+
+/// Build the instance actor document
+///
+/// # Arguments:
+/// * `base_url` - the public URL of this server
+/// * `key` - the private key of the instance actor
+pub fn actor_document(base_url: &str, key: &RsaPrivateKey) -> serde_json::Value {
+    let id = actor_id(base_url);
+    let public_key_pem = RsaPublicKey::from(key)
+        .to_public_key_pem(LineEnding::LF)
+        .unwrap_or_default();
+
+    serde_json::json!({
+        "@context": [
+            "https://www.w3.org/ns/activitystreams",
+            "https://w3id.org/security/v1"
+        ],
+        "id": id,
+        "type": "Application",
+        "preferredUsername": "deducktive",
+        "inbox": format!("{base_url}/inbox"),
+        "publicKey": {
+            "id": format!("{id}#main-key"),
+            "owner": id,
+            "publicKeyPem": public_key_pem
+        }
+    })
+}
+
+/// Load the instance actor's private key from a PKCS#8 PEM file
+pub fn load_private_key(path: &str) -> Option<RsaPrivateKey> {
+    let pem = std::fs::read_to_string(path).ok()?;
+    RsaPrivateKey::from_pkcs8_pem(&pem).ok()
+}
+
+/// Parse a public key PEM (SPKI or PKCS#1)
+pub fn parse_public_key(pem: &str) -> Option<RsaPublicKey> {
+    RsaPublicKey::from_public_key_pem(pem)
+        .or_else(|_| RsaPublicKey::from_pkcs1_pem(pem))
+        .ok()
+}
+
+/// Build the value of a `Digest` header for the given body
+pub fn digest_header(body: &[u8]) -> String {
+    format!("SHA-256={}", BASE64.encode(Sha256::digest(body)))
+}
+
+/// A parsed `Signature` header
+#[derive(Debug, Clone)]
+pub struct SignatureHeader {
+    pub key_id: String,
+    pub algorithm: String,
+    pub headers: Vec<String>,
+    pub signature: Vec<u8>,
+}
+
+impl SignatureHeader {
+    /// Parse a `Signature` header value
+    pub fn parse(value: &str) -> Option<SignatureHeader> {
+        let mut fields: HashMap<String, String> = HashMap::new();
+
+        for part in value.split(',') {
+            let (name, value) = part.trim().split_once('=')?;
+            fields.insert(
+                name.trim().to_lowercase(),
+                value.trim().trim_matches('"').to_string(),
+            );
+        }
+
+        Some(SignatureHeader {
+            key_id: fields.get("keyid")?.to_owned(),
+            algorithm: fields
+                .get("algorithm")
+                .cloned()
+                .unwrap_or(String::from("rsa-sha256")),
+            headers: fields
+                .get("headers")
+                .cloned()
+                .unwrap_or(String::from("date"))
+                .split_whitespace()
+                .map(|h| h.to_lowercase())
+                .collect(),
+            signature: BASE64.decode(fields.get("signature")?).ok()?,
+        })
+    }
+
+    /// Get the id of the actor owning the key (the `keyId` without its fragment)
+    pub fn actor(&self) -> String {
+        match self.key_id.split_once('#') {
+            Some((actor, _)) => actor.to_string(),
+            None => self.key_id.clone(),
+        }
+    }
+}
+
+/// Build the string which gets signed
+///
+/// # Arguments:
+/// * `method` - the request method
+/// * `path` - the request path (including the query string)
+/// * `headers` - the names of the headers to include, in order
+/// * `get_header` - a function returning the value of a header
+pub fn signing_string(
+    method: &str,
+    path: &str,
+    headers: &[String],
+    get_header: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for header in headers {
+        if header == "(request-target)" {
+            lines.push(format!(
+                "(request-target): {} {path}",
+                method.to_lowercase()
+            ));
+        } else {
+            lines.push(format!("{header}: {}", get_header(header)?));
+        }
+    }
+
+    Some(lines.join("\n"))
+}
+
+/// Verify a signature over the given signing string
+pub fn verify(key: &RsaPublicKey, signing_string: &str, signature: &[u8]) -> bool {
+    let signature = match Signature::try_from(signature) {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+
+    VerifyingKey::<Sha256>::new(key.clone())
+        .verify(signing_string.as_bytes(), &signature)
+        .is_ok()
+}
+
+/// Check if an HTTP date is within [`MAX_CLOCK_SKEW`] of the current time
+pub fn date_is_fresh(date: &str) -> bool {
+    let date = match httpdate::parse_http_date(date) {
+        Ok(d) => d,
+        Err(_) => return false,
+    };
+
+    let now = SystemTime::now();
+    let skew = match now.duration_since(date) {
+        Ok(d) => d,
+        Err(e) => e.duration(),
+    };
+
+    skew <= MAX_CLOCK_SKEW
+}
+
+/// Verify the HTTP signature of an incoming request
+///
+/// The actor is fetched from the `keyId` of the signature (through [`crate::snapshots::get`], so
+/// it can't point into private networks), and must be the document at that address and own the
+/// key. Returns the id of the actor who signed the request.
+///
+/// # Arguments:
+/// * `client` - the HTTP client (must not follow redirects, see [`crate::snapshots::client`])
+/// * `allow_private` - allow fetching actors in private networks (only for testing)
+/// * `signature` - the parsed `Signature` header
+/// * `method` - the request method
+/// * `path` - the request path (including the query string)
+/// * `get_header` - a function returning the value of a request header
+/// * `body` - the request body
+pub async fn verify_request(
+    client: &awc::Client,
+    allow_private: bool,
+    signature: &SignatureHeader,
+    method: &str,
+    path: &str,
+    get_header: impl Fn(&str) -> Option<String>,
+    body: &[u8],
+) -> Result<String, String> {
+    if (signature.algorithm != "rsa-sha256") && (signature.algorithm != "hs2019") {
+        return Err(String::from("Unsupported signature algorithm"));
+    }
+
+    // the signature must cover the target, the date and the body
+    for required in ["(request-target)", "host", "date", "digest"] {
+        if !signature.headers.iter().any(|h| h == required) {
+            return Err(format!("Signature does not include \"{required}\""));
+        }
+    }
+
+    let digest = get_header("digest").unwrap_or_default();
+    if !digest.split(',').any(|d| d.trim() == digest_header(body)) {
+        return Err(String::from("Digest does not match body"));
+    }
+
+    if !date_is_fresh(&get_header("date").unwrap_or_default()) {
+        return Err(String::from("Date is too old"));
+    }
+
+    // fetch key
+    let actor = signature.actor();
+    let url = match Url::parse(&actor) {
+        Ok(u) if u.username().is_empty() && u.password().is_none() => u,
+        _ => return Err(String::from("Invalid key id")),
+    };
+
+    let res = match crate::snapshots::get(client, &url, allow_private).await {
+        Ok(req) => {
+            req.timeout(Duration::from_secs(10))
+                .insert_header(("Accept", "application/activity+json"))
+                .send()
+                .await
+        }
+        Err(e) => return Err(e),
+    };
+
+    let doc = match res {
+        Ok(mut res) if res.status().is_success() => match res.json::<serde_json::Value>().await {
+            Ok(doc) => doc,
+            Err(_) => return Err(String::from("Failed to read actor")),
+        },
+        _ => return Err(String::from("Failed to fetch actor")),
+    };
+
+    // the document must be the actor at the key's address, and the key must be the signing key
+    if doc["id"].as_str() != Some(actor.as_str()) {
+        return Err(String::from("Actor id does not match key id"));
+    }
+
+    if doc["publicKey"]["id"].as_str() != Some(signature.key_id.as_str()) {
+        return Err(String::from("Key id does not match actor"));
+    }
+
+    let key = match doc["publicKey"]["publicKeyPem"]
+        .as_str()
+        .and_then(parse_public_key)
+    {
+        Some(k) => k,
+        None => return Err(String::from("Actor has no valid public key")),
+    };
+
+    // verify
+    let signing_string = match signing_string(method, path, &signature.headers, get_header) {
+        Some(s) => s,
+        None => return Err(String::from("Signed header is missing")),
+    };
+
+    if !verify(&key, &signing_string, &signature.signature) {
+        return Err(String::from("Invalid signature"));
+    }
+
+    Ok(actor)
+}
+
+/// Build the headers for a signed `POST` request
+///
+/// Returns `(Date, Digest, Signature)`
+///
+/// # Arguments:
+/// * `base_url` - the public URL of this server
+/// * `key` - the private key of the instance actor
+/// * `host` - the `Host` of the receiving server
+/// * `path` - the path of the receiving inbox
+/// * `body` - the request body
+pub fn sign_post(
+    base_url: &str,
+    key: &RsaPrivateKey,
+    host: &str,
+    path: &str,
+    body: &[u8],
+) -> (String, String, String) {
+    let date = httpdate::fmt_http_date(SystemTime::now());
+    let digest = digest_header(body);
+
+    let headers: Vec<String> = ["(request-target)", "host", "date", "digest"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    let to_sign = signing_string("post", path, &headers, |h| match h {
+        "host" => Some(host.to_string()),
+        "date" => Some(date.clone()),
+        "digest" => Some(digest.clone()),
+        _ => None,
+    })
+    .unwrap_or_default();
+
+    let signature = SigningKey::<Sha256>::new(key.clone()).sign(to_sign.as_bytes());
+
+    (
+        date,
+        digest,
+        format!(
+            "keyId=\"{}#main-key\",algorithm=\"rsa-sha256\",headers=\"{}\",signature=\"{}\"",
+            actor_id(base_url),
+            headers.join(" "),
+            BASE64.encode(signature.to_bytes())
+        ),
+    )
+}
+
+/// Check if an actor belongs to one of the blocked instances (by host, ignoring the port)
+///
+/// # Arguments:
+/// * `blocked` - the hosts of the blocked instances
+/// * `actor` - the id of the actor
+pub fn is_blocked(blocked: &[String], actor: &str) -> bool {
+    let host = |url: &str| Url::parse(url).ok()?.host_str().map(|h| h.to_string());
+
+    match host(actor) {
+        Some(actor) => blocked
+            .iter()
+            .any(|b| host(&format!("https://{b}")).as_deref() == Some(actor.as_str())),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpResponse, HttpServer};
+
+    /// Start a fake instance serving actor documents, returning its address
+    ///
+    /// * `/actor` - the actor of `key`
+    /// * `/impostor/actor` - the same document (so its id is `/actor`)
+    /// * `/nokey/actor` - the actor of `key` at this address, without a key id
+    fn serve(key: RsaPrivateKey) -> String {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let base_ = base.clone();
+
+        let server = HttpServer::new(move || {
+            let actor = actor_document(&base_, &key);
+            let mut nokey = actor_document(&format!("{base_}/nokey"), &key);
+            nokey["publicKey"].as_object_mut().unwrap().remove("id");
+
+            App::new()
+                .route(
+                    "/actor",
+                    web::get().to({
+                        let actor = actor.clone();
+                        move || {
+                            let actor = actor.clone();
+                            async move { HttpResponse::Ok().json(actor) }
+                        }
+                    }),
+                )
+                .route(
+                    "/impostor/actor",
+                    web::get().to(move || {
+                        let actor = actor.clone();
+                        async move { HttpResponse::Ok().json(actor) }
+                    }),
+                )
+                .route(
+                    "/nokey/actor",
+                    web::get().to(move || {
+                        let nokey = nokey.clone();
+                        async move { HttpResponse::Ok().json(nokey) }
+                    }),
+                )
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap();
+
+        actix_web::rt::spawn(server.run());
+        base
+    }
+
+    /// Sign a request to `/inbox` as the actor at `{base}/actor`, then verify it
+    async fn sign_and_verify(
+        key: &RsaPrivateKey,
+        base: &str,
+        body: &[u8],
+        received: &[u8],
+        allow_private: bool,
+    ) -> Result<String, String> {
+        let (date, digest, signature) = sign_post(base, key, "example.com", "/inbox", body);
+        let signature = SignatureHeader::parse(&signature).unwrap();
+
+        verify_request(
+            &crate::snapshots::client(),
+            allow_private,
+            &signature,
+            "POST",
+            "/inbox",
+            |h| match h {
+                "host" => Some(String::from("example.com")),
+                "date" => Some(date.clone()),
+                "digest" => Some(digest.clone()),
+                _ => None,
+            },
+            received,
+        )
+        .await
+    }
+
+    fn key() -> RsaPrivateKey {
+        RsaPrivateKey::new(&mut rsa::rand_core::OsRng, 1024).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn accepts_valid_signatures() {
+        let key = key();
+        let server = serve(key.clone());
+
+        assert_eq!(
+            sign_and_verify(&key, &server, b"{}", b"{}", true).await,
+            Ok(format!("{server}/actor"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn rejects_other_keys() {
+        let server = serve(key());
+
+        assert_eq!(
+            sign_and_verify(&key(), &server, b"{}", b"{}", true).await,
+            Err(String::from("Invalid signature"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn rejects_changed_bodies() {
+        let key = key();
+        let server = serve(key.clone());
+
+        assert_eq!(
+            sign_and_verify(&key, &server, b"{}", b"{\"a\":1}", true).await,
+            Err(String::from("Digest does not match body"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn rejects_actors_at_other_addresses() {
+        let key = key();
+        let server = serve(key.clone());
+
+        // the document claims to be `/actor`, but was fetched from `/impostor/actor`
+        assert_eq!(
+            sign_and_verify(&key, &format!("{server}/impostor"), b"{}", b"{}", true).await,
+            Err(String::from("Actor id does not match key id"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn rejects_actors_without_key_ids() {
+        let key = key();
+        let server = serve(key.clone());
+
+        assert_eq!(
+            sign_and_verify(&key, &format!("{server}/nokey"), b"{}", b"{}", true).await,
+            Err(String::from("Key id does not match actor"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn rejects_private_actors() {
+        let key = key();
+        let server = serve(key.clone());
+
+        assert_eq!(
+            sign_and_verify(&key, &server, b"{}", b"{}", false).await,
+            Err(String::from("Address resolves to a private network"))
+        );
+    }
+
+    #[test]
+    fn blocked_instances() {
+        let blocked = vec![String::from("Spam.Example"), String::from("bücher.example")];
+
+        assert!(is_blocked(&blocked, "https://spam.example/actor"));
+        assert!(is_blocked(&blocked, "https://SPAM.example:8443/users/a"));
+        assert!(is_blocked(&blocked, "https://xn--bcher-kva.example/actor"));
+        assert!(!is_blocked(&blocked, "https://notspam.example/actor"));
+        assert!(!is_blocked(&blocked, "https://spam.example.org/actor"));
+        assert!(!is_blocked(&blocked, "not a url"));
+    }
+}
@@ -0,0 +1,283 @@
+use crate::activitypub::{self, Activity, SignatureHeader};
+use crate::db::{AppData, Report, ReportStatus, ReportType};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use url::{Position, Url};
+
+use crate::pages::base;
+use crate::snapshots;
+
+/// The most reports created from a single `Flag` activity
+pub const MAX_FLAG_OBJECTS: usize = 20;
+
+// props
+#[derive(Serialize, Deserialize)]
+pub struct PForwardReport {
+    /// The inbox of the remote instance (usually its shared inbox)
+    pub inbox: String,
+}
+
+#[get("/actor")]
+/// Get the instance actor
+pub async fn actor_request(data: web::Data<AppData>) -> impl Responder {
+    let key = match (data.config.activitypub.enabled, &data.actor_key) {
+        (true, Some(k)) => k,
+        _ => return HttpResponse::NotFound().body("ActivityPub is disabled"),
+    };
+
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/activity+json"))
+        .body(activitypub::actor_document(&data.config.base_url, key).to_string())
+}
+
+#[post("/inbox")]
+/// Accept `Flag` activities and convert them into [`Report`]s
+pub async fn inbox_request(
+    req: HttpRequest,
+    body: web::Bytes,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    if !data.config.activitypub.enabled {
+        return HttpResponse::NotFound().body("ActivityPub is disabled");
+    }
+
+    let ip = crate::rules::request_ip(&req, &data.config.trusted_proxies);
+    let header = |name: &str| -> Option<String> {
+        req.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+
+    let signature = match header("signature").and_then(|s| SignatureHeader::parse(&s)) {
+        Some(s) => s,
+        None => return HttpResponse::Unauthorized().body("Missing or invalid signature"),
+    };
+
+    // blocked instances are refused before their actor is fetched
+    if activitypub::is_blocked(
+        &data.config.activitypub.blocked_instances,
+        &signature.actor(),
+    ) {
+        return HttpResponse::Forbidden().body("Instance is blocked");
+    }
+
+    let path = match req.uri().path_and_query() {
+        Some(p) => p.to_string(),
+        None => req.path().to_string(),
+    };
+
+    let actor = match activitypub::verify_request(
+        &crate::snapshots::client(),
+        data.config.activitypub.allow_private,
+        &signature,
+        req.method().as_str(),
+        &path,
+        header,
+        &body,
+    )
+    .await
+    {
+        Ok(a) => a,
+        Err(e) => return HttpResponse::Unauthorized().body(e),
+    };
+
+    let activity = match serde_json::from_slice::<Activity>(&body) {
+        Ok(a) => a,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    if activity.actor != actor {
+        return HttpResponse::Unauthorized().body("Actor does not match signature");
+    }
+
+    // we only handle flags, everything else is accepted and ignored
+    if activity.activity_type != "Flag" {
+        return HttpResponse::Accepted().finish();
+    }
+
+    let content = if activity.content.trim().is_empty() {
+        format!("Flagged by {actor}")
+    } else {
+        activity.content.clone()
+    };
+
+    // remote actors are rate limited like local reporters
+    let tier = match crate::reputation::admit(&data, &actor).await {
+        Ok(t) => t,
+        Err(e) => return HttpResponse::TooManyRequests().body(e),
+    };
+
+    let mut created: Vec<String> = Vec::new();
+    for object in activity.object.to_vec().iter().take(MAX_FLAG_OBJECTS) {
+        if crate::canonical::check_site(&data.config.sites, object).is_err() {
+            continue;
+        }
+
+        let mut report = Report {
+            id: String::new(),
+            report_type: ReportType::Other,
+            status: ReportStatus::Active,
+            author: actor.clone(),
+            content: content.clone(),
+            address: object.to_owned(),
+            reporter: actor.clone(),
+            ..Default::default()
+        };
+
+        if data.db.create_report(&mut report).await.success {
+            created.push(report.id.clone());
+            crate::api::reports::run_creation_hooks(&data, &mut report, tier, ip).await;
+        }
+    }
+
+    HttpResponse::Accepted()
+        .append_header(("Content-Type", "application/json"))
+        .body(
+            serde_json::to_string(&DefaultReturn {
+                success: !created.is_empty(),
+                message: format!("Created {} report(s)", created.len()),
+                payload: created,
+            })
+            .unwrap(),
+        )
+}
+
+#[post("/api/v1/reports/{id}/forward")]
+/// Forward a report to a remote instance as a `Flag` activity
+pub async fn forward_request(
+    req: HttpRequest,
+    body: web::Json<PForwardReport>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res: DefaultReturn<Option<String>> = if !base::has_permission(&token_user, "StaffDashboard")
+    {
+        DefaultReturn {
+            success: false,
+            message: String::from("You are not allowed to do this."),
+            payload: None,
+        }
+    } else {
+        let username = token_user.unwrap().payload.unwrap().user.username;
+        forward_report(&data, id, &body.inbox, &username).await
+    };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+/// Send a [`Report`] to the given inbox as a signed `Flag` activity
+///
+/// The inbox is only posted to if it resolves to a public address (see
+/// [`crate::snapshots::resolve`]).
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `id` - the ID of the report
+/// * `inbox` - the address of the remote inbox
+/// * `editor` - the username of the staff member forwarding the report
+async fn forward_report(
+    data: &web::Data<AppData>,
+    id: String,
+    inbox: &str,
+    editor: &str,
+) -> DefaultReturn<Option<String>> {
+    let key = match (data.config.activitypub.enabled, &data.actor_key) {
+        (true, Some(k)) => k,
+        _ => {
+            return DefaultReturn {
+                success: false,
+                message: String::from("ActivityPub is disabled"),
+                payload: None,
+            }
+        }
+    };
+
+    let url = match Url::parse(inbox) {
+        Ok(u) if u.has_host() && u.username().is_empty() && u.password().is_none() => u,
+        _ => {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Inbox is invalid"),
+                payload: None,
+            }
+        }
+    };
+
+    let addr = match snapshots::resolve(&url, data.config.activitypub.allow_private).await {
+        Ok(a) => a,
+        Err(e) => {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: None,
+            }
+        }
+    };
+
+    let report = match data.db.get_report_by_id(id.clone()).await.payload {
+        Some(r) => r,
+        None => {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Report does not exist"),
+                payload: None,
+            }
+        }
+    };
+
+    // send flag
+    let host = &url[Position::BeforeHost..Position::AfterPort];
+    let path = &url[Position::BeforePath..Position::AfterQuery];
+
+    let flag = Activity::flag(&data.config.base_url, &report);
+    let body = serde_json::to_vec(&flag).unwrap();
+    let (date, digest, signature) =
+        activitypub::sign_post(&data.config.base_url, key, host, path, &body);
+
+    let res = snapshots::client()
+        .post(url.as_str())
+        .address(addr)
+        .timeout(Duration::from_secs(10))
+        .insert_header(("Host", host))
+        .insert_header(("Date", date))
+        .insert_header(("Digest", digest))
+        .insert_header(("Signature", signature))
+        .insert_header(("Content-Type", "application/activity+json"))
+        .send_body(body)
+        .await;
+
+    match res {
+        Ok(res) if res.status().is_success() => {
+            data.db
+                .audit(&report.id, editor, format!("forward:{}", url.as_str()))
+                .await;
+
+            DefaultReturn {
+                success: true,
+                message: String::from("Report forwarded!"),
+                payload: Some(flag.id),
+            }
+        }
+        Ok(res) => DefaultReturn {
+            success: false,
+            message: format!("Remote instance returned {}", res.status()),
+            payload: None,
+        },
+        Err(e) => DefaultReturn {
+            success: false,
+            message: e.to_string(),
+            payload: None,
+        },
+    }
+}
@@ -0,0 +1,182 @@
+use crate::attachments;
+use crate::db::{AppData, Attachment};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+#[derive(Default, PartialEq, serde::Deserialize)]
+pub struct UploadQueryProps {
+    /// The name of the uploaded file
+    #[serde(default)]
+    pub name: String,
+}
+
+/// Read an uploaded file, limited to `attachments.max_size`
+async fn read_body(data: &web::Data<AppData>, body: web::Payload) -> Result<web::Bytes, String> {
+    match body
+        .to_bytes_limited(data.config.attachments.max_size)
+        .await
+    {
+        Ok(Ok(b)) => Ok(b),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(String::from("File is too large")),
+    }
+}
+
+#[post("/api/v1/reports/{id}/attachments")]
+/// Attach a file (the request body) to a report as staff
+pub async fn upload_request(
+    req: HttpRequest,
+    data: web::Data<AppData>,
+    info: web::Query<UploadQueryProps>,
+    body: web::Payload,
+) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res: DefaultReturn<Option<Attachment>> =
+        if !base::has_permission(&token_user, "StaffDashboard") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else {
+            let username = token_user.unwrap().payload.unwrap().user.username;
+
+            match (
+                data.db.get_report_by_id(id).await.payload,
+                read_body(&data, body).await,
+            ) {
+                (Some(report), Ok(file)) => {
+                    attachments::upload(&data, &report, &info.name, file, &username).await
+                }
+                (None, _) => DefaultReturn {
+                    success: false,
+                    message: String::from("Report does not exist"),
+                    payload: None,
+                },
+                (_, Err(e)) => DefaultReturn {
+                    success: false,
+                    message: e,
+                    payload: None,
+                },
+            }
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[post("/api/v1/track/{token}/attachments")]
+/// Attach a file (the request body) to an active report as its reporter (through its tracking token)
+pub async fn track_upload_request(
+    req: HttpRequest,
+    data: web::Data<AppData>,
+    info: web::Query<UploadQueryProps>,
+    body: web::Payload,
+) -> impl Responder {
+    let token = req.match_info().get("token").unwrap().to_string();
+
+    let res: DefaultReturn<Option<Attachment>> = match (
+        data.db.get_report_by_tracking(token).await.payload,
+        read_body(&data, body).await,
+    ) {
+        (Some(report), Ok(file)) if report.status == crate::db::ReportStatus::Active => {
+            attachments::upload(&data, &report, &info.name, file, "").await
+        }
+        (Some(_), Ok(_)) => DefaultReturn {
+            success: false,
+            message: String::from("Report has already been handled"),
+            payload: None,
+        },
+        (None, _) => DefaultReturn {
+            success: false,
+            message: String::from("Report does not exist"),
+            payload: None,
+        },
+        (_, Err(e)) => DefaultReturn {
+            success: false,
+            message: e,
+            payload: None,
+        },
+    };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[get("/api/v1/reports/{id}/attachments")]
+/// Get the attachments of a report
+pub async fn list_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res: DefaultReturn<Option<Vec<Attachment>>> =
+        if !base::has_permission(&token_user, "StaffDashboard") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else {
+            data.db.get_attachments_by_report(id).await
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[get("/api/v1/attachments/{id}")]
+/// Get the file of an attachment
+///
+/// Images are shown inline, anything else is always downloaded.
+pub async fn file_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if !base::has_permission(&token_user, "StaffDashboard") {
+        return HttpResponse::Unauthorized().body("You are not allowed to do this.");
+    }
+
+    let attachment = match data.db.get_attachment_by_id(id).await.payload {
+        Some(a) => a,
+        None => return HttpResponse::NotFound().body("Attachment does not exist"),
+    };
+
+    let store = data.attachments.clone();
+    let file_id = attachment.id.clone();
+    let file = match web::block(move || store.get(&file_id)).await {
+        Ok(Ok(f)) => f,
+        _ => return HttpResponse::NotFound().body("Attachment file does not exist"),
+    };
+
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", attachment.mime.as_str()))
+        .append_header((
+            "Content-Disposition",
+            format!(
+                "{}; filename=\"{}\"",
+                if attachments::is_inline(&attachment.mime) {
+                    "inline"
+                } else {
+                    "attachment"
+                },
+                // names are sanitized when uploaded, but never trust the database
+                attachments::safe_file_name(&attachment.name)
+            ),
+        ))
+        .append_header(("X-Content-Type-Options", "nosniff"))
+        .append_header(("Content-Security-Policy", "sandbox; default-src 'none'"))
+        .body(file)
+}
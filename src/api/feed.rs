@@ -0,0 +1,104 @@
+use crate::dates::rfc3339;
+use crate::db::{AppData, Report, ReportFilter};
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use askama::Template;
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+#[derive(Default, PartialEq, serde::Deserialize)]
+pub struct FeedQueryProps {
+    pub token: String,
+    pub status: Option<String>,
+    #[serde(rename = "type")]
+    pub report_type: Option<String>,
+    pub tag: Option<String>,
+}
+
+pub struct FeedEntry {
+    pub report: Report,
+    pub updated: String,
+}
+
+#[derive(Template)]
+#[template(path = "feed.xml")]
+struct FeedTemplate {
+    entries: Vec<FeedEntry>,
+    updated: String,
+    base_url: String,
+    filter: String,
+}
+
+#[get("/api/v1/reports/feed.atom")]
+/// Get an Atom feed of the newest reports (authenticated through a feed token)
+pub async fn feed_request(
+    data: web::Data<AppData>,
+    info: web::Query<FeedQueryProps>,
+) -> impl Responder {
+    let user = data.db.get_feed_token_user(info.token.clone()).await;
+
+    match user.payload {
+        Some(ua) => {
+            if !ua.level.permissions.contains(&"StaffDashboard".to_string()) {
+                return HttpResponse::Unauthorized().body("You are not allowed to do this.");
+            }
+        }
+        None => return HttpResponse::Unauthorized().body(user.message),
+    }
+
+    // get reports
+    let filter = ReportFilter::from_query(&info.status, &info.report_type, &info.tag, &None);
+    let res = data.db.get_reports_by_filter(&filter, None).await;
+
+    if !res.success {
+        return HttpResponse::NotAcceptable().body(res.message);
+    }
+
+    let reports = res.payload.unwrap();
+
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/atom+xml; charset=utf-8"))
+        .body(
+            FeedTemplate {
+                updated: rfc3339(match reports.first() {
+                    Some(r) => r.timestamp,
+                    None => dorsal::utility::unix_epoch_timestamp(),
+                }),
+                entries: reports
+                    .into_iter()
+                    .map(|r| FeedEntry {
+                        updated: rfc3339(r.timestamp),
+                        report: r,
+                    })
+                    .collect(),
+                base_url: data.config.base_url.clone(),
+                filter: filter.to_query().trim_start_matches('&').to_string(),
+            }
+            .render()
+            .unwrap(),
+        )
+}
+
+#[post("/api/v1/feed/token")]
+/// Create a feed token for the current user (replacing their old token)
+pub async fn create_token_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res: DefaultReturn<Option<String>> = if !base::has_permission(&token_user, "StaffDashboard")
+    {
+        DefaultReturn {
+            success: false,
+            message: String::from("You are not allowed to do this."),
+            payload: None,
+        }
+    } else {
+        let username = token_user.unwrap().payload.unwrap().user.username;
+        data.db.create_feed_token(username).await
+    };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
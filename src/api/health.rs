@@ -0,0 +1,108 @@
+use crate::db::{AppData, MIGRATIONS};
+use actix_web::dev::ServiceRequest;
+use actix_web::{get, web, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+
+/// The paths served before startup (and migrations) has finished, every other request gets a
+/// `503` until then (see [`is_starting`])
+pub const STARTUP_PATHS: &[&str] = &["/healthz", "/readyz"];
+
+#[derive(Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub name: String,
+    pub version: String,
+    /// The git commit the binary was built from (set through `GIT_COMMIT` at build time)
+    pub commit: String,
+    pub database: String,
+    pub debug: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HealthReport {
+    /// If startup (and migrations) have finished
+    pub ready: bool,
+    pub database: bool,
+    pub cache: bool,
+    /// The number of migrations applied to the database
+    pub schema_version: usize,
+    /// The number of migrations this build expects to be applied
+    pub expected_schema_version: usize,
+    pub build: BuildInfo,
+}
+
+/// If a request has to be refused because startup (and migrations) hasn't finished yet
+pub fn is_starting(req: &ServiceRequest) -> bool {
+    !STARTUP_PATHS.contains(&req.path())
+        && req
+            .app_data::<web::Data<AppData>>()
+            .is_none_or(|d| !d.ready.load(Ordering::SeqCst))
+}
+
+/// Collect the current [`HealthReport`]
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `check_connections` - if the database and cache connections should be checked
+pub async fn get_health_report(data: &web::Data<AppData>, check_connections: bool) -> HealthReport {
+    let database = check_connections && data.db.check_database().await;
+
+    HealthReport {
+        ready: data.ready.load(Ordering::SeqCst),
+        database,
+        cache: check_connections && data.db.check_cache().await,
+        schema_version: if database {
+            data.db.get_schema_version().await.unwrap_or(0)
+        } else {
+            0
+        },
+        expected_schema_version: MIGRATIONS.len(),
+        build: BuildInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: option_env!("GIT_COMMIT").unwrap_or("unknown").to_string(),
+            database: data.db.base.db._type.clone(),
+            debug: cfg!(debug_assertions),
+        },
+    }
+}
+
+/// Build a probe response, `503` is returned when `healthy` is `false`
+fn probe_response(healthy: bool, report: HealthReport) -> HttpResponse {
+    let body = serde_json::to_string(&DefaultReturn {
+        success: healthy,
+        message: String::from(if healthy { "Healthy" } else { "Unhealthy" }),
+        payload: report,
+    })
+    .unwrap();
+
+    if healthy {
+        HttpResponse::Ok()
+            .append_header(("Content-Type", "application/json"))
+            .body(body)
+    } else {
+        HttpResponse::ServiceUnavailable()
+            .append_header(("Content-Type", "application/json"))
+            .body(body)
+    }
+}
+
+#[get("/healthz")]
+/// Liveness probe, only checks that the server responds (see [`readyz_request`] for readiness)
+pub async fn healthz_request(data: web::Data<AppData>) -> impl Responder {
+    let report = get_health_report(&data, false).await;
+    probe_response(true, report)
+}
+
+#[get("/readyz")]
+/// Readiness probe, checks the database client, the cache connection and the schema version
+pub async fn readyz_request(data: web::Data<AppData>) -> impl Responder {
+    let report = get_health_report(&data, true).await;
+    let healthy = report.ready
+        && report.database
+        && report.cache
+        && (report.schema_version == report.expected_schema_version);
+
+    probe_response(healthy, report)
+}
@@ -1,2 +1,12 @@
+pub mod activitypub;
+pub mod attachments;
 pub mod auth;
+pub mod feed;
+pub mod health;
 pub mod reports;
+pub mod retention;
+pub mod rules;
+pub mod sla;
+pub mod snapshots;
+pub mod stats;
+pub mod subjects;
@@ -1,5 +1,8 @@
-use crate::db::AppData;
-use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use crate::db::{AppData, BulkAction, CreatedReport, TrackedReport};
+use crate::notify::{self, ReportEvent};
+use crate::rules::RuleEvent;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
 use serde::{Deserialize, Serialize};
 
 use crate::pages::base;
@@ -12,52 +15,499 @@ pub struct PCreateReport {
     pub address: String,
     #[serde(default)]
     pub as_user: String,
+    /// The values of the custom fields of the reported site (see [`crate::fields`])
+    #[serde(default)]
+    pub fields: std::collections::BTreeMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct PEditStatus {
     pub status: crate::db::ReportStatus,
+    /// The public resolution message shown to the reporter (unchanged if missing)
+    #[serde(default)]
+    pub resolution: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PBulk {
+    /// The IDs of the reports to change
+    #[serde(default)]
+    pub ids: Vec<String>,
+    /// Change every report matching this filter instead of `ids`
+    #[serde(default)]
+    pub filter: Option<crate::db::ReportFilter>,
+    pub action: crate::db::BulkAction,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PErasure {
+    /// The person whose reports are redacted (as their author or reporter)
+    pub author: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PAddTag {
+    pub tag: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PEscalate {
+    #[serde(default)]
+    pub reason: String,
 }
 
 // ...
+/// Why [`file_report`] refused a report
+pub enum FileError {
+    /// The report is invalid (or its site isn't accepted)
+    Invalid(String),
+    /// The reporter reached their rate limit
+    RateLimited(String),
+}
+
+impl FileError {
+    /// The message shown to the reporter
+    pub fn message(&self) -> &str {
+        match self {
+            FileError::Invalid(m) => m,
+            FileError::RateLimited(m) => m,
+        }
+    }
+}
+
+/// File a new report: check its site and custom fields, apply the reporter's rate limit, create
+/// it and run every creation hook (spam scores, rules, snapshots and notifications)
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `ip` - the IP address the report was sent from
+/// * `author` - the author of the report (empty if anonymous)
+/// * `authenticated` - if `author` is the user of an authenticated session (and not just given by
+///   the client), only authenticated authors are used as the reporter
+/// * `props` - [`PCreateReport`]
+pub async fn file_report(
+    data: &web::Data<AppData>,
+    ip: Option<std::net::IpAddr>,
+    author: String,
+    authenticated: bool,
+    props: &PCreateReport,
+) -> Result<crate::db::Report, FileError> {
+    // check site and custom fields
+    let site = crate::canonical::find_site(&data.config.sites, &props.address);
+    let fields = crate::canonical::check_site(&data.config.sites, &props.address)
+        .and_then(|_| crate::fields::validate(site, &props.report_type, &props.fields))
+        .map_err(FileError::Invalid)?;
+
+    // check rate limit
+    let reporter =
+        crate::reputation::reporter(data, Some(author.as_str()).filter(|_| authenticated), ip)
+            .await;
+    let tier = crate::reputation::admit(data, &reporter)
+        .await
+        .map_err(FileError::RateLimited)?;
+
+    // create report
+    let res = data
+        .db
+        .create_report(&mut crate::db::Report {
+            id: String::new(),
+            report_type: props.report_type.clone(),
+            status: crate::db::ReportStatus::Active,
+            author,
+            content: props.content.clone(),
+            address: props.address.clone(),
+            reporter,
+            fields,
+            ..Default::default()
+        })
+        .await;
+
+    let mut report = match res.payload {
+        Some(r) => r,
+        None => return Err(FileError::Invalid(res.message)),
+    };
+
+    run_creation_hooks(data, &mut report, tier, ip).await;
+    Ok(report)
+}
+
+/// Run every creation hook of a new report: spam scores, reputation, subjects, rules, snapshots
+/// and notifications
+///
+/// The given report is updated with the changes made.
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `report` - the created [`crate::db::Report`]
+/// * `tier` - the [`crate::reputation::TrustTier`] of its reporter
+/// * `ip` - the IP address the report was sent from
+pub async fn run_creation_hooks(
+    data: &web::Data<AppData>,
+    report: &mut crate::db::Report,
+    tier: crate::reputation::TrustTier,
+    ip: Option<std::net::IpAddr>,
+) {
+    crate::spam::check(data, report).await;
+    crate::bayes::check(data, report).await;
+    crate::reputation::check(data, report, tier).await;
+    crate::subjects::record(data, report).await;
+    crate::rules::apply(data, report, RuleEvent::Created, ip).await;
+    crate::snapshots::capture(data, report);
+    notify::dispatch(data, ReportEvent::Created(report.to_owned()));
+}
+
 #[post("/api/v1/reports")]
-/// Create a new report
+/// Create a new report, returning its [`CreatedReport`]
 pub async fn create_request(
     req: HttpRequest,
     body: web::Json<PCreateReport>,
     data: web::Data<AppData>,
 ) -> impl Responder {
     // ...
+    let ip = crate::rules::request_ip(&req, &data.config.trusted_proxies);
     let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
 
-    // create report
+    let (author, authenticated) = if token_user.is_some() {
+        let username = token_user.unwrap().payload.unwrap().user.username;
+
+        match username.is_empty() {
+            true => (body.as_user.clone(), false), // use the body username
+            false => (username, true),             // otherwise, use the username from the token
+        }
+    } else {
+        (body.as_user.clone(), false)
+    };
+
+    let res = match file_report(&data, ip, author, authenticated, &body).await {
+        Ok(report) => DefaultReturn {
+            success: true,
+            message: String::from("Content reported."),
+            payload: Some(CreatedReport::from(report)),
+        },
+        Err(FileError::RateLimited(e)) => {
+            return HttpResponse::TooManyRequests()
+                .append_header(("Content-Type", "application/json"))
+                .append_header(("Set-Cookie", set_cookie))
+                .body(
+                    serde_json::to_string(&DefaultReturn::<Option<String>> {
+                        success: false,
+                        message: e,
+                        payload: None,
+                    })
+                    .unwrap(),
+                );
+        }
+        Err(FileError::Invalid(e)) => DefaultReturn {
+            success: false,
+            message: e,
+            payload: None,
+        },
+    };
+
+    // return
+    return HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap());
+}
+
+#[get("/api/v1/track/{token}")]
+/// Get the public view of a report by its tracking token
+pub async fn track_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let token = req.match_info().get("token").unwrap().to_string();
+    let res = data.db.get_report_by_tracking(token).await;
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .body(
+            serde_json::to_string(&DefaultReturn::<Option<TrackedReport>> {
+                success: res.success,
+                message: res.message,
+                payload: res.payload.map(TrackedReport::from),
+            })
+            .unwrap(),
+        )
+}
+
+#[post("/api/v1/reports/bulk")]
+/// Apply an action to many reports at once
+pub async fn bulk_request(
+    req: HttpRequest,
+    body: web::Json<PBulk>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    // edit reports
+    let body = body.into_inner();
     let res = data
         .db
-        .create_report(&mut crate::db::Report {
-            id: String::new(),
-            report_type: body.report_type.clone(),
-            status: crate::db::ReportStatus::Active,
-            author: if token_user.is_some() {
-                let username = token_user.unwrap().payload.unwrap().user.username;
+        .bulk_edit_reports(
+            body.ids,
+            body.filter,
+            body.action.clone(),
+            token_user.and_then(|ua| ua.payload),
+        )
+        .await;
+
+    // status changes run the same hooks as editing a single report
+    if let (BulkAction::Status(_), Some(changed)) = (&body.action, &res.payload) {
+        for id in changed {
+            if let Some(mut report) = data.db.get_report_by_id(id.to_string()).await.payload {
+                crate::rules::apply(&data, &mut report, RuleEvent::StatusChanged, None).await;
+                notify::dispatch(&data, ReportEvent::StatusChanged(report));
+            }
+        }
+    }
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&count_changed(res)).unwrap())
+}
+
+/// Replace the changed report IDs of a bulk action result with their number
+fn count_changed(res: DefaultReturn<Option<Vec<String>>>) -> DefaultReturn<Option<usize>> {
+    DefaultReturn {
+        success: res.success,
+        message: res.message,
+        payload: res.payload.map(|ids| ids.len()),
+    }
+}
+
+/// Apply a [`BulkAction`] to a single report
+async fn single_action(
+    data: &web::Data<AppData>,
+    id: String,
+    action: BulkAction,
+    edit_as: Option<dorsal::db::special::auth_db::FullUser<String>>,
+) -> DefaultReturn<Option<String>> {
+    let res = data
+        .db
+        .bulk_edit_reports(vec![id.clone()], None, action, edit_as)
+        .await;
+
+    match res.payload {
+        Some(ids) if ids.is_empty() => DefaultReturn {
+            success: false,
+            message: String::from("Report does not exist"),
+            payload: None,
+        },
+        Some(_) => DefaultReturn {
+            success: true,
+            message: String::from("Report updated!"),
+            payload: Some(id),
+        },
+        None => DefaultReturn {
+            success: false,
+            message: res.message,
+            payload: None,
+        },
+    }
+}
+
+#[delete("/api/v1/reports/{id}")]
+/// Permanently delete a report
+pub async fn delete_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+    let res = single_action(
+        &data,
+        id,
+        BulkAction::Delete,
+        token_user.and_then(|ua| ua.payload),
+    )
+    .await;
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[post("/api/v1/reports/{id}/redact")]
+/// Remove the author and content of a report
+pub async fn redact_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+    let res = single_action(
+        &data,
+        id,
+        BulkAction::Redact,
+        token_user.and_then(|ua| ua.payload),
+    )
+    .await;
 
-                match username.is_empty() {
-                    true => body.as_user.clone(), // use the body username
-                    false => username,            // otherwise, use the username from the token
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[post("/api/v1/erasure")]
+/// Redact every report created or filed by a person and remove their reputation (for data
+/// erasure requests)
+pub async fn erasure_request(
+    req: HttpRequest,
+    body: web::Json<PErasure>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let editor = match token_user.and_then(|ua| ua.payload) {
+        Some(ua) if ua.level.permissions.contains(&"StaffDashboard".to_string()) => {
+            Some(ua.user.username)
+        }
+        _ => None,
+    };
+
+    let res: DefaultReturn<Option<usize>> = match editor {
+        None => DefaultReturn {
+            success: false,
+            message: String::from("You are not allowed to do this."),
+            payload: None,
+        },
+        Some(_) if body.author.trim().is_empty() => DefaultReturn {
+            success: false,
+            message: String::from("Author is invalid"),
+            payload: None,
+        },
+        Some(editor) => count_changed(data.db.erase_person(body.author.trim(), &editor).await),
+    };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[get("/api/v1/tags")]
+/// Get every predefined and used tag
+pub async fn tags_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res: DefaultReturn<Option<Vec<String>>> =
+        if !base::has_permission(&token_user, "StaffDashboard") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else {
+            DefaultReturn {
+                success: true,
+                message: String::from("Found tags"),
+                payload: Some(data.db.get_known_tags(&data.config.tags).await),
+            }
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[post("/api/v1/reports/{id}/tags")]
+/// Add a tag to a report
+pub async fn add_tag_request(
+    req: HttpRequest,
+    body: web::Json<PAddTag>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res = data
+        .db
+        .add_report_tag(id, body.tag.clone(), token_user.and_then(|ua| ua.payload))
+        .await;
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[delete("/api/v1/reports/{id}/tags/{tag}")]
+/// Remove a tag from a report
+pub async fn remove_tag_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+    let tag = req.match_info().get("tag").unwrap().to_string();
+
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res = data
+        .db
+        .remove_report_tag(id, tag, token_user.and_then(|ua| ua.payload))
+        .await;
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[post("/api/v1/reports/{id}/escalate")]
+/// Escalate a report, notifying staff
+pub async fn escalate_request(
+    req: HttpRequest,
+    body: web::Json<PEscalate>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+
+    // ...
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res: DefaultReturn<Option<String>> = if !base::has_permission(&token_user, "StaffDashboard")
+    {
+        DefaultReturn {
+            success: false,
+            message: String::from("You are not allowed to do this."),
+            payload: None,
+        }
+    } else {
+        match data.db.get_report_by_id(id.clone()).await.payload {
+            Some(report) => {
+                notify::dispatch(&data, ReportEvent::Escalated(report, body.reason.clone()));
+
+                DefaultReturn {
+                    success: true,
+                    message: String::from("Report escalated!"),
+                    payload: Some(id),
                 }
-            } else {
-                body.as_user.clone()
+            }
+            None => DefaultReturn {
+                success: false,
+                message: String::from("Report does not exist"),
+                payload: None,
             },
-            content: body.content.clone(),
-            address: body.address.clone(),
-            timestamp: dorsal::utility::unix_epoch_timestamp(),
-        })
-        .await;
+        }
+    };
 
     // return
-    return HttpResponse::Ok()
+    HttpResponse::Ok()
         .append_header(("Content-Type", "application/json"))
         .append_header(("Set-Cookie", set_cookie))
-        .body(serde_json::to_string(&res).unwrap());
+        .body(serde_json::to_string(&res).unwrap())
 }
 
 #[post("/api/v1/reports/{id:.*}")]
@@ -78,6 +528,7 @@ pub async fn edit_status_request(
         .edit_report_status_by_id(
             id.to_string(),
             body.status.to_owned(),
+            body.resolution.to_owned(),
             if token_user.is_some() {
                 token_user.unwrap().payload
             } else {
@@ -86,6 +537,13 @@ pub async fn edit_status_request(
         )
         .await;
 
+    if res.success {
+        if let Some(mut report) = data.db.get_report_by_id(id.to_string()).await.payload {
+            crate::rules::apply(&data, &mut report, RuleEvent::StatusChanged, None).await;
+            notify::dispatch(&data, ReportEvent::StatusChanged(report));
+        }
+    }
+
     // return
     return HttpResponse::Ok()
         .append_header(("Content-Type", "application/json"))
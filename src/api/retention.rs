@@ -0,0 +1,40 @@
+use crate::db::AppData;
+use crate::retention::{self, RetentionMatch};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+#[get("/api/v1/retention/preview")]
+/// Get every report the retention rules would currently affect (without changing anything)
+pub async fn preview_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let res: DefaultReturn<Option<Vec<RetentionMatch>>> =
+        if !base::has_permission(&token_user, "StaffDashboard") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else {
+            match retention::find_matches(&data.db, &data.config.retention.rules).await {
+                Ok(matches) => DefaultReturn {
+                    success: true,
+                    message: String::from("Found affected reports"),
+                    payload: Some(matches),
+                },
+                Err(e) => DefaultReturn {
+                    success: false,
+                    message: e,
+                    payload: None,
+                },
+            }
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
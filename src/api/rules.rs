@@ -0,0 +1,153 @@
+use crate::db::AppData;
+use crate::rules::{self, Rule, RuleMatch};
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+#[get("/api/v1/rules")]
+/// Get every triage rule
+pub async fn list_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let res: DefaultReturn<Option<Vec<Rule>>> = if !base::has_permission(&token_user, "ManageRules")
+    {
+        DefaultReturn {
+            success: false,
+            message: String::from("You are not allowed to do this."),
+            payload: None,
+        }
+    } else {
+        data.db.get_rules().await
+    };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[post("/api/v1/rules")]
+/// Create a new triage rule
+pub async fn create_request(
+    req: HttpRequest,
+    body: web::Json<Rule>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let res: DefaultReturn<Option<Rule>> = if !base::has_permission(&token_user, "ManageRules") {
+        DefaultReturn {
+            success: false,
+            message: String::from("You are not allowed to do this."),
+            payload: None,
+        }
+    } else {
+        let username = token_user.unwrap().payload.unwrap().user.username;
+        data.db.create_rule(body.into_inner(), &username).await
+    };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[post("/api/v1/rules/simulate")]
+/// Get every existing report a (possibly unsaved) rule would match, without changing anything
+pub async fn simulate_request(
+    req: HttpRequest,
+    body: web::Json<Rule>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let mut rule = body.into_inner();
+    let res: DefaultReturn<Option<Vec<RuleMatch>>> =
+        if !base::has_permission(&token_user, "ManageRules") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else if let Err(e) = rule.validate() {
+            DefaultReturn {
+                success: false,
+                message: e,
+                payload: None,
+            }
+        } else {
+            match rules::simulate(&data.db, &rule).await {
+                Ok(matches) => DefaultReturn {
+                    success: true,
+                    message: format!("Rule matches {} report(s)", matches.len()),
+                    payload: Some(matches),
+                },
+                Err(e) => DefaultReturn {
+                    success: false,
+                    message: e,
+                    payload: None,
+                },
+            }
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[post("/api/v1/rules/{id}")]
+/// Replace an existing triage rule
+pub async fn update_request(
+    req: HttpRequest,
+    body: web::Json<Rule>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let res: DefaultReturn<Option<Rule>> = if !base::has_permission(&token_user, "ManageRules") {
+        DefaultReturn {
+            success: false,
+            message: String::from("You are not allowed to do this."),
+            payload: None,
+        }
+    } else {
+        let username = token_user.unwrap().payload.unwrap().user.username;
+        data.db.update_rule(id, body.into_inner(), &username).await
+    };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[delete("/api/v1/rules/{id}")]
+/// Delete a triage rule
+pub async fn delete_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let res: DefaultReturn<Option<String>> = if !base::has_permission(&token_user, "ManageRules") {
+        DefaultReturn {
+            success: false,
+            message: String::from("You are not allowed to do this."),
+            payload: None,
+        }
+    } else {
+        let username = token_user.unwrap().payload.unwrap().user.username;
+        data.db.delete_rule(id, &username).await
+    };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
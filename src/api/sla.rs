@@ -0,0 +1,41 @@
+use crate::db::AppData;
+use crate::sla::{self, SlaReport};
+use crate::stats::Period;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+#[get("/api/v1/sla")]
+/// Get the SLA compliance of every report type for a period (defaults to the current quarter)
+pub async fn sla_request(
+    req: HttpRequest,
+    data: web::Data<AppData>,
+    info: web::Query<super::stats::StatsQueryProps>,
+) -> impl Responder {
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let res: DefaultReturn<Option<SlaReport>> =
+        if !base::has_permission(&token_user, "StaffDashboard") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else {
+            match Period::from_query(&info.period) {
+                Some(period) => sla::get_report(&data.db, &data.config.sla, period).await,
+                None => DefaultReturn {
+                    success: false,
+                    message: String::from("Period is invalid"),
+                    payload: None,
+                },
+            }
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
@@ -0,0 +1,57 @@
+use crate::db::{AppData, Snapshot};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+/// The policy snapshots are served with, nothing they reference is loaded and no scripts run
+pub const SNAPSHOT_CSP: &str =
+    "sandbox; default-src 'none'; style-src 'unsafe-inline'; img-src data:; frame-ancestors 'self'";
+
+#[get("/api/v1/reports/{id}/snapshot")]
+/// Get the snapshot of a report's address
+pub async fn snapshot_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    let res: DefaultReturn<Option<Snapshot>> =
+        if !base::has_permission(&token_user, "StaffDashboard") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else {
+            data.db.get_snapshot_by_report(id).await
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
+
+#[get("/api/v1/reports/{id}/snapshot/frame")]
+/// Get the stored HTML of a report's snapshot (shown in a sandboxed frame)
+pub async fn frame_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let id = req.match_info().get("id").unwrap().to_string();
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    if !base::has_permission(&token_user, "StaffDashboard") {
+        return HttpResponse::Unauthorized().body("You are not allowed to do this.");
+    }
+
+    let snapshot = match data.db.get_snapshot_by_report(id).await.payload {
+        Some(s) if !s.html.is_empty() => s,
+        _ => return HttpResponse::NotFound().body("Snapshot does not exist"),
+    };
+
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", "text/html; charset=utf-8"))
+        .append_header(("Content-Security-Policy", SNAPSHOT_CSP))
+        .append_header(("X-Content-Type-Options", "nosniff"))
+        .append_header(("Referrer-Policy", "no-referrer"))
+        .body(snapshot.html)
+}
@@ -0,0 +1,48 @@
+use crate::db::AppData;
+use crate::stats::{self, Period, ReportStats};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+#[derive(Default, PartialEq, serde::Deserialize)]
+pub struct StatsQueryProps {
+    /// The period to calculate statistics for (ex: `2024`, `2024-Q2` or `2024-05`)
+    pub period: Option<String>,
+}
+
+#[get("/api/v1/stats")]
+/// Get aggregated report statistics for a period (defaults to the current quarter)
+///
+/// Public if `stats.public` is enabled, otherwise only available to staff.
+pub async fn stats_request(
+    req: HttpRequest,
+    data: web::Data<AppData>,
+    info: web::Query<StatsQueryProps>,
+) -> impl Responder {
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let res: DefaultReturn<Option<ReportStats>> =
+        if !data.config.stats.public && !base::has_permission(&token_user, "StaffDashboard") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else {
+            match Period::from_query(&info.period) {
+                Some(period) => stats::get_stats(&data.db, period).await,
+                None => DefaultReturn {
+                    success: false,
+                    message: String::from("Period is invalid"),
+                    payload: None,
+                },
+            }
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
@@ -0,0 +1,30 @@
+use crate::db::AppData;
+use crate::subjects::{self, Subject};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use dorsal::DefaultReturn;
+
+use crate::pages::base;
+
+#[get("/api/v1/subjects/{key:.*}")]
+/// Get every report about a subject (ex: `host:example.com`) and their outcomes
+pub async fn subject_request(req: HttpRequest, data: web::Data<AppData>) -> impl Responder {
+    let key = req.match_info().get("key").unwrap().to_string();
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let res: DefaultReturn<Option<Subject>> =
+        if !base::has_permission(&token_user, "StaffDashboard") {
+            DefaultReturn {
+                success: false,
+                message: String::from("You are not allowed to do this."),
+                payload: None,
+            }
+        } else {
+            subjects::get(&data.db, &key).await
+        };
+
+    // return
+    HttpResponse::Ok()
+        .append_header(("Content-Type", "application/json"))
+        .append_header(("Set-Cookie", set_cookie))
+        .body(serde_json::to_string(&res).unwrap())
+}
@@ -0,0 +1,276 @@
+//! Files attached to reports as evidence
+use actix_web::web;
+use dorsal::DefaultReturn;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::AttachmentConfig;
+use crate::db::{AppData, Attachment, Database, Report};
+
+/// Somewhere attachment files can be stored
+///
+/// Methods are blocking, call them through [`web::block`].
+pub trait AttachmentStore: Send + Sync {
+    /// Store the file of an attachment
+    fn put(&self, id: &str, data: &[u8]) -> Result<(), String>;
+    /// Get the file of an attachment
+    fn get(&self, id: &str) -> Result<Vec<u8>, String>;
+    /// Remove the file of an attachment
+    fn remove(&self, id: &str) -> Result<(), String>;
+    /// Get the ID of every stored file
+    fn list(&self) -> Result<Vec<String>, String>;
+}
+
+/// An [`AttachmentStore`] keeping files in a local directory
+pub struct LocalStore {
+    pub root: PathBuf,
+}
+
+impl LocalStore {
+    /// Create a new [`LocalStore`] (its directory is created when the first file is stored)
+    pub fn new(path: &str) -> LocalStore {
+        LocalStore {
+            root: PathBuf::from(path),
+        }
+    }
+
+    /// Get the path of a file, IDs are only ever generated by us but are checked anyway
+    fn path(&self, id: &str) -> Result<PathBuf, String> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(String::from("Attachment ID is invalid"));
+        }
+
+        Ok(self.root.join(id))
+    }
+}
+
+impl AttachmentStore for LocalStore {
+    fn put(&self, id: &str, data: &[u8]) -> Result<(), String> {
+        std::fs::create_dir_all(&self.root).map_err(|e| e.to_string())?;
+        std::fs::write(self.path(id)?, data).map_err(|e| e.to_string())
+    }
+
+    fn get(&self, id: &str) -> Result<Vec<u8>, String> {
+        std::fs::read(self.path(id)?).map_err(|e| e.to_string())
+    }
+
+    fn remove(&self, id: &str) -> Result<(), String> {
+        std::fs::remove_file(self.path(id)?).map_err(|e| e.to_string())
+    }
+
+    fn list(&self) -> Result<Vec<String>, String> {
+        let entries = match std::fs::read_dir(&self.root) {
+            Ok(e) => e,
+            // nothing has been stored yet
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+
+        Ok(entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .collect())
+    }
+}
+
+/// Create the [`AttachmentStore`] described by the config
+pub fn store(config: &AttachmentConfig) -> Arc<dyn AttachmentStore> {
+    Arc::new(LocalStore::new(&config.path))
+}
+
+/// Detect the MIME type of a file from its content
+///
+/// Only types which are safe to show staff are detected, anything else is `None`.
+pub fn detect_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if (data.len() >= 12) && data.starts_with(b"RIFF") && (&data[8..12] == b"WEBP") {
+        Some("image/webp")
+    } else if data.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if !data.contains(&0) && std::str::from_utf8(data).is_ok() {
+        Some("text/plain")
+    } else {
+        None
+    }
+}
+
+/// If a MIME type can be shown in the browser instead of being downloaded
+pub fn is_inline(mime: &str) -> bool {
+    mime.starts_with("image/")
+}
+
+/// Make a file name safe to store and send back in a `Content-Disposition` header
+pub fn safe_file_name(name: &str) -> String {
+    let name: String = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() | ['.', '-', '_'].contains(c))
+        .take(100)
+        .collect();
+
+    let name = name.trim_start_matches('.');
+
+    if name.is_empty() {
+        String::from("attachment")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Attach a file to a [`Report`]
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `report` - [`Report`]
+/// * `name` - the file name given by the uploader
+/// * `body` - the file (already limited to `attachments.max_size`)
+/// * `author` - the username of the staff member uploading the file (empty for the reporter)
+pub async fn upload(
+    data: &web::Data<AppData>,
+    report: &Report,
+    name: &str,
+    body: web::Bytes,
+    author: &str,
+) -> DefaultReturn<Option<Attachment>> {
+    let config = &data.config.attachments;
+
+    if !config.enabled {
+        return DefaultReturn {
+            success: false,
+            message: String::from("Attachments are not enabled"),
+            payload: None,
+        };
+    }
+
+    if body.is_empty() {
+        return DefaultReturn {
+            success: false,
+            message: String::from("File is empty"),
+            payload: None,
+        };
+    }
+
+    let mime = match detect_mime(&body) {
+        Some(m) if config.mime_types.iter().any(|t| t == m) => m,
+        _ => {
+            return DefaultReturn {
+                success: false,
+                message: String::from("File type is not allowed"),
+                payload: None,
+            }
+        }
+    };
+
+    match data
+        .db
+        .get_attachments_by_report(report.id.clone())
+        .await
+        .payload
+    {
+        Some(a) if a.len() < config.max_per_report => (),
+        Some(_) => {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Report has too many attachments"),
+                payload: None,
+            }
+        }
+        None => {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Failed to check attachments"),
+                payload: None,
+            }
+        }
+    }
+
+    let attachment = Attachment {
+        id: dorsal::utility::random_id(),
+        report: report.id.clone(),
+        name: safe_file_name(name),
+        mime: mime.to_string(),
+        size: body.len(),
+        author: author.to_string(),
+        timestamp: dorsal::utility::unix_epoch_timestamp(),
+    };
+
+    // the row is stored first so the cleanup job never removes a file which is being uploaded
+    let res = data.db.create_attachment(&attachment).await;
+
+    if !res.success {
+        return DefaultReturn {
+            success: false,
+            message: res.message,
+            payload: None,
+        };
+    }
+
+    let store = data.attachments.clone();
+    let id = attachment.id.clone();
+    if let Err(e) = web::block(move || store.put(&id, &body))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+    {
+        // the row would otherwise point at a missing file and count toward the limit
+        if let Err(e) = data.db.delete_attachment(attachment.id.clone()).await {
+            eprintln!("Failed to remove attachment {}: {e}", attachment.id);
+        }
+
+        return DefaultReturn {
+            success: false,
+            message: format!("Failed to store file: {e}"),
+            payload: None,
+        };
+    }
+
+    let editor = if author.is_empty() {
+        "reporter"
+    } else {
+        author
+    };
+    data.db
+        .audit(&report.id, editor, format!("attach:{}", attachment.name))
+        .await;
+
+    DefaultReturn {
+        success: true,
+        message: String::from("File attached!"),
+        payload: Some(attachment),
+    }
+}
+
+/// Remove every stored file which no longer has an [`Attachment`], returns the number removed
+///
+/// Attachment rows are removed with their reports (see [`crate::db::BulkAction::Delete`]).
+pub async fn cleanup(db: &Database, store: Arc<dyn AttachmentStore>) -> Result<usize, String> {
+    // files are listed before rows are fetched, a listed file's row was stored before it was
+    let list_store = store.clone();
+    let stored = web::block(move || list_store.list())
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let known = db.get_attachment_ids().await?;
+
+    web::block(move || {
+        let mut removed: usize = 0;
+
+        for id in stored.iter().filter(|id| !known.contains(*id)) {
+            match store.remove(id) {
+                Ok(_) => removed += 1,
+                Err(e) => eprintln!("Failed to remove attachment {id}: {e}"),
+            }
+        }
+
+        removed
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,499 @@
+//! A naive Bayes spam model trained from staff decisions
+//!
+//! Reports staff marked as spam are spam examples, reports staff resolved are non-spam examples.
+//! Decisions made automatically (by rules, spam scores, this model or jobs) are never learned
+//! from. The model is stored in the meta table and runs entirely in-process.
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use crate::config::BayesConfig;
+use crate::db::{AppData, BulkAction, Database, Report, ReportStatus, REDACTED_CONTENT};
+
+/// The name the model is stored as in the meta table
+pub const MODEL_NAME: &str = "bayes_model";
+
+/// The name stored in the audit entries of reports marked as spam by the model
+pub const BAYES_EDITOR: &str = "system:bayes";
+
+/// The most tokens kept in the model (the most frequent are kept)
+pub const MAX_TOKENS: usize = 10_000;
+
+/// How long a cached model is used before it is loaded again, so models trained by other
+/// instances are picked up (milliseconds)
+pub const MODEL_REFRESH: u128 = 5 * 60 * 1000;
+
+/// A trained model
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct BayesModel {
+    /// The number of spam examples
+    pub spam: u64,
+    /// The number of non-spam examples
+    pub ham: u64,
+    /// The number of spam examples each token appeared in
+    pub spam_tokens: HashMap<String, u64>,
+    /// The number of non-spam examples each token appeared in
+    pub ham_tokens: HashMap<String, u64>,
+    /// When the model was trained
+    pub trained: u128,
+}
+
+/// How well a model does on decisions it wasn't trained on
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Evaluation {
+    /// The number of decisions trained on
+    pub trained: usize,
+    /// The number of decisions tested
+    pub tested: usize,
+    /// The threshold (spam probability as a percentage) reports were classified with
+    pub threshold: u8,
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub true_negatives: usize,
+    pub false_negatives: usize,
+}
+
+impl Evaluation {
+    /// The share of reports classified as spam which were spam
+    pub fn precision(&self) -> f64 {
+        let positives = self.true_positives + self.false_positives;
+
+        if positives == 0 {
+            return 0.0;
+        }
+
+        self.true_positives as f64 / positives as f64
+    }
+
+    /// The share of spam reports which were classified as spam
+    pub fn recall(&self) -> f64 {
+        let spam = self.true_positives + self.false_negatives;
+
+        if spam == 0 {
+            return 0.0;
+        }
+
+        self.true_positives as f64 / spam as f64
+    }
+}
+
+impl std::fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Trained on {} decision(s), tested on {} at {}%",
+            self.trained, self.tested, self.threshold
+        )?;
+        writeln!(
+            f,
+            "True positives: {}, false positives: {}, true negatives: {}, false negatives: {}",
+            self.true_positives, self.false_positives, self.true_negatives, self.false_negatives
+        )?;
+        write!(
+            f,
+            "Precision: {:.1}%, recall: {:.1}%",
+            self.precision() * 100.0,
+            self.recall() * 100.0
+        )
+    }
+}
+
+/// Get the tokens of a [`Report`] (the words of its content and the host of its address)
+pub fn tokens(report: &Report) -> HashSet<String> {
+    let mut out: HashSet<String> = crate::spam::normalize_words(&report.content)
+        .split_whitespace()
+        .filter(|w| (w.chars().count() >= 2) && (w.chars().count() <= 30))
+        .map(|w| w.to_string())
+        .collect();
+
+    if let Some(host) = url::Url::parse(&report.address)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+    {
+        out.insert(format!("host:{host}"));
+    }
+
+    out
+}
+
+impl BayesModel {
+    /// Train a model from labelled examples
+    ///
+    /// # Arguments:
+    /// * `examples` - the tokens of each example and if it is spam
+    pub fn train(examples: &[(HashSet<String>, bool)]) -> BayesModel {
+        let mut model = BayesModel {
+            trained: dorsal::utility::unix_epoch_timestamp(),
+            ..Default::default()
+        };
+
+        for (tokens, spam) in examples {
+            let (count, counts) = if *spam {
+                (&mut model.spam, &mut model.spam_tokens)
+            } else {
+                (&mut model.ham, &mut model.ham_tokens)
+            };
+
+            *count += 1;
+            for token in tokens {
+                *counts.entry(token.clone()).or_insert(0) += 1;
+            }
+        }
+
+        model.prune(MAX_TOKENS);
+        model
+    }
+
+    /// Keep only the `max` most frequent tokens
+    fn prune(&mut self, max: usize) {
+        let mut totals: Vec<(String, u64)> = self
+            .spam_tokens
+            .keys()
+            .chain(self.ham_tokens.keys())
+            .collect::<HashSet<&String>>()
+            .into_iter()
+            .map(|t| {
+                (
+                    t.clone(),
+                    self.spam_tokens.get(t).unwrap_or(&0) + self.ham_tokens.get(t).unwrap_or(&0),
+                )
+            })
+            .collect();
+
+        if totals.len() <= max {
+            return;
+        }
+
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let keep: HashSet<String> = totals.into_iter().take(max).map(|(t, _)| t).collect();
+
+        self.spam_tokens.retain(|t, _| keep.contains(t));
+        self.ham_tokens.retain(|t, _| keep.contains(t));
+    }
+
+    /// Get the spam probability (as a percentage) of the given tokens
+    ///
+    /// Returns `None` if the model has fewer than `min_documents` examples of either kind.
+    pub fn score(&self, tokens: &HashSet<String>, min_documents: u64) -> Option<u8> {
+        if (self.spam < min_documents.max(1)) || (self.ham < min_documents.max(1)) {
+            return None;
+        }
+
+        let total = (self.spam + self.ham) as f64;
+        let mut spam = (self.spam as f64 / total).ln();
+        let mut ham = (self.ham as f64 / total).ln();
+
+        // unknown tokens say nothing about either kind
+        for token in tokens {
+            let in_spam = *self.spam_tokens.get(token).unwrap_or(&0);
+            let in_ham = *self.ham_tokens.get(token).unwrap_or(&0);
+
+            if (in_spam == 0) && (in_ham == 0) {
+                continue;
+            }
+
+            // laplace smoothing
+            spam += ((in_spam + 1) as f64 / (self.spam + 2) as f64).ln();
+            ham += ((in_ham + 1) as f64 / (self.ham + 2) as f64).ln();
+        }
+
+        let probability = 1.0 / (1.0 + (ham - spam).exp());
+        Some((probability * 100.0).round() as u8)
+    }
+}
+
+/// Get every report staff made a decision about and if it was spam (oldest first)
+pub async fn decisions(db: &Database) -> Result<Vec<(Report, bool)>, String> {
+    let editors = db.get_status_editors().await?;
+
+    let res = db
+        .get_every_report_by_filter(&crate::db::ReportFilter::default())
+        .await;
+
+    let mut reports = match res.payload {
+        Some(r) => r,
+        None => return Err(res.message),
+    };
+
+    reports.sort_by_key(|r| r.timestamp);
+
+    Ok(reports
+        .into_iter()
+        .filter(|r| r.content != REDACTED_CONTENT)
+        .filter(|r| match editors.get(&r.id) {
+            Some(editor) => !editor.starts_with("system:"),
+            None => false,
+        })
+        .filter_map(|r| match r.status {
+            ReportStatus::Spam => Some((r, true)),
+            ReportStatus::Archived => Some((r, false)),
+            ReportStatus::Active => None,
+        })
+        .collect())
+}
+
+/// Get the stored model (`None` if it was never trained)
+pub async fn load(db: &Database) -> Option<BayesModel> {
+    serde_json::from_str(&db.get_meta(MODEL_NAME).await?).ok()
+}
+
+/// A model loaded into a [`ModelCache`]
+struct CachedModel {
+    /// `None` if the model was never trained
+    model: Option<Arc<BayesModel>>,
+    /// When the model was loaded
+    loaded: u128,
+}
+
+/// The parsed model shared by every worker, so it isn't loaded again for every report
+#[derive(Clone, Default)]
+pub struct ModelCache(Arc<RwLock<Option<CachedModel>>>);
+
+impl ModelCache {
+    /// Get the model, loading it if it isn't cached or was cached over [`MODEL_REFRESH`] ago
+    pub async fn get(&self, db: &Database) -> Option<Arc<BayesModel>> {
+        let now = dorsal::utility::unix_epoch_timestamp();
+
+        if let Some(cached) = self.0.read().unwrap().as_ref() {
+            if now.saturating_sub(cached.loaded) < MODEL_REFRESH {
+                return cached.model.clone();
+            }
+        }
+
+        let model = load(db).await.map(Arc::new);
+        *self.0.write().unwrap() = Some(CachedModel {
+            model: model.clone(),
+            loaded: now,
+        });
+
+        model
+    }
+
+    /// Replace the cached model (ex: after [`retrain`])
+    pub fn set(&self, model: BayesModel) {
+        *self.0.write().unwrap() = Some(CachedModel {
+            model: Some(Arc::new(model)),
+            loaded: dorsal::utility::unix_epoch_timestamp(),
+        });
+    }
+}
+
+/// Train a new model from every staff decision and store it
+pub async fn retrain(db: &Database) -> Result<BayesModel, String> {
+    let examples: Vec<(HashSet<String>, bool)> = decisions(db)
+        .await?
+        .iter()
+        .map(|(r, spam)| (tokens(r), *spam))
+        .collect();
+
+    let model = BayesModel::train(&examples);
+    db.set_meta(MODEL_NAME, &serde_json::to_string(&model).unwrap())
+        .await?;
+
+    Ok(model)
+}
+
+/// Evaluate the model by training on most decisions and testing it on every fifth decision
+///
+/// # Arguments:
+/// * `db` - [`Database`]
+/// * `config` - [`BayesConfig`] (reports are classified at `threshold`, or 50% if it's `0`)
+pub async fn evaluate(db: &Database, config: &BayesConfig) -> Result<Evaluation, String> {
+    let examples: Vec<(HashSet<String>, bool)> = decisions(db)
+        .await?
+        .iter()
+        .map(|(r, spam)| (tokens(r), *spam))
+        .collect();
+
+    evaluate_examples(examples, config)
+}
+
+/// Evaluate a model trained on most of the given examples on every fifth example
+fn evaluate_examples(
+    examples: Vec<(HashSet<String>, bool)>,
+    config: &BayesConfig,
+) -> Result<Evaluation, String> {
+    let (test, train): (Vec<_>, Vec<_>) = examples
+        .into_iter()
+        .enumerate()
+        .partition(|(i, _)| i % 5 == 4);
+
+    let train: Vec<(HashSet<String>, bool)> = train.into_iter().map(|(_, e)| e).collect();
+    let model = BayesModel::train(&train);
+
+    let threshold = if config.threshold == 0 {
+        50
+    } else {
+        config.threshold
+    };
+
+    let mut evaluation = Evaluation {
+        trained: train.len(),
+        tested: test.len(),
+        threshold,
+        ..Default::default()
+    };
+
+    for (_, (tokens, spam)) in test {
+        let predicted = match model.score(&tokens, config.min_documents) {
+            Some(score) => score >= threshold,
+            None => return Err(String::from("Not enough decisions to train a model")),
+        };
+
+        match (predicted, spam) {
+            (true, true) => evaluation.true_positives += 1,
+            (true, false) => evaluation.false_positives += 1,
+            (false, false) => evaluation.true_negatives += 1,
+            (false, true) => evaluation.false_negatives += 1,
+        }
+    }
+
+    Ok(evaluation)
+}
+
+/// Score a new [`Report`] and mark it as spam if its probability is at least the threshold (if
+/// enabled and a model has been trained)
+///
+/// The given report is updated with the changes made.
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `report` - [`Report`]
+pub async fn check(data: &web::Data<AppData>, report: &mut Report) {
+    let config = &data.config.bayes;
+
+    if !config.enabled {
+        return;
+    }
+
+    let score = match data
+        .bayes
+        .get(&data.db)
+        .await
+        .and_then(|m| m.score(&tokens(report), config.min_documents))
+    {
+        Some(s) => s,
+        None => return,
+    };
+
+    let res = data
+        .db
+        .set_bayes_score_by_id(report.id.clone(), score)
+        .await;
+
+    if !res.success {
+        eprintln!(
+            "Failed to store bayes score of {}: {}",
+            report.id, res.message
+        );
+        return;
+    }
+
+    report.bayes_score = Some(score);
+
+    if (config.threshold == 0)
+        || (score < config.threshold)
+        || (report.status == ReportStatus::Spam)
+    {
+        return;
+    }
+
+    let res = data
+        .db
+        .apply_bulk_action(
+            vec![report.id.clone()],
+            None,
+            BulkAction::Status(ReportStatus::Spam),
+            BAYES_EDITOR,
+        )
+        .await;
+
+    if res.success {
+        report.status = ReportStatus::Spam;
+        report.status_timestamp = dorsal::utility::unix_epoch_timestamp();
+    } else {
+        eprintln!("Failed to mark {} as spam: {}", report.id, res.message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(tokens: &[&str], spam: bool) -> (HashSet<String>, bool) {
+        (tokens.iter().map(|t| t.to_string()).collect(), spam)
+    }
+
+    #[test]
+    fn train_and_score() {
+        // nothing to score with
+        assert_eq!(
+            BayesModel::default().score(&example(&["a"], true).0, 0),
+            None
+        );
+
+        let model = BayesModel::train(&[example(&["buy", "now"], true), example(&["now"], false)]);
+        assert_eq!((model.spam, model.ham), (1, 1));
+        assert_eq!(model.spam_tokens.get("now"), Some(&1));
+        assert_eq!(model.ham_tokens.get("buy"), None);
+
+        // not enough examples of either kind
+        assert_eq!(model.score(&example(&["buy"], true).0, 2), None);
+
+        // smoothed: (1 + 1) / (1 + 2) against (0 + 1) / (1 + 2)
+        assert_eq!(model.score(&example(&["buy"], true).0, 1), Some(67));
+        assert_eq!(model.score(&example(&["now"], true).0, 1), Some(50));
+        // unknown tokens are ignored
+        assert_eq!(model.score(&example(&["hello"], true).0, 1), Some(50));
+        assert_eq!(model.score(&HashSet::new(), 1), Some(50));
+    }
+
+    #[test]
+    fn prune_tokens() {
+        let mut model = BayesModel::train(&[
+            example(&["a", "b"], true),
+            example(&["a", "c"], false),
+            example(&["a", "b"], false),
+        ]);
+        model.prune(2);
+
+        assert_eq!(model.spam_tokens.len(), 2);
+        assert_eq!(model.ham_tokens.len(), 2);
+        assert!(!model.ham_tokens.contains_key("c"));
+    }
+
+    #[test]
+    fn evaluate_precision_and_recall() {
+        let config = BayesConfig {
+            min_documents: 1,
+            ..Default::default()
+        };
+
+        // every fifth example (4 and 9) is tested, 9 is non-spam with a spam word
+        let examples: Vec<(HashSet<String>, bool)> = (0..10)
+            .map(|i| match i {
+                9 => example(&["buy"], false),
+                _ if i % 2 == 0 => example(&["buy", "now"], true),
+                _ => example(&["hello"], false),
+            })
+            .collect();
+
+        let evaluation = evaluate_examples(examples, &config).unwrap();
+        assert_eq!((evaluation.trained, evaluation.tested), (8, 2));
+        assert_eq!(evaluation.threshold, 50);
+        assert_eq!(evaluation.true_positives, 1);
+        assert_eq!(evaluation.false_positives, 1);
+        assert_eq!(evaluation.false_negatives, 0);
+        assert_eq!(evaluation.precision(), 0.5);
+        assert_eq!(evaluation.recall(), 1.0);
+
+        // nothing classified as spam
+        assert_eq!(Evaluation::default().precision(), 0.0);
+        assert_eq!(Evaluation::default().recall(), 0.0);
+
+        // no spam examples to train on
+        let examples = (0..5).map(|_| example(&["hello"], false)).collect();
+        assert!(evaluate_examples(examples, &config).is_err());
+    }
+}
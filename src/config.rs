@@ -0,0 +1,543 @@
+//! Server configuration
+//!
+//! Loaded from the JSON file given through the `--config` argument (defaults to `./config.json`).
+//! Every field is optional, missing fields use their default value.
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The public URL this server is served from (without a trailing slash)
+    pub base_url: String,
+    /// ActivityPub federation options
+    pub activitypub: ActivityPubConfig,
+    /// Staff email notification options
+    pub email: EmailConfig,
+    /// Chat webhooks which are notified about report events
+    pub webhooks: Vec<WebhookConfig>,
+    /// Report statistics options
+    pub stats: StatsConfig,
+    /// Predefined tags suggested in the dashboard (any other tag can still be used)
+    pub tags: Vec<String>,
+    /// Automatic report purging options
+    pub retention: RetentionConfig,
+    /// Handling time targets for each report type
+    pub sla: SlaConfig,
+    /// Options for snapshots of reported addresses
+    pub snapshots: SnapshotConfig,
+    /// Options for files attached to reports
+    pub attachments: AttachmentConfig,
+    /// Spam scoring of new reports
+    pub spam: SpamConfig,
+    /// The Bayesian spam model trained from staff decisions
+    pub bayes: BayesConfig,
+    /// Reporter trust scores and the rate limits and spam handling based on them
+    pub reputation: ReputationConfig,
+    /// How reported users are found in report addresses
+    pub subjects: SubjectConfig,
+    /// The sites reports can be filed about (reports about any address are accepted if empty)
+    pub sites: Vec<SiteConfig>,
+    /// Translation options
+    pub i18n: I18nConfig,
+    /// Addresses or CIDR ranges of reverse proxies whose `X-Forwarded-For` header is trusted
+    pub trusted_proxies: Vec<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsConfig {
+    /// If aggregated statistics are public (`/transparency` and `/api/v1/stats`)
+    pub public: bool,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActivityPubConfig {
+    /// If the inbox should accept `Flag` activities
+    pub enabled: bool,
+    /// Path to the PKCS#8 PEM private key of the instance actor
+    pub private_key: String,
+    /// Hosts which are not allowed to send activities to the inbox
+    pub blocked_instances: Vec<String>,
+    /// Allow fetching actors and forwarding reports to inboxes in private, loopback and
+    /// link-local ranges (only for testing)
+    pub allow_private: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum EmailMode {
+    /// Send an email for every event
+    #[default]
+    Each,
+    /// Collect events and send them together every `digest_interval` minutes
+    Digest,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SmtpSecurity {
+    /// Plain SMTP (only use this for local servers!)
+    None,
+    /// Upgrade the connection with `STARTTLS`
+    #[default]
+    StartTls,
+    /// Connect using implicit TLS
+    Tls,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// If emails should be sent
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub security: SmtpSecurity,
+    pub username: String,
+    pub password: String,
+    /// The sender address (ex: `Deducktive <deducktive@example.com>`)
+    pub from: String,
+    /// The staff addresses to notify
+    pub to: Vec<String>,
+    pub mode: EmailMode,
+    /// Minutes between digest emails
+    pub digest_interval: u64,
+    /// Text placed before every email subject
+    pub subject_prefix: String,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::from("localhost"),
+            port: 587,
+            security: SmtpSecurity::default(),
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            to: Vec::new(),
+            mode: EmailMode::default(),
+            digest_interval: 60,
+            subject_prefix: String::from("[Deducktive]"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum WebhookKind {
+    /// A Matrix room, messages are sent through the client-server API
+    Matrix,
+    /// A Discord incoming webhook
+    Discord,
+    /// A Slack-compatible incoming webhook (also Mattermost, Rocket.Chat, etc.)
+    #[default]
+    Slack,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub kind: WebhookKind,
+    /// The incoming webhook URL (the homeserver URL for [`WebhookKind::Matrix`])
+    pub url: String,
+    /// The room ID to post in ([`WebhookKind::Matrix`] only)
+    pub room: String,
+    /// The access token of the posting user ([`WebhookKind::Matrix`] only)
+    pub access_token: String,
+    /// The report types routed to this webhook (empty for all)
+    pub categories: Vec<crate::db::ReportType>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum RetentionAction {
+    /// Permanently delete reports
+    #[default]
+    Purge,
+    /// Remove the author and content of reports
+    Redact,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionRule {
+    /// The status reports must have (any status if missing)
+    pub status: Option<crate::db::ReportStatus>,
+    /// The type reports must have (any type if missing)
+    pub report_type: Option<crate::db::ReportType>,
+    pub action: RetentionAction,
+    /// Days since the report's status last changed (or it was created) before the action is applied
+    pub after_days: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// Rules applied in order, a report is only affected by the first rule it matches
+    pub rules: Vec<RetentionRule>,
+    /// Minutes between retention runs
+    pub interval: u64,
+    /// Only log what would be affected instead of changing anything
+    pub dry_run: bool,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            interval: 60,
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SlaAction {
+    /// Raise the report's priority by one
+    BumpPriority,
+    /// Notify staff (through email and webhooks) that the report was escalated
+    Notify,
+    /// Change the report's status
+    SetStatus(crate::db::ReportStatus),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SlaTarget {
+    pub report_type: crate::db::ReportType,
+    /// Hours an active report can wait before it breaches the target
+    pub hours: u64,
+    /// What is done to reports breaching the target
+    pub actions: Vec<SlaAction>,
+}
+
+impl Default for SlaTarget {
+    fn default() -> Self {
+        Self {
+            report_type: crate::db::ReportType::default(),
+            hours: 24,
+            actions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SlaConfig {
+    /// The target of each report type (types without a target are never breached)
+    pub targets: Vec<SlaTarget>,
+    /// Minutes between checks for breaching reports
+    pub interval: u64,
+}
+
+impl Default for SlaConfig {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+            interval: 5,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    /// If reported addresses are fetched and stored when reports are created
+    pub enabled: bool,
+    /// The largest response body stored (in bytes)
+    pub max_size: usize,
+    /// Seconds before a fetch is abandoned
+    pub timeout: u64,
+    /// Allow fetching addresses in private, loopback and link-local ranges (only for testing)
+    pub allow_private: bool,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_size: 512 * 1024,
+            timeout: 10,
+            allow_private: false,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttachmentConfig {
+    /// If files can be attached to reports
+    pub enabled: bool,
+    /// The directory attachments are stored in
+    pub path: String,
+    /// The largest attachment (in bytes)
+    pub max_size: usize,
+    /// The most attachments a single report can have
+    pub max_per_report: usize,
+    /// The allowed MIME types (detected from the file content, see [`crate::attachments::detect_mime`])
+    pub mime_types: Vec<String>,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: String::from("./attachments"),
+            max_size: 5 * 1024 * 1024,
+            max_per_report: 10,
+            mime_types: vec![
+                String::from("image/png"),
+                String::from("image/jpeg"),
+                String::from("image/gif"),
+                String::from("image/webp"),
+                String::from("application/pdf"),
+                String::from("text/plain"),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpamConfig {
+    /// If new reports are scored (see [`crate::spam`])
+    pub enabled: bool,
+    /// Reports scoring at least this much (out of 100) are marked as spam, `0` never marks reports
+    pub threshold: u8,
+    /// Words and phrases (matched as whole words, ignoring case) which count towards the score
+    pub blocklist: Vec<String>,
+    /// Points for each blocklisted word or phrase found
+    pub blocklist_weight: u8,
+    /// Points if a report with the same content was created within `window` minutes
+    pub repeated_weight: u8,
+    /// The largest share (`0.0` to `1.0`) of words in the content which can be links
+    pub max_link_density: f64,
+    /// Points if the content has more links than `max_link_density` allows
+    pub link_weight: u8,
+    /// The most reports by one reporter (or about one address if the reporter is unknown) within
+    /// `window` minutes
+    pub max_velocity: usize,
+    /// Points if there are more reports than `max_velocity` allows
+    pub velocity_weight: u8,
+    /// Minutes looked back for repeated content and submission velocity
+    pub window: u64,
+}
+
+impl Default for SpamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 70,
+            blocklist: Vec::new(),
+            blocklist_weight: 35,
+            repeated_weight: 40,
+            max_link_density: 0.3,
+            link_weight: 25,
+            max_velocity: 5,
+            velocity_weight: 40,
+            window: 60,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BayesConfig {
+    /// If new reports are scored by the model and it's retrained every `interval` minutes
+    pub enabled: bool,
+    /// Reports with at least this spam probability (as a percentage) are marked as spam, `0`
+    /// never marks reports
+    pub threshold: u8,
+    /// The fewest spam and non-spam decisions (each) the model needs before it scores reports
+    pub min_documents: u64,
+    /// Minutes between retraining the model
+    pub interval: u64,
+}
+
+impl Default for BayesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 0,
+            min_documents: 20,
+            interval: 24 * 60,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReputationConfig {
+    /// If reporters are tracked (anonymous reporters by a fingerprint of their IP address) and
+    /// their trust scores are recalculated every `interval` minutes
+    pub enabled: bool,
+    /// The fewest staff decisions about a reporter's reports before they can be untrusted or
+    /// trusted
+    pub min_decisions: usize,
+    /// Reporters with a lower trust score are untrusted
+    pub untrusted_below: u8,
+    /// Reporters with at least this trust score are trusted
+    pub trusted_from: u8,
+    /// The most reports untrusted reporters can file every `window` minutes (`0` is unlimited)
+    pub untrusted_limit: usize,
+    /// The most reports other reporters can file every `window` minutes (`0` is unlimited)
+    pub limit: usize,
+    /// The most reports trusted reporters can file every `window` minutes (`0` is unlimited)
+    pub trusted_limit: usize,
+    /// Minutes reports are counted over for rate limits
+    pub window: u64,
+    /// If new reports by untrusted reporters are marked as spam
+    pub mark_untrusted_as_spam: bool,
+    /// Minutes between recalculating trust scores
+    pub interval: u64,
+}
+
+impl Default for ReputationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_decisions: 5,
+            untrusted_below: 20,
+            trusted_from: 80,
+            untrusted_limit: 2,
+            limit: 10,
+            trusted_limit: 0,
+            window: 60,
+            mark_untrusted_as_spam: true,
+            interval: 60,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubjectConfig {
+    /// Patterns finding the reported user in addresses of specific sites
+    pub patterns: Vec<SubjectPattern>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubjectPattern {
+    /// The host of the site (ex: `github.com`)
+    pub host: String,
+    /// A regular expression matched against the path of addresses, the first capture group is the
+    /// username (ex: `^/@?([^/]+)/?$`)
+    pub pattern: String,
+    /// The compiled `pattern` (set by [`SubjectConfig::compile`] when the config is loaded)
+    #[serde(skip)]
+    pub regex: Option<regex::Regex>,
+}
+
+impl SubjectConfig {
+    /// Compile every pattern, failing on the first invalid pattern
+    pub fn compile(&mut self) -> Result<(), String> {
+        for pattern in self.patterns.iter_mut() {
+            pattern.regex =
+                Some(regex::Regex::new(&pattern.pattern).map_err(|e| {
+                    format!("Subject pattern for {} is invalid: {e}", pattern.host)
+                })?);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SiteConfig {
+    /// The origin of the site (ex: `https://example.com`)
+    pub origin: String,
+    /// CSS variables of the embed form (ex: `{"--color-primary": "#2e7d32"}`, see
+    /// [`crate::fields::theme_css`])
+    pub theme: std::collections::BTreeMap<String, String>,
+    /// The notice shown before the embed form (the translated default notice if empty)
+    pub notice: String,
+    /// Extra fields of the embed form (see [`crate::fields`])
+    pub fields: Vec<FieldConfig>,
+}
+
+/// The kind of input of a [`FieldConfig`]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum FieldKind {
+    /// A single line of text
+    #[default]
+    Text,
+    /// Multiple lines of text
+    LongText,
+    /// One of the field's `options`
+    Select,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldConfig {
+    /// The name the value is stored as (ex: `impersonating`)
+    pub name: String,
+    /// The label shown in the embed form (ex: `Impersonating who?`)
+    pub label: String,
+    pub kind: FieldKind,
+    /// The report types the field is asked for (every type if empty)
+    pub report_types: Vec<crate::db::ReportType>,
+    /// The allowed values of [`FieldKind::Select`] fields
+    pub options: Vec<String>,
+    /// If reports of the field's types can't be filed without it
+    pub required: bool,
+    /// The longest allowed value (in characters)
+    pub max_length: usize,
+}
+
+impl Default for FieldConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            label: String::new(),
+            kind: FieldKind::Text,
+            report_types: Vec::new(),
+            options: Vec::new(),
+            required: false,
+            max_length: 500,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct I18nConfig {
+    /// The locale used when none of the requested locales have a catalog
+    pub default_locale: String,
+    /// A directory of extra translation catalogs (`<locale>.json`), which add locales or replace
+    /// messages of the built-in catalogs (only the built-in catalogs are used if empty)
+    pub directory: String,
+}
+
+impl Default for I18nConfig {
+    fn default() -> Self {
+        Self {
+            default_locale: String::from("en"),
+            directory: String::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from the given file
+    ///
+    /// # Arguments:
+    /// * `path` - the path to the JSON config file
+    pub fn load(path: &str) -> Config {
+        match std::fs::read_to_string(path) {
+            Ok(c) => match serde_json::from_str::<Config>(&c) {
+                Ok(mut c) => {
+                    if let Err(e) = c.subjects.compile() {
+                        panic!("Failed to load config file: {e}");
+                    }
+
+                    c
+                }
+                Err(e) => panic!("Failed to parse config file: {e}"),
+            },
+            // no config file, use defaults
+            Err(_) => Config::default(),
+        }
+    }
+}
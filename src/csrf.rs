@@ -0,0 +1,167 @@
+//! Stateless CSRF tokens of the HTML report form
+//!
+//! Tokens are an HMAC-SHA256 of when they were issued, the reported address and the session of
+//! the reporter (a hash of their token cookie, empty if they aren't logged in), keyed with a
+//! secret stored in the meta table. A token only works for the same address and session, and
+//! only for [`TOKEN_LIFETIME`], so no state has to be kept for issued tokens.
+use actix_web::HttpRequest;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::dates::{HOUR, MINUTE};
+use crate::db::Database;
+
+/// The name the secret is stored as in the meta table
+pub const SECRET_NAME: &str = "csrf_secret";
+
+/// How long tokens can be used after they're issued
+pub const TOKEN_LIFETIME: u128 = 2 * HOUR;
+
+/// Get the secret tokens are signed with, creating it if it doesn't exist yet
+pub async fn secret(db: &Database) -> Result<String, String> {
+    if let Some(secret) = db.get_meta(SECRET_NAME).await {
+        return Ok(secret);
+    }
+
+    let secret = dorsal::utility::random_id();
+    db.set_meta(SECRET_NAME, &secret).await?;
+    Ok(secret)
+}
+
+/// Get the session a request belongs to (empty if it isn't logged in)
+pub fn session(req: &HttpRequest) -> String {
+    match req.cookie("__Secure-Token") {
+        Some(c) => dorsal::utility::hash(c.value().to_string()),
+        None => String::new(),
+    }
+}
+
+/// HMAC-SHA256 of a message, as hex
+fn hmac(key: &str, message: &str) -> String {
+    // keys of any length are accepted
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).unwrap();
+    mac.update(message.as_bytes());
+
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Build the token issued at the given time
+fn token(secret: &str, session: &str, address: &str, issued: u128) -> String {
+    format!(
+        "{issued}.{}",
+        hmac(secret, &format!("{issued}:{session}:{address}"))
+    )
+}
+
+/// Issue a token
+///
+/// # Arguments:
+/// * `secret` - the secret (see [`secret`])
+/// * `session` - the session of the reporter (see [`session`])
+/// * `address` - the reported address
+pub fn issue(secret: &str, session: &str, address: &str) -> String {
+    token(
+        secret,
+        session,
+        address,
+        dorsal::utility::unix_epoch_timestamp(),
+    )
+}
+
+/// Check if a token was issued for the given session and address and hasn't expired
+///
+/// # Arguments:
+/// * `secret` - the secret (see [`secret`])
+/// * `session` - the session of the reporter (see [`session`])
+/// * `address` - the reported address
+/// * `token` - the submitted token
+pub fn verify(secret: &str, session: &str, address: &str, token: &str) -> bool {
+    let (issued, signature) = match token.split_once('.') {
+        Some((i, s)) => match i.parse::<u128>() {
+            Ok(i) => (i, s),
+            Err(_) => return false,
+        },
+        None => return false,
+    };
+
+    let now = dorsal::utility::unix_epoch_timestamp();
+    if (issued > now + MINUTE) || (now.saturating_sub(issued) > TOKEN_LIFETIME) {
+        return false;
+    }
+
+    let expected = hmac(secret, &format!("{issued}:{session}:{address}"));
+
+    // constant time, so signatures can't be guessed from how long checks take
+    (expected.len() == signature.len())
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmac_vectors() {
+        // RFC 4231 test cases 1 and 2
+        assert_eq!(
+            hmac(&"\x0b".repeat(20), "Hi There"),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hmac("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn verify_tokens() {
+        let address = "https://example.com/a";
+        let now = dorsal::utility::unix_epoch_timestamp();
+
+        let valid = issue("secret", "session", address);
+        assert!(verify("secret", "session", address, &valid));
+
+        // other secrets, sessions and addresses
+        assert!(!verify("other", "session", address, &valid));
+        assert!(!verify("secret", "", address, &valid));
+        assert!(!verify(
+            "secret",
+            "session",
+            "https://example.com/b",
+            &valid
+        ));
+
+        // expired, and from the future
+        let expired = token("secret", "session", address, now - TOKEN_LIFETIME - MINUTE);
+        assert!(!verify("secret", "session", address, &expired));
+        let future = token("secret", "session", address, now + 5 * MINUTE);
+        assert!(!verify("secret", "session", address, &future));
+        let old = token("secret", "session", address, now - TOKEN_LIFETIME + MINUTE);
+        assert!(verify("secret", "session", address, &old));
+
+        // malformed
+        let (issued, signature) = valid.split_once('.').unwrap();
+        for token in [
+            "",
+            ".",
+            issued,
+            signature,
+            &format!("{issued}."),
+            &format!("x{issued}.{signature}"),
+            &format!("{issued}.{}", &signature[1..]),
+            &format!("{issued}.{signature}0"),
+            &format!("{}.{signature}", issued.parse::<u128>().unwrap() + 1),
+            &format!("{issued}.{}", "é".repeat(32)),
+        ] {
+            assert!(!verify("secret", "session", address, token), "{token}");
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Calendar helpers for unix timestamps (all dates are UTC)
+//!
+//! Based on <http://howardhinnant.github.io/date_algorithms.html>
+
+/// Milliseconds in a minute
+pub const MINUTE: u128 = 60_000;
+
+/// Milliseconds in an hour
+pub const HOUR: u128 = 3_600_000;
+
+/// Milliseconds in a day
+pub const DAY: u128 = 86_400_000;
+
+/// Get the `(year, month, day)` of the given number of days since the unix epoch
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Get the number of days since the unix epoch of the given date
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146_097 + doe - 719_468
+}
+
+/// Get the unix timestamp (in milliseconds) of the start of the given date
+pub fn timestamp_from_civil(year: i64, month: i64, day: i64) -> u128 {
+    (days_from_civil(year, month, day).max(0) as u128) * DAY
+}
+
+/// Get the `(year, month, day)` of a unix timestamp (in milliseconds)
+pub fn civil_from_timestamp(timestamp: u128) -> (i64, i64, i64) {
+    civil_from_days((timestamp / DAY) as i64)
+}
+
+/// Format a unix timestamp (in milliseconds) as an RFC 3339 date
+pub fn rfc3339(timestamp: u128) -> String {
+    let (year, month, day) = civil_from_timestamp(timestamp);
+    let rem = (timestamp % DAY) / 1000;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Format a duration (in milliseconds) in a readable form (ex: `2d 4h`)
+pub fn duration_text(ms: u128) -> String {
+    let minutes = ms / 60_000;
+    let (days, hours, minutes) = (minutes / 1440, (minutes % 1440) / 60, minutes % 60);
+
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
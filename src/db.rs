@@ -6,6 +6,86 @@ use serde::{Deserialize, Serialize};
 pub struct AppData {
     pub db: Database,
     pub http_client: awc::Client,
+    pub config: crate::config::Config,
+    /// Where attachment files are stored
+    pub attachments: std::sync::Arc<dyn crate::attachments::AttachmentStore>,
+    /// The private key of the ActivityPub instance actor
+    pub actor_key: Option<rsa::RsaPrivateKey>,
+    pub mailer: crate::email::Mailer,
+    /// If startup (including [`Database::init`]) has finished
+    pub ready: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    /// Translation catalogs of the dashboard and embed form
+    pub i18n: crate::i18n::Catalogs,
+    /// The Bayesian spam model (see [`crate::bayes::check`])
+    pub bayes: crate::bayes::ModelCache,
+}
+
+/// Schema migrations applied by [`Database::init`], in order
+///
+/// Entries must never be edited or reordered once released, new schema changes
+/// should always be appended to the end of this list.
+pub const MIGRATIONS: &[&str] = &[
+    // 1: report tracking tokens
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"tracking_hashed\" VARCHAR(1000000) DEFAULT ''",
+    // 2: public resolution messages
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"resolution\" VARCHAR(1000000) DEFAULT ''",
+    // 3: when report statuses were last changed
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"status_timestamp\" VARCHAR(1000000) DEFAULT '0'",
+    // 4: report assignment
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"assignee\" VARCHAR(1000000) DEFAULT ''",
+    // 5: report priorities
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"priority\" VARCHAR(1000000) DEFAULT '0'",
+    // 6: when reports were escalated for breaching their SLA
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"escalated\" VARCHAR(1000000) DEFAULT '0'",
+    // 7: spam scores
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"spam_score\" VARCHAR(1000000) DEFAULT '0'",
+    // 8: spam probabilities from the Bayesian model
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"bayes_score\" VARCHAR(1000000) DEFAULT ''",
+    // 9: who filed reports (see crate::reputation)
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"reporter\" VARCHAR(1000000) DEFAULT ''",
+    // 10: existing reports were filed by their author, but only if it's an account (anyone could
+    // create reports as any name, so the reporter of the others is unknown)
+    "UPDATE \"de_reports\" SET \"reporter\" = \"author\" WHERE \"author\" IN (SELECT \"username\" FROM \"Users\")",
+    // 11: canonical report addresses (see crate::canonical)
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"canonical_address\" VARCHAR(1000000) DEFAULT ''",
+    // 12: custom field values (see crate::fields)
+    "ALTER TABLE \"de_reports\" ADD COLUMN \"fields\" VARCHAR(1000000) DEFAULT '{}'",
+    // 13: truncated snapshots (see crate::snapshots)
+    "ALTER TABLE \"de_snapshots\" ADD COLUMN \"truncated\" VARCHAR(1000000) DEFAULT 'false'",
+    // 14-18: remove duplicate report tags (keeping the oldest), then keep them unique
+    "CREATE TABLE \"de_report_tags_unique\" AS SELECT \"report\", \"tag\", MIN(\"timestamp\") AS \"timestamp\" FROM \"de_report_tags\" GROUP BY \"report\", \"tag\"",
+    "DELETE FROM \"de_report_tags\"",
+    "INSERT INTO \"de_report_tags\" (\"report\", \"tag\", \"timestamp\") SELECT \"report\", \"tag\", \"timestamp\" FROM \"de_report_tags_unique\"",
+    "DROP TABLE \"de_report_tags_unique\"",
+    "CREATE UNIQUE INDEX \"de_report_tags_report_tag\" ON \"de_report_tags\" (\"report\", \"tag\")",
+];
+
+/// The highest [`Report`] priority (kept to one digit so priorities sort as text)
+pub const MAX_PRIORITY: u8 = 9;
+
+/// The most reports a single bulk action can change
+pub const MAX_BULK_REPORTS: usize = 1_000;
+
+/// The content of reports after they're redacted
+pub const REDACTED_CONTENT: &str = "[redacted]";
+
+/// Normalize a tag name (ex: `Needs Legal` becomes `needs-legal`)
+///
+/// Returns `None` if the tag is empty, longer than 32 characters or contains characters
+/// other than letters, numbers, `-` and `_`.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase().replace(' ', "-");
+
+    if tag.is_empty()
+        || (tag.chars().count() > 32)
+        || !tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || (c == '-') || (c == '_'))
+    {
+        return None;
+    }
+
+    Some(tag)
 }
 
 // ...
@@ -52,7 +132,7 @@ impl std::fmt::Display for ReportStatus {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Report {
     /// The ID of the report
     pub id: String,
@@ -68,6 +148,335 @@ pub struct Report {
     pub address: String,
     /// When it was reported
     pub timestamp: u128,
+    /// When the status was last changed (`0` if it never was)
+    #[serde(default)]
+    pub status_timestamp: u128,
+    /// The username of the staff member the report is assigned to (empty if unassigned)
+    #[serde(default)]
+    pub assignee: String,
+    /// The tags of the report (see [`normalize_tag`])
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The public message shown to the reporter about how the report was handled
+    #[serde(default)]
+    pub resolution: String,
+    /// The priority of the report (`0` to [`MAX_PRIORITY`], higher is more urgent)
+    #[serde(default)]
+    pub priority: u8,
+    /// When the report was escalated for breaching its SLA (`0` if it never was)
+    #[serde(default)]
+    pub escalated: u128,
+    /// How likely the report is to be spam (`0` to `100`, see [`crate::spam`])
+    #[serde(default)]
+    pub spam_score: u8,
+    /// The spam probability (as a percentage) given by the Bayesian model when the report was
+    /// created (`None` if there was no model, see [`crate::bayes`])
+    #[serde(default)]
+    pub bayes_score: Option<u8>,
+    /// Who filed the report: the account it was filed from, or the fingerprint of a reporter who
+    /// isn't logged in (empty if unknown, see [`crate::reputation::reporter`])
+    #[serde(default)]
+    pub reporter: String,
+    /// The canonical form of `address` reports are deduplicated and grouped by (see
+    /// [`crate::canonical`])
+    #[serde(default)]
+    pub canonical_address: String,
+    /// The values of the custom fields of the reported site (see [`crate::fields`])
+    #[serde(default)]
+    pub fields: std::collections::BTreeMap<String, String>,
+    /// The token the reporter can track the report with
+    ///
+    /// Only included when the report is created, the database only stores its hash.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub tracking: String,
+}
+
+impl Report {
+    /// When the report got its current status (when it was created if the status never changed)
+    pub fn status_since(&self) -> u128 {
+        if self.status_timestamp > 0 {
+            self.status_timestamp
+        } else {
+            self.timestamp
+        }
+    }
+
+    /// How long (in milliseconds) the report has had its current status
+    ///
+    /// # Arguments:
+    /// * `now` - the current unix timestamp (in milliseconds)
+    pub fn time_in_status(&self, now: u128) -> u128 {
+        now.saturating_sub(self.status_since())
+    }
+}
+
+/// The public view of a [`Report`], shown to anybody with its tracking token
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrackedReport {
+    pub report_type: ReportType,
+    pub status: ReportStatus,
+    pub address: String,
+    pub timestamp: u128,
+    pub resolution: String,
+}
+
+impl From<Report> for TrackedReport {
+    fn from(report: Report) -> Self {
+        TrackedReport {
+            report_type: report.report_type,
+            status: report.status,
+            address: report.address,
+            timestamp: report.timestamp,
+            resolution: report.resolution,
+        }
+    }
+}
+
+/// What the submitter of a new [`Report`] gets back
+///
+/// Scores, statuses and other triage details are left out, so they can't be used to tune reports
+/// until they get past spam checks.
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+pub struct CreatedReport {
+    pub id: String,
+    /// The token the submitter tracks the report with
+    pub tracking: String,
+}
+
+impl From<Report> for CreatedReport {
+    fn from(report: Report) -> Self {
+        CreatedReport {
+            id: report.id,
+            tracking: report.tracking,
+        }
+    }
+}
+
+/// An action applied to many reports at once by [`Database::bulk_edit_reports`]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum BulkAction {
+    /// Set the [`ReportStatus`] of every report
+    Status(ReportStatus),
+    /// Assign every report to a staff member (by username, empty to unassign)
+    Assign(String),
+    /// Add a tag to every report
+    Tag(String),
+    /// Set the priority of every report (capped at [`MAX_PRIORITY`])
+    Priority(u8),
+    /// Remove the `author` and `content` of every report, keeping everything else for statistics
+    Redact,
+    /// Permanently delete every report
+    Delete,
+}
+
+impl std::fmt::Display for BulkAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BulkAction::Status(status) => write!(f, "status:{status}"),
+            BulkAction::Assign(username) => write!(f, "assign:{username}"),
+            BulkAction::Tag(tag) => write!(f, "tag:{tag}"),
+            BulkAction::Priority(priority) => write!(f, "priority:{priority}"),
+            BulkAction::Redact => write!(f, "redact"),
+            BulkAction::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+/// An audit log entry (stored in `Logs` with the `de_audit` type)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    /// The ID of the changed report (or rule, for `rule:` actions)
+    pub report: String,
+    /// The username of the staff member who made the change
+    pub user: String,
+    /// What was changed (ex: `status:Spam`)
+    pub action: String,
+}
+
+/// The stored state of a scheduled job (see [`crate::jobs`])
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct JobState {
+    pub name: String,
+    /// When the job last finished (`0` if it never ran)
+    pub last_run: u128,
+    /// When the job should run next
+    pub next_run: u128,
+    /// The instance currently running the job (empty if it isn't running)
+    pub locked_by: String,
+    /// When the lock expires, even if the instance never released it
+    pub locked_until: u128,
+}
+
+/// A single finished run of a scheduled job
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct JobRun {
+    pub id: String,
+    pub name: String,
+    /// The instance that ran the job
+    pub instance: String,
+    pub started: u128,
+    pub finished: u128,
+    pub success: bool,
+    pub message: String,
+}
+
+/// A copy of a reported address taken when the report was created
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Snapshot {
+    pub id: String,
+    /// The ID of the report
+    pub report: String,
+    /// The address that was requested
+    pub url: String,
+    /// The address after following redirects
+    pub final_url: String,
+    /// The HTTP status of the final response (`0` if no response was received)
+    pub status: u16,
+    /// The headers of the final response
+    pub headers: Vec<(String, String)>,
+    /// The response body (empty if it wasn't HTML)
+    pub html: String,
+    /// The readable text of the response body (empty if it wasn't text or HTML)
+    pub text: String,
+    /// The hex SHA-256 hash of the response body
+    pub hash: String,
+    pub timestamp: u128,
+    /// Why the snapshot couldn't be taken (empty if it could)
+    pub error: String,
+    /// If the response body was larger than the size limit and only its start was stored
+    pub truncated: bool,
+}
+
+/// A file attached to a report as evidence (the file itself is in an
+/// [`AttachmentStore`](crate::attachments::AttachmentStore))
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Attachment {
+    pub id: String,
+    /// The ID of the report
+    pub report: String,
+    /// The (sanitized) file name
+    pub name: String,
+    /// The MIME type detected from the file content
+    pub mime: String,
+    /// The size of the file (in bytes)
+    pub size: usize,
+    /// The username of the staff member who uploaded the file (empty if uploaded by the reporter)
+    pub author: String,
+    pub timestamp: u128,
+}
+
+/// The outcomes of every report filed by a reporter (see [`crate::reputation`])
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReporterStats {
+    /// The author of the reports, or the fingerprint of an anonymous reporter
+    pub reporter: String,
+    /// The number of reports filed
+    pub reports: usize,
+    /// The number of reports staff resolved
+    pub accepted: usize,
+    /// The number of reports staff marked as spam
+    pub spam: usize,
+    /// The trust score (`0` to `100`, see [`crate::reputation::trust`])
+    pub trust: u8,
+    /// When the statistics were calculated
+    pub updated: u128,
+}
+
+impl ReporterStats {
+    /// The number of reports staff made a decision about
+    pub fn decisions(&self) -> usize {
+        self.accepted + self.spam
+    }
+}
+
+/// The order of reports returned by [`Database::get_reports_by_filter`]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum ReportSort {
+    /// Most urgent first
+    #[default]
+    Priority,
+    /// Most trusted reporter first (see [`crate::reputation`])
+    Trust,
+}
+
+impl std::fmt::Display for ReportSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Filters for [`Database::get_reports_by_filter`], `None` matches everything
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ReportFilter {
+    pub status: Option<ReportStatus>,
+    pub report_type: Option<ReportType>,
+    /// Only match reports created by this username
+    pub author: Option<String>,
+    /// Only match reports created or filed by this person (their `author` or `reporter`)
+    #[serde(default)]
+    pub person: Option<String>,
+    /// Only match reports with this tag
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// The order of matched reports (newest first within each group)
+    #[serde(default)]
+    pub sort: ReportSort,
+}
+
+impl ReportFilter {
+    /// Build a filter from (optional) query string values, invalid values are ignored
+    pub fn from_query(
+        status: &Option<String>,
+        report_type: &Option<String>,
+        tag: &Option<String>,
+        sort: &Option<String>,
+    ) -> ReportFilter {
+        ReportFilter {
+            status: Self::parse_value(status),
+            report_type: Self::parse_value(report_type),
+            author: None,
+            person: None,
+            tag: tag.as_deref().and_then(normalize_tag),
+            sort: Self::parse_value(sort).unwrap_or_default(),
+        }
+    }
+
+    /// Parse an enum variant from its name (empty values are `None`)
+    fn parse_value<T: serde::de::DeserializeOwned>(value: &Option<String>) -> Option<T> {
+        match value {
+            Some(v) if !v.is_empty() => {
+                serde_json::from_value(serde_json::Value::String(v.to_owned())).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the query string for this filter (starting with `&`, empty if nothing is filtered)
+    pub fn to_query(&self) -> String {
+        let mut out = String::new();
+
+        if let Some(ref status) = self.status {
+            out.push_str(&format!("&status={status}"));
+        }
+
+        if let Some(ref report_type) = self.report_type {
+            out.push_str(&format!("&type={report_type}"));
+        }
+
+        if let Some(ref author) = self.author {
+            out.push_str(&format!("&author={author}"));
+        }
+
+        if let Some(ref tag) = self.tag {
+            out.push_str(&format!("&tag={tag}"));
+        }
+
+        if self.sort != ReportSort::default() {
+            out.push_str(&format!("&sort={}", self.sort));
+        }
+
+        out
+    }
 }
 
 // server
@@ -81,8 +490,11 @@ pub struct Database {
 
 impl Database {
     pub async fn new(opts: dorsal::DatabaseOpts) -> Database {
-        let db = dorsal::StarterDatabase::new(opts).await;
+        Database::from_base(dorsal::StarterDatabase::new(opts).await)
+    }
 
+    /// Create a new [`Database`] from an existing connection
+    pub fn from_base(db: dorsal::StarterDatabase) -> Database {
         let auth = dorsal::AuthDatabase { base: db.clone() };
         let logs = dorsal::LogDatabase { base: db.clone() };
 
@@ -98,7 +510,10 @@ impl Database {
         }
     }
 
-    pub async fn init(&self) {
+    /// Create all tables and apply any pending [`MIGRATIONS`]
+    ///
+    /// Returns `false` if a migration failed.
+    pub async fn init(&self) -> bool {
         let c = &self.base.db.client;
 
         let _ = sqlquery(
@@ -138,132 +553,366 @@ impl Database {
         )
         .execute(c)
         .await;
-    }
 
-    // example
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_feed_tokens\" (
+                token_hashed VARCHAR(1000000),
+                username VARCHAR(1000000),
+                timestamp VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-    // GET
-    /// Get all [`Report`]s (limited)
-    ///
-    /// # Arguments:
-    /// * `offset` - optional value representing the SQL fetch offset
-    pub async fn get_all_reports(&self, offset: Option<i32>) -> DefaultReturn<Option<Vec<Report>>> {
-        let offset = if offset.is_some() { offset.unwrap() } else { 0 };
+        // tags
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_tags\" (
+                name VARCHAR(1000000),
+                timestamp VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-        // check in cache
-        let cached = self
-            .base
-            .cachedb
-            .get(format!("reports:offset{}", offset))
-            .await;
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_report_tags\" (
+                report VARCHAR(1000000),
+                tag VARCHAR(1000000),
+                timestamp VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-        if cached.is_some() {
-            // ...
-            let reports = serde_json::from_str::<Vec<Report>>(cached.unwrap().as_str()).unwrap();
+        // jobs
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_jobs\" (
+                name VARCHAR(1000000),
+                last_run VARCHAR(1000000),
+                next_run VARCHAR(1000000),
+                locked_by VARCHAR(1000000),
+                locked_until VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-            // return
-            return DefaultReturn {
-                success: true,
-                message: String::from("Found reports"),
-                payload: Option::Some(reports),
-            };
-        }
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_job_runs\" (
+                id VARCHAR(1000000),
+                name VARCHAR(1000000),
+                instance VARCHAR(1000000),
+                started VARCHAR(1000000),
+                finished VARCHAR(1000000),
+                success VARCHAR(1000000),
+                message VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-        // ...
-        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
-            "SELECT * FROM \"de_reports\" ORDER BY \"timestamp\" DESC LIMIT 50 OFFSET ?"
-        } else {
-            "SELECT * FROM \"de_reports\" ORDER BY \"timestamp\" DESC LIMIT 50 OFFSET $1"
-        };
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_snapshots\" (
+                id VARCHAR(1000000),
+                report VARCHAR(1000000),
+                url VARCHAR(1000000),
+                final_url VARCHAR(1000000),
+                status VARCHAR(1000000),
+                headers VARCHAR(1000000),
+                html VARCHAR(1000000),
+                text VARCHAR(1000000),
+                hash VARCHAR(1000000),
+                timestamp VARCHAR(1000000),
+                error VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-        let c = &self.base.db.client;
-        let res = sqlquery(query).bind(offset).fetch_all(c).await;
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_attachments\" (
+                id VARCHAR(1000000),
+                report VARCHAR(1000000),
+                name VARCHAR(1000000),
+                mime VARCHAR(1000000),
+                size VARCHAR(1000000),
+                author VARCHAR(1000000),
+                timestamp VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-        if res.is_err() {
-            return DefaultReturn {
-                success: false,
-                message: String::from(res.err().unwrap().to_string()),
-                payload: Option::None,
-            };
-        }
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_rules\" (
+                id VARCHAR(1000000),
+                name VARCHAR(1000000),
+                position VARCHAR(1000000),
+                enabled VARCHAR(1000000),
+                events VARCHAR(1000000),
+                conditions VARCHAR(1000000),
+                actions VARCHAR(1000000),
+                stop VARCHAR(1000000),
+                timestamp VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-        // build res
-        let mut full_res: Vec<Report> = Vec::new();
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_reporters\" (
+                reporter VARCHAR(1000000),
+                reports VARCHAR(1000000),
+                accepted VARCHAR(1000000),
+                spam VARCHAR(1000000),
+                trust VARCHAR(1000000),
+                updated VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
 
-        for row in res.unwrap() {
-            let row = self.base.textify_row(row).data;
-            full_res.push(Report {
-                id: row.get("id").unwrap().to_string(),
-                report_type: serde_json::from_str(row.get("report_type").unwrap()).unwrap(),
-                status: serde_json::from_str(row.get("report_status").unwrap()).unwrap(),
-                author: row.get("author").unwrap().to_string(),
-                content: row.get("content").unwrap().to_string(),
-                address: row.get("address").unwrap().to_string(),
-                timestamp: row.get("timestamp").unwrap().parse::<u128>().unwrap(),
-            });
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_report_subjects\" (
+                report VARCHAR(1000000),
+                subject VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
+
+        // meta table
+        let _ = sqlquery(
+            "CREATE TABLE IF NOT EXISTS \"de_meta\" (
+                name VARCHAR(1000000),
+                content VARCHAR(1000000)
+            )",
+        )
+        .execute(c)
+        .await;
+
+        if !self.migrate().await {
+            return false;
         }
 
-        // store in cache
-        self.base
-            .cachedb
-            .set(
-                format!("reports:offset{}", offset),
-                serde_json::to_string::<Vec<Report>>(&full_res).unwrap(),
-            )
-            .await;
+        // reports created before canonical addresses were stored are never deduplicated against
+        match self.backfill_canonical_addresses().await {
+            Ok(0) => (),
+            Ok(n) => println!("Stored the canonical address of {n} report(s)"),
+            Err(e) => eprintln!("Failed to store canonical addresses: {e}"),
+        }
 
-        // return
-        return DefaultReturn {
-            success: true,
-            message: String::from("Found reports"),
-            payload: Option::Some(full_res),
-        };
+        true
     }
 
-    /// Get a report by its id
+    /// Store the canonical address of every report created before they were stored, returning
+    /// the number of reports
     ///
-    /// # Arguments:
-    /// * `id` - `String` of the report's `id`
-    pub async fn get_report_by_id(&self, id: String) -> DefaultReturn<Option<Report>> {
-        // check in cache
-        let cached = self.base.cachedb.get(format!("report:{}", id)).await;
-
-        if cached.is_some() {
-            // ...
-            let report = serde_json::from_str::<Report>(cached.unwrap().as_str()).unwrap();
-
-            // return
-            return DefaultReturn {
-                success: true,
-                message: String::from("Report exists (cache)"),
-                payload: Option::Some(report),
-            };
-        }
+    /// Addresses which can't be canonicalized are stored as they are.
+    async fn backfill_canonical_addresses(&self) -> Result<usize, String> {
+        let c = &self.base.db.client;
+        let rows = sqlquery(
+            "SELECT \"id\", \"address\" FROM \"de_reports\" WHERE \"canonical_address\" = '' OR \"canonical_address\" IS NULL",
+        )
+        .fetch_all(c)
+        .await
+        .map_err(|e| e.to_string())?;
 
-        // ...
         let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
-            "SELECT * FROM \"de_reports\" WHERE \"id\" = ?"
+            "UPDATE \"de_reports\" SET \"canonical_address\" = ? WHERE \"id\" = ?"
         } else {
-            "SELECT * FROM \"de_reports\" WHERE \"id\" = $1"
+            "UPDATE \"de_reports\" SET \"canonical_address\" = $1 WHERE \"id\" = $2"
         };
 
-        let c = &self.base.db.client;
-        let res = sqlquery(query).bind::<&String>(&id).fetch_one(c).await;
-
-        if res.is_err() {
-            return DefaultReturn {
-                success: false,
-                message: String::from("Report does not exist"),
-                payload: Option::None,
+        let count = rows.len();
+        for row in rows {
+            let row = self.base.textify_row(row).data;
+            let (id, address) = match (row.get("id"), row.get("address")) {
+                (Some(id), Some(address)) => (id, address),
+                _ => continue,
             };
+
+            sqlquery(query)
+                .bind::<&String>(
+                    &crate::canonical::canonicalize(address).unwrap_or(address.to_string()),
+                )
+                .bind::<&String>(id)
+                .execute(c)
+                .await
+                .map_err(|e| e.to_string())?;
         }
 
-        // ...
-        let row = res.unwrap();
+        Ok(count)
+    }
+
+    /// Apply every migration in [`MIGRATIONS`] that hasn't been applied yet
+    async fn migrate(&self) -> bool {
+        let c = &self.base.db.client;
+        let version = match self.get_schema_version().await {
+            Some(v) => v,
+            None => {
+                // fresh database, nothing has been applied yet
+                let query: &str =
+                    if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                        "INSERT INTO \"de_meta\" VALUES (?, ?)"
+                    } else {
+                        "INSERT INTO \"de_meta\" VALUES ($1, $2)"
+                    };
+
+                if let Err(e) = sqlquery(query)
+                    .bind::<&str>("schema_version")
+                    .bind::<&str>("0")
+                    .execute(c)
+                    .await
+                {
+                    eprintln!("Failed to store schema version: {e}");
+                    return false;
+                }
+
+                0
+            }
+        };
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            if let Err(e) = sqlquery(migration).execute(c).await {
+                eprintln!("Migration {} failed: {e}", i + 1);
+                return false;
+            }
+
+            // update version
+            let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql")
+            {
+                "UPDATE \"de_meta\" SET \"content\" = ? WHERE \"name\" = ?"
+            } else {
+                "UPDATE \"de_meta\" SET (\"content\") = ($1) WHERE \"name\" = $2"
+            };
+
+            if let Err(e) = sqlquery(query)
+                .bind::<&String>(&(i + 1).to_string())
+                .bind::<&str>("schema_version")
+                .execute(c)
+                .await
+            {
+                eprintln!("Failed to store schema version: {e}");
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Get the number of [`MIGRATIONS`] applied to the database
+    ///
+    /// Returns `None` if the version has never been stored (or the database can't be reached).
+    pub async fn get_schema_version(&self) -> Option<usize> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_meta\" WHERE \"name\" = ?"
+        } else {
+            "SELECT * FROM \"de_meta\" WHERE \"name\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let row = sqlquery(query)
+            .bind::<&str>("schema_version")
+            .fetch_one(c)
+            .await
+            .ok()?;
+
         let row = self.base.textify_row(row).data;
+        row.get("content")?.parse::<usize>().ok()
+    }
 
-        // store in cache
-        let report = Report {
+    /// Get a stored value from the meta table
+    ///
+    /// # Arguments:
+    /// * `name` - the name of the value
+    pub async fn get_meta(&self, name: &str) -> Option<String> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_meta\" WHERE \"name\" = ?"
+        } else {
+            "SELECT * FROM \"de_meta\" WHERE \"name\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let row = sqlquery(query).bind::<&str>(name).fetch_one(c).await.ok()?;
+        self.base.textify_row(row).data.remove("content")
+    }
+
+    /// Store a value in the meta table, replacing any existing value
+    ///
+    /// # Arguments:
+    /// * `name` - the name of the value
+    /// * `content` - the value
+    pub async fn set_meta(&self, name: &str, content: &str) -> Result<(), String> {
+        let c = &self.base.db.client;
+        let update: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"de_meta\" SET \"content\" = ? WHERE \"name\" = ?"
+        } else {
+            "UPDATE \"de_meta\" SET (\"content\") = ($1) WHERE \"name\" = $2"
+        };
+
+        let res = sqlquery(update)
+            .bind::<&str>(content)
+            .bind::<&str>(name)
+            .execute(c)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if res.rows_affected() > 0 {
+            return Ok(());
+        }
+
+        let insert: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"de_meta\" VALUES (?, ?)"
+        } else {
+            "INSERT INTO \"de_meta\" VALUES ($1, $2)"
+        };
+
+        sqlquery(insert)
+            .bind::<&str>(name)
+            .bind::<&str>(content)
+            .execute(c)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Check if the database client can still run queries
+    pub async fn check_database(&self) -> bool {
+        sqlquery("SELECT 1")
+            .execute(&self.base.db.client)
+            .await
+            .is_ok()
+    }
+
+    /// Check if the cache server can be reached
+    pub async fn check_cache(&self) -> bool {
+        let client = self.base.cachedb.client.clone();
+
+        // connecting blocks, so it can't run on the worker thread
+        actix_web::web::block(move || {
+            client
+                .get_connection_with_timeout(std::time::Duration::from_secs(2))
+                .is_ok()
+        })
+        .await
+        .unwrap_or(false)
+    }
+
+    // example
+
+    /// Get the placeholder for the `n`th (starting at 1) value bound to a query
+    fn placeholder(&self, n: usize) -> String {
+        if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            String::from("?")
+        } else {
+            format!("${n}")
+        }
+    }
+
+    /// Build a [`Report`] from a textified `de_reports` row
+    fn report_from_row(row: &std::collections::HashMap<String, String>) -> Report {
+        Report {
             id: row.get("id").unwrap().to_string(),
             report_type: serde_json::from_str(row.get("report_type").unwrap()).unwrap(),
             status: serde_json::from_str(row.get("report_status").unwrap()).unwrap(),
@@ -271,8 +920,252 @@ impl Database {
             content: row.get("content").unwrap().to_string(),
             address: row.get("address").unwrap().to_string(),
             timestamp: row.get("timestamp").unwrap().parse::<u128>().unwrap(),
+            status_timestamp: row
+                .get("status_timestamp")
+                .and_then(|t| t.parse::<u128>().ok())
+                .unwrap_or(0),
+            assignee: row.get("assignee").cloned().unwrap_or_default(),
+            tags: Vec::new(),
+            resolution: row.get("resolution").cloned().unwrap_or_default(),
+            priority: row
+                .get("priority")
+                .and_then(|p| p.parse::<u8>().ok())
+                .unwrap_or(0),
+            escalated: row
+                .get("escalated")
+                .and_then(|t| t.parse::<u128>().ok())
+                .unwrap_or(0),
+            spam_score: row
+                .get("spam_score")
+                .and_then(|s| s.parse::<u8>().ok())
+                .unwrap_or(0),
+            bayes_score: row.get("bayes_score").and_then(|s| s.parse::<u8>().ok()),
+            reporter: row.get("reporter").cloned().unwrap_or_default(),
+            // reports created before canonical addresses were stored
+            canonical_address: match row.get("canonical_address") {
+                Some(a) if !a.is_empty() => a.to_string(),
+                _ => {
+                    let address = row.get("address").unwrap();
+                    crate::canonical::canonicalize(address).unwrap_or(address.to_string())
+                }
+            },
+            fields: row
+                .get("fields")
+                .and_then(|f| serde_json::from_str(f).ok())
+                .unwrap_or_default(),
+            tracking: String::new(),
+        }
+    }
+
+    /// Build the `WHERE` clause (empty if nothing is filtered) and the values to bind for a [`ReportFilter`]
+    fn filter_conditions(&self, filter: &ReportFilter) -> (String, Vec<String>) {
+        let mut conditions: Vec<String> = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+
+        if let Some(ref status) = filter.status {
+            values.push(serde_json::to_string(status).unwrap());
+            conditions.push(format!(
+                "\"report_status\" = {}",
+                self.placeholder(values.len())
+            ));
+        }
+
+        if let Some(ref report_type) = filter.report_type {
+            values.push(serde_json::to_string(report_type).unwrap());
+            conditions.push(format!(
+                "\"report_type\" = {}",
+                self.placeholder(values.len())
+            ));
+        }
+
+        if let Some(ref author) = filter.author {
+            values.push(author.to_owned());
+            conditions.push(format!("\"author\" = {}", self.placeholder(values.len())));
+        }
+
+        if let Some(ref person) = filter.person {
+            values.push(person.to_owned());
+            values.push(person.to_owned());
+            conditions.push(format!(
+                "(\"author\" = {} OR \"reporter\" = {})",
+                self.placeholder(values.len() - 1),
+                self.placeholder(values.len())
+            ));
+        }
+
+        if let Some(ref tag) = filter.tag {
+            values.push(tag.to_owned());
+            conditions.push(format!(
+                "\"id\" IN (SELECT \"report\" FROM \"de_report_tags\" WHERE \"tag\" = {})",
+                self.placeholder(values.len())
+            ));
+        }
+
+        if conditions.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!(" WHERE {}", conditions.join(" AND ")), values)
+        }
+    }
+
+    // GET
+    /// Get all [`Report`]s (limited)
+    ///
+    /// # Arguments:
+    /// * `offset` - optional value representing the SQL fetch offset
+    pub async fn get_all_reports(&self, offset: Option<i32>) -> DefaultReturn<Option<Vec<Report>>> {
+        self.get_reports_by_filter(&ReportFilter::default(), offset)
+            .await
+    }
+
+    /// Get all [`Report`]s matching a [`ReportFilter`] (limited)
+    ///
+    /// # Arguments:
+    /// * `filter` - [`ReportFilter`]
+    /// * `offset` - optional value representing the SQL fetch offset
+    pub async fn get_reports_by_filter(
+        &self,
+        filter: &ReportFilter,
+        offset: Option<i32>,
+    ) -> DefaultReturn<Option<Vec<Report>>> {
+        let offset = offset.unwrap_or(0);
+        let cache_key = format!("reports:offset{}{}", offset, filter.to_query());
+
+        // check in cache
+        if let Some(cached) = self.base.cachedb.get(cache_key.clone()).await {
+            let reports = serde_json::from_str::<Vec<Report>>(cached.as_str()).unwrap();
+
+            // return
+            return DefaultReturn {
+                success: true,
+                message: String::from("Found reports"),
+                payload: Option::Some(reports),
+            };
+        }
+
+        // build query
+        let (conditions, values) = self.filter_conditions(filter);
+
+        // trust scores are stored padded to 3 digits so they sort as text
+        let order = match filter.sort {
+            ReportSort::Priority => "\"priority\" DESC",
+            ReportSort::Trust => "COALESCE((SELECT \"trust\" FROM \"de_reporters\" WHERE \"de_reporters\".\"reporter\" = \"de_reports\".\"reporter\"), '050') DESC",
+        };
+
+        let query = format!(
+            "SELECT * FROM \"de_reports\"{} ORDER BY {}, \"timestamp\" DESC LIMIT 50 OFFSET {}",
+            conditions,
+            order,
+            self.placeholder(values.len() + 1)
+        );
+
+        let mut query = sqlquery(&query);
+
+        for value in &values {
+            query = query.bind::<&String>(value);
+        }
+
+        let c = &self.base.db.client;
+        let res = match query.bind(offset).fetch_all(c).await {
+            Ok(r) => r,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        // build res
+        let mut full_res: Vec<Report> = Vec::new();
+
+        for row in res {
+            let row = self.base.textify_row(row).data;
+            full_res.push(Self::report_from_row(&row));
+        }
+
+        // get tags
+        let mut tags = self
+            .get_tags_of_reports(
+                &full_res
+                    .iter()
+                    .map(|r| r.id.clone())
+                    .collect::<Vec<String>>(),
+            )
+            .await;
+
+        for report in full_res.iter_mut() {
+            report.tags = tags.remove(&report.id).unwrap_or_default();
+        }
+
+        // store in cache
+        self.base
+            .cachedb
+            .set(
+                cache_key,
+                serde_json::to_string::<Vec<Report>>(&full_res).unwrap(),
+            )
+            .await;
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Found reports"),
+            payload: Option::Some(full_res),
+        }
+    }
+
+    /// Get a report by its id
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the report's `id`
+    pub async fn get_report_by_id(&self, id: String) -> DefaultReturn<Option<Report>> {
+        // check in cache
+        let cached = self.base.cachedb.get(format!("report:{}", id)).await;
+
+        if cached.is_some() {
+            // ...
+            let report = serde_json::from_str::<Report>(cached.unwrap().as_str()).unwrap();
+
+            // return
+            return DefaultReturn {
+                success: true,
+                message: String::from("Report exists (cache)"),
+                payload: Option::Some(report),
+            };
+        }
+
+        // ...
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_reports\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"de_reports\" WHERE \"id\" = $1"
         };
 
+        let c = &self.base.db.client;
+        let res = sqlquery(query).bind::<&String>(&id).fetch_one(c).await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Report does not exist"),
+                payload: Option::None,
+            };
+        }
+
+        // ...
+        let row = res.unwrap();
+        let row = self.base.textify_row(row).data;
+
+        // store in cache
+        let mut report = Self::report_from_row(&row);
+        report.tags = self
+            .get_tags_of_reports(std::slice::from_ref(&id))
+            .await
+            .remove(&id)
+            .unwrap_or_default();
+
         self.base
             .cachedb
             .set(
@@ -305,10 +1198,7 @@ impl Database {
         }
 
         // check address
-        if (props.address.len() < 1)
-            | (props.address.len() > 2_000)
-            | (!props.address.starts_with("http"))
-        {
+        if (props.address.len() < 1) | (props.address.len() > 2_000) {
             return DefaultReturn {
                 success: false,
                 message: String::from("Address is invalid"),
@@ -316,22 +1206,74 @@ impl Database {
             };
         }
 
+        props.canonical_address = match crate::canonical::canonicalize(&props.address) {
+            Ok(a) => a,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e,
+                    payload: Option::None,
+                }
+            }
+        };
+
+        // check for duplicates (anonymous reporters are identified by their fingerprint, see
+        // crate::reputation::reporter)
+        if !props.reporter.is_empty() {
+            match self
+                .count_active_reports_by_address(&props.canonical_address, &props.reporter)
+                .await
+            {
+                Ok(0) => (),
+                Ok(_) => {
+                    return DefaultReturn {
+                        success: false,
+                        message: String::from("This address has already been reported"),
+                        payload: Option::None,
+                    }
+                }
+                Err(e) => eprintln!("Failed to check for duplicate reports: {e}"),
+            }
+        }
+
         // create report
+        props.id = dorsal::utility::random_id();
+        props.timestamp = dorsal::utility::unix_epoch_timestamp();
+        props.resolution = String::new();
+        props.status_timestamp = 0;
+        props.assignee = String::new();
+        props.priority = 0;
+        props.escalated = 0;
+        props.spam_score = 0;
+        props.bayes_score = None;
+        props.tracking = dorsal::utility::random_id();
+
         let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
-            "INSERT INTO \"de_reports\" VALUES (?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO \"de_reports\" (\"id\", \"report_type\", \"report_status\", \"author\", \"content\", \"address\", \"timestamp\", \"tracking_hashed\", \"resolution\", \"status_timestamp\", \"assignee\", \"priority\", \"escalated\", \"spam_score\", \"bayes_score\", \"reporter\", \"canonical_address\", \"fields\") VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         } else {
-            "INSERT INTO \"de_reports\" VALUES ($1, $2, $3, $4, $5, $6, $7)"
+            "INSERT INTO \"de_reports\" (\"id\", \"report_type\", \"report_status\", \"author\", \"content\", \"address\", \"timestamp\", \"tracking_hashed\", \"resolution\", \"status_timestamp\", \"assignee\", \"priority\", \"escalated\", \"spam_score\", \"bayes_score\", \"reporter\", \"canonical_address\", \"fields\") VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)"
         };
 
         let c = &self.base.db.client;
         let res = sqlquery(query)
-            .bind::<&String>(&dorsal::utility::random_id())
+            .bind::<&String>(&props.id)
             .bind::<&String>(&serde_json::to_string(&props.report_type).unwrap())
             .bind::<&String>(&serde_json::to_string(&props.status).unwrap())
             .bind::<&String>(&props.author)
             .bind::<&String>(&props.content)
             .bind::<&String>(&props.address)
-            .bind::<&String>(&dorsal::utility::unix_epoch_timestamp().to_string())
+            .bind::<&String>(&props.timestamp.to_string())
+            .bind::<&String>(&dorsal::utility::hash(props.tracking.clone()))
+            .bind::<&String>(&props.resolution)
+            .bind::<&str>("0")
+            .bind::<&str>("")
+            .bind::<&str>("0")
+            .bind::<&str>("0")
+            .bind::<&str>("0")
+            .bind::<&str>("")
+            .bind::<&String>(&props.reporter)
+            .bind::<&String>(&props.canonical_address)
+            .bind::<&String>(&serde_json::to_string(&props.fields).unwrap())
             .execute(c)
             .await;
 
@@ -358,10 +1300,17 @@ impl Database {
     }
 
     /// Update a [`Report`]'s [`ReportStatus`] by its `id`
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the report's `id`
+    /// * `status` - the new [`ReportStatus`]
+    /// * `resolution` - the new public resolution message (`None` keeps the existing message)
+    /// * `edit_as` - the user editing the report
     pub async fn edit_report_status_by_id(
         &self,
         id: String,
         status: ReportStatus,
+        resolution: Option<String>,
         edit_as: Option<dorsal::db::special::auth_db::FullUser<String>>,
     ) -> DefaultReturn<Option<String>> {
         // make sure report exists
@@ -384,7 +1333,7 @@ impl Database {
         };
 
         // make sure we can do this
-        match edit_as {
+        let editor = match edit_as {
             Some(ua) => {
                 if !ua.level.permissions.contains(&"StaffDashboard".to_string()) {
                     return DefaultReturn {
@@ -393,6 +1342,8 @@ impl Database {
                         payload: Option::None,
                     };
                 }
+
+                ua.user.username
             }
             None => {
                 return DefaultReturn {
@@ -401,18 +1352,37 @@ impl Database {
                     payload: Option::None,
                 }
             }
-        }
+        };
+
+        let resolution = match resolution {
+            Some(r) => {
+                if r.len() > 2_000 {
+                    return DefaultReturn {
+                        success: false,
+                        message: String::from("Resolution is invalid"),
+                        payload: Option::None,
+                    };
+                }
+
+                r
+            }
+            None => existing.payload.as_ref().unwrap().resolution.clone(),
+        };
 
         // update paste
         let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
-            "UPDATE \"de_reports\" SET \"report_status\" = ? WHERE \"id\" = ?"
+            "UPDATE \"de_reports\" SET \"report_status\" = ?, \"resolution\" = ?, \"status_timestamp\" = ? WHERE \"id\" = ?"
         } else {
-            "UPDATE \"de_reports\" SET (\"report_status\") = ($1) WHERE \"id\" = $2"
+            "UPDATE \"de_reports\" SET (\"report_status\", \"resolution\", \"status_timestamp\") = ($1, $2, $3) WHERE \"id\" = $4"
         };
 
+        let status_timestamp = dorsal::utility::unix_epoch_timestamp();
+
         let c = &self.base.db.client;
         let res = sqlquery(query)
             .bind::<&String>(&serde_json::to_string(&status).unwrap())
+            .bind::<&String>(&resolution)
+            .bind::<&String>(&status_timestamp.to_string())
             .bind::<&String>(&id)
             .execute(c)
             .await;
@@ -425,12 +1395,16 @@ impl Database {
             };
         }
 
+        self.audit(&id, &editor, format!("status:{status}")).await;
+
         // update cache
         let existing_in_cache = self.base.cachedb.get(format!("report:{}", id)).await;
 
         if existing_in_cache.is_some() {
             let mut report = serde_json::from_str::<Report>(&existing_in_cache.unwrap()).unwrap();
             report.status = status;
+            report.resolution = resolution;
+            report.status_timestamp = status_timestamp;
 
             // update cache
             self.base
@@ -440,14 +1414,14 @@ impl Database {
                     serde_json::to_string::<Report>(&report).unwrap(),
                 )
                 .await;
-
-            // TODO: maybe only clear the correct offset
-            self.base
-                .cachedb
-                .remove_starting_with("reports:offset*".to_string())
-                .await;
         }
 
+        // TODO: maybe only clear the correct offset
+        self.base
+            .cachedb
+            .remove_starting_with("reports:offset*".to_string())
+            .await;
+
         // return
         return DefaultReturn {
             success: true,
@@ -455,4 +1429,2513 @@ impl Database {
             payload: Option::Some(id),
         };
     }
+
+    /// Apply a [`BulkAction`] to many reports in a single transaction
+    ///
+    /// Every changed report gets its own [`AuditEntry`]. Returns the IDs of the changed reports.
+    ///
+    /// # Arguments:
+    /// * `ids` - the IDs of the reports to change
+    /// * `filter` - change every report matching this [`ReportFilter`] instead of `ids`
+    /// * `action` - [`BulkAction`]
+    /// * `edit_as` - the user making the change
+    pub async fn bulk_edit_reports(
+        &self,
+        ids: Vec<String>,
+        filter: Option<ReportFilter>,
+        action: BulkAction,
+        edit_as: Option<dorsal::db::special::auth_db::FullUser<String>>,
+    ) -> DefaultReturn<Option<Vec<String>>> {
+        // make sure we can do this
+        let editor = match edit_as {
+            Some(ua) if ua.level.permissions.contains(&"StaffDashboard".to_string()) => {
+                ua.user.username
+            }
+            _ => {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("You are not allowed to do this."),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        self.apply_bulk_action(ids, filter, action, &editor).await
+    }
+
+    /// Redact every report created or filed by a person and remove their reputation, **without
+    /// checking permissions**
+    ///
+    /// Reports are redacted [`MAX_BULK_REPORTS`] at a time, their reputation is removed along with
+    /// the last batch.
+    ///
+    /// # Arguments:
+    /// * `person` - the author or reporter
+    /// * `editor` - the name stored in each [`AuditEntry`]
+    pub async fn erase_person(
+        &self,
+        person: &str,
+        editor: &str,
+    ) -> DefaultReturn<Option<Vec<String>>> {
+        // redacted reports have no author, so an empty person would never run out of matches
+        if person.is_empty() {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Author is invalid"),
+                payload: Option::None,
+            };
+        }
+
+        let filter = ReportFilter {
+            person: Some(person.to_string()),
+            ..Default::default()
+        };
+
+        let (conditions, values) = self.filter_conditions(&filter);
+        let query = format!("SELECT \"id\" FROM \"de_reports\"{conditions}");
+        let mut changed: Vec<String> = Vec::new();
+
+        loop {
+            let mut ids_query = sqlquery(&query);
+            for value in &values {
+                ids_query = ids_query.bind::<&String>(value);
+            }
+
+            let ids: Vec<String> = match ids_query.fetch_all(&self.base.db.client).await {
+                Ok(rows) => rows
+                    .into_iter()
+                    .filter_map(|row| self.base.textify_row(row).data.remove("id"))
+                    .collect(),
+                Err(e) => {
+                    return DefaultReturn {
+                        success: false,
+                        message: e.to_string(),
+                        payload: Option::None,
+                    }
+                }
+            };
+
+            // the last batch is redacted by filter, which also removes their reputation
+            let last = ids.len() <= MAX_BULK_REPORTS;
+            let res = if last {
+                self.apply_bulk_action(Vec::new(), Some(filter.clone()), BulkAction::Redact, editor)
+                    .await
+            } else {
+                self.apply_bulk_action(
+                    ids[..MAX_BULK_REPORTS].to_vec(),
+                    None,
+                    BulkAction::Redact,
+                    editor,
+                )
+                .await
+            };
+
+            match res.payload {
+                Some(ids) if last => {
+                    changed.extend(ids);
+                    break;
+                }
+                Some(ids) if !ids.is_empty() => changed.extend(ids),
+                _ => {
+                    return DefaultReturn {
+                        success: false,
+                        message: format!(
+                            "Redacted {} report(s) before failing: {}",
+                            changed.len(),
+                            res.message
+                        ),
+                        payload: Option::None,
+                    }
+                }
+            }
+        }
+
+        DefaultReturn {
+            success: true,
+            message: format!("Redacted {} report(s)", changed.len()),
+            payload: Option::Some(changed),
+        }
+    }
+
+    /// Apply a [`BulkAction`] to many reports in a single transaction, **without checking permissions**
+    ///
+    /// Used by [`Database::bulk_edit_reports`] and background jobs.
+    ///
+    /// # Arguments:
+    /// * `ids` - the IDs of the reports to change
+    /// * `filter` - change every report matching this [`ReportFilter`] instead of `ids`
+    /// * `action` - [`BulkAction`]
+    /// * `editor` - the name stored in each [`AuditEntry`] (ex: a username or `system:retention`)
+    pub async fn apply_bulk_action(
+        &self,
+        ids: Vec<String>,
+        filter: Option<ReportFilter>,
+        action: BulkAction,
+        editor: &str,
+    ) -> DefaultReturn<Option<Vec<String>>> {
+        if let BulkAction::Assign(ref username) = action {
+            if !username.is_empty()
+                && !self
+                    .auth
+                    .get_user_by_username(username.clone())
+                    .await
+                    .success
+            {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Assignee does not exist"),
+                    payload: Option::None,
+                };
+            }
+        }
+
+        let action = match action {
+            BulkAction::Tag(tag) => match normalize_tag(&tag) {
+                Some(tag) => BulkAction::Tag(tag),
+                None => {
+                    return DefaultReturn {
+                        success: false,
+                        message: String::from("Tag is invalid"),
+                        payload: Option::None,
+                    }
+                }
+            },
+            BulkAction::Priority(priority) => BulkAction::Priority(priority.min(MAX_PRIORITY)),
+            action => action,
+        };
+
+        let c = &self.base.db.client;
+        let mut tx = match c.begin().await {
+            Ok(t) => t,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        // redacting everything of a person also removes their reputation (see crate::reputation)
+        let erased = match (&action, &filter) {
+            (BulkAction::Redact, Some(f)) => f.person.clone(),
+            _ => None,
+        };
+
+        // get ids
+        let ids: Vec<String> = match filter {
+            Some(filter) => {
+                let (conditions, values) = self.filter_conditions(&filter);
+                let query = format!("SELECT \"id\" FROM \"de_reports\"{conditions}");
+                let mut query = sqlquery(&query);
+
+                for value in &values {
+                    query = query.bind::<&String>(value);
+                }
+
+                match query.fetch_all(&mut *tx).await {
+                    Ok(rows) => rows
+                        .into_iter()
+                        .filter_map(|row| self.base.textify_row(row).data.remove("id"))
+                        .collect(),
+                    Err(e) => {
+                        return DefaultReturn {
+                            success: false,
+                            message: e.to_string(),
+                            payload: Option::None,
+                        }
+                    }
+                }
+            }
+            None => ids,
+        };
+
+        if ids.len() > MAX_BULK_REPORTS {
+            return DefaultReturn {
+                success: false,
+                message: format!("Cannot change more than {MAX_BULK_REPORTS} reports at once"),
+                payload: Option::None,
+            };
+        }
+
+        // apply action
+        let status_timestamp = dorsal::utility::unix_epoch_timestamp().to_string();
+        let (query, value): (&str, String) = match action {
+            BulkAction::Status(ref status) => (
+                if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                    "UPDATE \"de_reports\" SET \"report_status\" = ?, \"status_timestamp\" = ? WHERE \"id\" = ?"
+                } else {
+                    "UPDATE \"de_reports\" SET (\"report_status\", \"status_timestamp\") = ($1, $2) WHERE \"id\" = $3"
+                },
+                serde_json::to_string(status).unwrap(),
+            ),
+            BulkAction::Priority(priority) => (
+                if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                    "UPDATE \"de_reports\" SET \"priority\" = ? WHERE \"id\" = ?"
+                } else {
+                    "UPDATE \"de_reports\" SET \"priority\" = $1 WHERE \"id\" = $2"
+                },
+                priority.to_string(),
+            ),
+            BulkAction::Assign(ref username) => (
+                if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                    "UPDATE \"de_reports\" SET \"assignee\" = ? WHERE \"id\" = ?"
+                } else {
+                    "UPDATE \"de_reports\" SET (\"assignee\") = ($1) WHERE \"id\" = $2"
+                },
+                username.to_owned(),
+            ),
+            // only inserted if the report exists and doesn't have the tag yet
+            BulkAction::Tag(ref tag) => (
+                if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                    "INSERT INTO \"de_report_tags\" (\"report\", \"tag\", \"timestamp\") SELECT \"id\", ?, ? FROM \"de_reports\" WHERE \"id\" = ? AND NOT EXISTS (SELECT 1 FROM \"de_report_tags\" WHERE \"report\" = ? AND \"tag\" = ?)"
+                } else {
+                    "INSERT INTO \"de_report_tags\" (\"report\", \"tag\", \"timestamp\") SELECT \"id\", $1, $2 FROM \"de_reports\" WHERE \"id\" = $3 AND NOT EXISTS (SELECT 1 FROM \"de_report_tags\" WHERE \"report\" = $4 AND \"tag\" = $5)"
+                },
+                tag.to_owned(),
+            ),
+            BulkAction::Redact => (
+                if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                    "UPDATE \"de_reports\" SET \"author\" = '', \"reporter\" = '', \"fields\" = '{}', \"content\" = ? WHERE \"id\" = ?"
+                } else {
+                    "UPDATE \"de_reports\" SET (\"author\", \"reporter\", \"fields\", \"content\") = ('', '', '{}', $1) WHERE \"id\" = $2"
+                },
+                REDACTED_CONTENT.to_string(),
+            ),
+            BulkAction::Delete => (
+                if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                    "DELETE FROM \"de_reports\" WHERE \"id\" = ?"
+                } else {
+                    "DELETE FROM \"de_reports\" WHERE \"id\" = $1"
+                },
+                String::new(),
+            ),
+        };
+
+        // the tag is added to the list of used tags along with its first report
+        let create_tag_query: &str = if (self.base.db._type == "sqlite")
+            | (self.base.db._type == "mysql")
+        {
+            "INSERT INTO \"de_tags\" (\"name\", \"timestamp\") SELECT ?, ? FROM \"de_reports\" WHERE \"id\" = ? AND NOT EXISTS (SELECT 1 FROM \"de_tags\" WHERE \"name\" = ?)"
+        } else {
+            "INSERT INTO \"de_tags\" (\"name\", \"timestamp\") SELECT $1, $2 FROM \"de_reports\" WHERE \"id\" = $3 AND NOT EXISTS (SELECT 1 FROM \"de_tags\" WHERE \"name\" = $4)"
+        };
+
+        let untag_all_query: &str =
+            if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                "DELETE FROM \"de_report_tags\" WHERE \"report\" = ?"
+            } else {
+                "DELETE FROM \"de_report_tags\" WHERE \"report\" = $1"
+            };
+
+        let unsubject_query: &str =
+            if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                "DELETE FROM \"de_report_subjects\" WHERE \"report\" = ?"
+            } else {
+                "DELETE FROM \"de_report_subjects\" WHERE \"report\" = $1"
+            };
+
+        let unsnapshot_query: &str =
+            if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                "DELETE FROM \"de_snapshots\" WHERE \"report\" = ?"
+            } else {
+                "DELETE FROM \"de_snapshots\" WHERE \"report\" = $1"
+            };
+
+        // files are removed by the attachment cleanup job once their rows are gone
+        let unattach_query: &str =
+            if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                "DELETE FROM \"de_attachments\" WHERE \"report\" = ?"
+            } else {
+                "DELETE FROM \"de_attachments\" WHERE \"report\" = $1"
+            };
+
+        let audit_query: &str =
+            if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                "INSERT INTO \"Logs\" VALUES (?, ?, ?, ?)"
+            } else {
+                "INSERT INTO \"Logs\" VALUES ($1, $2, $3, $4)"
+            };
+
+        let mut changed: Vec<String> = Vec::new();
+        for id in ids {
+            let mut update = sqlquery(query);
+
+            if action != BulkAction::Delete {
+                update = update.bind::<&String>(&value);
+            }
+
+            if let BulkAction::Status(_) | BulkAction::Tag(_) = action {
+                update = update.bind::<&String>(&status_timestamp);
+            }
+
+            update = update.bind::<&String>(&id);
+
+            if let BulkAction::Tag(ref tag) = action {
+                update = update.bind::<&String>(&id).bind::<&String>(tag);
+            }
+
+            let res = match update.execute(&mut *tx).await {
+                Ok(r) => r,
+                Err(e) => {
+                    // dropping the transaction rolls it back
+                    return DefaultReturn {
+                        success: false,
+                        message: e.to_string(),
+                        payload: Option::None,
+                    };
+                }
+            };
+
+            // missing reports (and reports which already have the tag) aren't changed
+            if res.rows_affected() == 0 {
+                continue;
+            }
+
+            if let BulkAction::Tag(ref tag) = action {
+                if let Err(e) = sqlquery(create_tag_query)
+                    .bind::<&String>(tag)
+                    .bind::<&String>(&status_timestamp)
+                    .bind::<&String>(&id)
+                    .bind::<&String>(tag)
+                    .execute(&mut *tx)
+                    .await
+                {
+                    return DefaultReturn {
+                        success: false,
+                        message: e.to_string(),
+                        payload: Option::None,
+                    };
+                }
+            }
+
+            // remove tags and subjects of deleted reports
+            if action == BulkAction::Delete {
+                for query in [untag_all_query, unsubject_query] {
+                    if let Err(e) = sqlquery(query).bind::<&String>(&id).execute(&mut *tx).await {
+                        return DefaultReturn {
+                            success: false,
+                            message: e.to_string(),
+                            payload: Option::None,
+                        };
+                    }
+                }
+            }
+
+            // snapshots and attachments contain the reported content too
+            if let BulkAction::Delete | BulkAction::Redact = action {
+                for query in [unsnapshot_query, unattach_query] {
+                    if let Err(e) = sqlquery(query).bind::<&String>(&id).execute(&mut *tx).await {
+                        return DefaultReturn {
+                            success: false,
+                            message: e.to_string(),
+                            payload: Option::None,
+                        };
+                    }
+                }
+            }
+
+            // audit
+            let entry = AuditEntry {
+                report: id.clone(),
+                user: editor.to_string(),
+                action: action.to_string(),
+            };
+
+            if let Err(e) = sqlquery(audit_query)
+                .bind::<&String>(&dorsal::utility::random_id())
+                .bind::<&str>("de_audit")
+                .bind::<&String>(&status_timestamp)
+                .bind::<&String>(&serde_json::to_string(&entry).unwrap())
+                .execute(&mut *tx)
+                .await
+            {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                };
+            }
+
+            changed.push(id);
+        }
+
+        if let Some(person) = erased {
+            let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql")
+            {
+                "DELETE FROM \"de_reporters\" WHERE \"reporter\" = ?"
+            } else {
+                "DELETE FROM \"de_reporters\" WHERE \"reporter\" = $1"
+            };
+
+            if let Err(e) = sqlquery(query)
+                .bind::<&String>(&person)
+                .execute(&mut *tx)
+                .await
+            {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                };
+            }
+        }
+
+        if let Err(e) = tx.commit().await {
+            return DefaultReturn {
+                success: false,
+                message: e.to_string(),
+                payload: Option::None,
+            };
+        }
+
+        // update cache
+        for id in &changed {
+            self.base.cachedb.remove(format!("report:{}", id)).await;
+        }
+
+        self.base
+            .cachedb
+            .remove_starting_with("reports:offset*".to_string())
+            .await;
+
+        DefaultReturn {
+            success: true,
+            message: format!("Updated {} report(s)", changed.len()),
+            payload: Option::Some(changed),
+        }
+    }
+
+    // jobs
+    // timestamps are stored zero-padded to 13 digits so they can be compared as text
+
+    /// Create the stored state of a job if it doesn't exist yet
+    ///
+    /// # Arguments:
+    /// * `name` - the name of the job
+    pub async fn ensure_job(&self, name: &str) -> Result<(), String> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_jobs\" WHERE \"name\" = ?"
+        } else {
+            "SELECT * FROM \"de_jobs\" WHERE \"name\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let existing = sqlquery(query)
+            .bind::<&str>(name)
+            .fetch_optional(c)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if existing.is_some() {
+            return Ok(());
+        }
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"de_jobs\" VALUES (?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"de_jobs\" VALUES ($1, $2, $3, $4, $5)"
+        };
+
+        let zero = format!("{:013}", 0);
+        sqlquery(query)
+            .bind::<&str>(name)
+            .bind::<&String>(&zero)
+            .bind::<&String>(&zero)
+            .bind::<&str>("")
+            .bind::<&String>(&zero)
+            .execute(c)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Try to take the lock of a job which is due to run
+    ///
+    /// Only one instance can hold the lock at a time, returns `true` if this instance got it.
+    ///
+    /// # Arguments:
+    /// * `name` - the name of the job
+    /// * `instance` - the ID of this instance
+    /// * `lock_for` - how long (in milliseconds) the lock is held if it is never released
+    pub async fn acquire_job(&self, name: &str, instance: &str, lock_for: u128) -> bool {
+        let now = dorsal::utility::unix_epoch_timestamp();
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"de_jobs\" SET \"locked_by\" = ?, \"locked_until\" = ? WHERE \"name\" = ? AND \"locked_until\" < ? AND \"next_run\" <= ?"
+        } else {
+            "UPDATE \"de_jobs\" SET (\"locked_by\", \"locked_until\") = ($1, $2) WHERE \"name\" = $3 AND \"locked_until\" < $4 AND \"next_run\" <= $5"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&str>(instance)
+            .bind::<&String>(&format!("{:013}", now + lock_for))
+            .bind::<&str>(name)
+            .bind::<&String>(&format!("{now:013}"))
+            .bind::<&String>(&format!("{now:013}"))
+            .execute(c)
+            .await
+        {
+            Ok(r) => r.rows_affected() > 0,
+            Err(e) => {
+                eprintln!("Failed to lock job {name}: {e}");
+                false
+            }
+        }
+    }
+
+    /// Extend the lock of a job this instance is running
+    ///
+    /// Returns `false` if this instance doesn't hold the lock anymore.
+    ///
+    /// # Arguments:
+    /// * `name` - the name of the job
+    /// * `instance` - the ID of this instance
+    /// * `lock_for` - how long (in milliseconds from now) the lock is held if it is never renewed
+    pub async fn renew_job(&self, name: &str, instance: &str, lock_for: u128) -> bool {
+        let now = dorsal::utility::unix_epoch_timestamp();
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"de_jobs\" SET \"locked_until\" = ? WHERE \"name\" = ? AND \"locked_by\" = ?"
+        } else {
+            "UPDATE \"de_jobs\" SET (\"locked_until\") = ($1) WHERE \"name\" = $2 AND \"locked_by\" = $3"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query)
+            .bind::<&String>(&format!("{:013}", now + lock_for))
+            .bind::<&str>(name)
+            .bind::<&str>(instance)
+            .execute(c)
+            .await
+        {
+            Ok(r) => r.rows_affected() > 0,
+            Err(e) => {
+                eprintln!("Failed to renew lock of job {name}: {e}");
+                false
+            }
+        }
+    }
+
+    /// Release the lock of a job after it ran, scheduling its next run
+    ///
+    /// # Arguments:
+    /// * `name` - the name of the job
+    /// * `instance` - the ID of this instance
+    /// * `next_run` - when the job should run next
+    pub async fn release_job(&self, name: &str, instance: &str, next_run: u128) {
+        let now = dorsal::utility::unix_epoch_timestamp();
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"de_jobs\" SET \"last_run\" = ?, \"next_run\" = ?, \"locked_by\" = '', \"locked_until\" = ? WHERE \"name\" = ? AND \"locked_by\" = ?"
+        } else {
+            "UPDATE \"de_jobs\" SET (\"last_run\", \"next_run\", \"locked_by\", \"locked_until\") = ($1, $2, '', $3) WHERE \"name\" = $4 AND \"locked_by\" = $5"
+        };
+
+        let c = &self.base.db.client;
+        if let Err(e) = sqlquery(query)
+            .bind::<&String>(&format!("{now:013}"))
+            .bind::<&String>(&format!("{next_run:013}"))
+            .bind::<&String>(&format!("{:013}", 0))
+            .bind::<&str>(name)
+            .bind::<&str>(instance)
+            .execute(c)
+            .await
+        {
+            eprintln!("Failed to release job {name}: {e}");
+        }
+    }
+
+    /// Get the stored state of every job
+    pub async fn get_jobs(&self) -> DefaultReturn<Option<Vec<JobState>>> {
+        let c = &self.base.db.client;
+        let res = match sqlquery("SELECT * FROM \"de_jobs\" ORDER BY \"name\" ASC")
+            .fetch_all(c)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        let parse = |v: Option<&String>| v.and_then(|v| v.parse::<u128>().ok()).unwrap_or(0);
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Found jobs"),
+            payload: Option::Some(
+                res.into_iter()
+                    .map(|row| {
+                        let row = self.base.textify_row(row).data;
+
+                        JobState {
+                            name: row.get("name").unwrap().to_string(),
+                            last_run: parse(row.get("last_run")),
+                            next_run: parse(row.get("next_run")),
+                            locked_by: row.get("locked_by").unwrap().to_string(),
+                            locked_until: parse(row.get("locked_until")),
+                        }
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Store a finished [`JobRun`], removing runs older than 30 days
+    ///
+    /// # Arguments:
+    /// * `run` - [`JobRun`]
+    pub async fn create_job_run(&self, run: &JobRun) {
+        let c = &self.base.db.client;
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"de_job_runs\" VALUES (?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"de_job_runs\" VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        };
+
+        if let Err(e) = sqlquery(query)
+            .bind::<&String>(&run.id)
+            .bind::<&String>(&run.name)
+            .bind::<&String>(&run.instance)
+            .bind::<&String>(&format!("{:013}", run.started))
+            .bind::<&String>(&format!("{:013}", run.finished))
+            .bind::<&String>(&run.success.to_string())
+            .bind::<&String>(&run.message)
+            .execute(c)
+            .await
+        {
+            eprintln!("Failed to store job run: {e}");
+        }
+
+        // remove old runs
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"de_job_runs\" WHERE \"started\" < ?"
+        } else {
+            "DELETE FROM \"de_job_runs\" WHERE \"started\" < $1"
+        };
+
+        let _ = sqlquery(query)
+            .bind::<&String>(&format!(
+                "{:013}",
+                run.started.saturating_sub(30 * crate::dates::DAY)
+            ))
+            .execute(c)
+            .await;
+    }
+
+    /// Get the newest [`JobRun`]s (limited)
+    pub async fn get_job_runs(&self) -> DefaultReturn<Option<Vec<JobRun>>> {
+        let c = &self.base.db.client;
+        let res =
+            match sqlquery("SELECT * FROM \"de_job_runs\" ORDER BY \"started\" DESC LIMIT 100")
+                .fetch_all(c)
+                .await
+            {
+                Ok(r) => r,
+                Err(e) => {
+                    return DefaultReturn {
+                        success: false,
+                        message: e.to_string(),
+                        payload: Option::None,
+                    }
+                }
+            };
+
+        let parse = |v: Option<&String>| v.and_then(|v| v.parse::<u128>().ok()).unwrap_or(0);
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Found job runs"),
+            payload: Option::Some(
+                res.into_iter()
+                    .map(|row| {
+                        let row = self.base.textify_row(row).data;
+
+                        JobRun {
+                            id: row.get("id").unwrap().to_string(),
+                            name: row.get("name").unwrap().to_string(),
+                            instance: row.get("instance").unwrap().to_string(),
+                            started: parse(row.get("started")),
+                            finished: parse(row.get("finished")),
+                            success: row.get("success").is_some_and(|s| s == "true"),
+                            message: row.get("message").unwrap().to_string(),
+                        }
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    // tags
+
+    /// Get the name of every tag that has been used (sorted)
+    pub async fn get_tags(&self) -> DefaultReturn<Option<Vec<String>>> {
+        let c = &self.base.db.client;
+        let res = match sqlquery("SELECT * FROM \"de_tags\"").fetch_all(c).await {
+            Ok(r) => r,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        let mut tags: Vec<String> = res
+            .into_iter()
+            .filter_map(|row| self.base.textify_row(row).data.remove("name"))
+            .collect();
+
+        tags.sort();
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Found tags"),
+            payload: Option::Some(tags),
+        }
+    }
+
+    /// Get every used tag along with the given predefined tags (sorted)
+    ///
+    /// # Arguments:
+    /// * `predefined` - the tags from the config
+    pub async fn get_known_tags(&self, predefined: &[String]) -> Vec<String> {
+        let mut tags = self.get_tags().await.payload.unwrap_or_default();
+
+        for tag in predefined.iter().filter_map(|t| normalize_tag(t)) {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+
+        tags.sort();
+        tags
+    }
+
+    /// Get the tags of many reports (by report ID)
+    ///
+    /// # Arguments:
+    /// * `ids` - the IDs of the reports
+    async fn get_tags_of_reports(
+        &self,
+        ids: &[String],
+    ) -> std::collections::HashMap<String, Vec<String>> {
+        let mut out: std::collections::HashMap<String, Vec<String>> =
+            std::collections::HashMap::new();
+
+        if ids.is_empty() {
+            return out;
+        }
+
+        let query = format!(
+            "SELECT * FROM \"de_report_tags\" WHERE \"report\" IN ({})",
+            (1..=ids.len())
+                .map(|n| self.placeholder(n))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        let mut query = sqlquery(&query);
+
+        for id in ids {
+            query = query.bind::<&String>(id);
+        }
+
+        let c = &self.base.db.client;
+        let res = match query.fetch_all(c).await {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("Failed to get report tags: {e}");
+                return out;
+            }
+        };
+
+        for row in res {
+            let row = self.base.textify_row(row).data;
+            out.entry(row.get("report").unwrap().to_string())
+                .or_default()
+                .push(row.get("tag").unwrap().to_string());
+        }
+
+        for tags in out.values_mut() {
+            tags.sort();
+        }
+
+        out
+    }
+
+    /// Add a tag to a [`Report`]
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the report's `id`
+    /// * `tag` - the tag to add (see [`normalize_tag`])
+    /// * `edit_as` - the user adding the tag
+    pub async fn add_report_tag(
+        &self,
+        id: String,
+        tag: String,
+        edit_as: Option<dorsal::db::special::auth_db::FullUser<String>>,
+    ) -> DefaultReturn<Option<String>> {
+        let tag = match normalize_tag(&tag) {
+            Some(t) => t,
+            None => {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Tag is invalid"),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        // a single report bulk action does exactly this
+        let res = self
+            .bulk_edit_reports(vec![id], None, BulkAction::Tag(tag.clone()), edit_as)
+            .await;
+
+        DefaultReturn {
+            success: res.success,
+            message: if res.success {
+                String::from("Tag added!")
+            } else {
+                res.message
+            },
+            payload: if res.success { Some(tag) } else { None },
+        }
+    }
+
+    /// Remove a tag from a [`Report`]
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the report's `id`
+    /// * `tag` - the tag to remove
+    /// * `edit_as` - the user removing the tag
+    pub async fn remove_report_tag(
+        &self,
+        id: String,
+        tag: String,
+        edit_as: Option<dorsal::db::special::auth_db::FullUser<String>>,
+    ) -> DefaultReturn<Option<String>> {
+        // make sure we can do this
+        let editor = match edit_as {
+            Some(ua) if ua.level.permissions.contains(&"StaffDashboard".to_string()) => {
+                ua.user.username
+            }
+            _ => {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("You are not allowed to do this."),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"de_report_tags\" WHERE \"report\" = ? AND \"tag\" = ?"
+        } else {
+            "DELETE FROM \"de_report_tags\" WHERE \"report\" = $1 AND \"tag\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(query)
+            .bind::<&String>(&id)
+            .bind::<&String>(&tag)
+            .execute(c)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        if res.rows_affected() == 0 {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Report does not have this tag"),
+                payload: Option::None,
+            };
+        }
+
+        self.audit(&id, &editor, format!("untag:{tag}")).await;
+
+        // update cache
+        self.base.cachedb.remove(format!("report:{}", id)).await;
+        self.base
+            .cachedb
+            .remove_starting_with("reports:offset*".to_string())
+            .await;
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Tag removed!"),
+            payload: Option::Some(tag),
+        }
+    }
+
+    /// Mark a [`Report`] as escalated for breaching its SLA and set its priority
+    ///
+    /// Only used by background jobs, so permissions aren't checked.
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the report's `id`
+    /// * `priority` - the new priority (capped at [`MAX_PRIORITY`])
+    /// * `editor` - the name recorded in the audit log
+    pub async fn escalate_report_by_id(
+        &self,
+        id: String,
+        priority: u8,
+        editor: &str,
+    ) -> DefaultReturn<Option<String>> {
+        let priority = priority.min(MAX_PRIORITY);
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"de_reports\" SET \"priority\" = ?, \"escalated\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"de_reports\" SET (\"priority\", \"escalated\") = ($1, $2) WHERE \"id\" = $3"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&priority.to_string())
+            .bind::<&String>(&dorsal::utility::unix_epoch_timestamp().to_string())
+            .bind::<&String>(&id)
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        self.audit(&id, editor, format!("escalate:{priority}"))
+            .await;
+
+        // update cache
+        self.base.cachedb.remove(format!("report:{}", id)).await;
+        self.base
+            .cachedb
+            .remove_starting_with("reports:offset*".to_string())
+            .await;
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Report escalated!"),
+            payload: Option::Some(id),
+        }
+    }
+
+    /// Set the spam score of a [`Report`] (capped at `100`)
+    ///
+    /// Only used when reports are created, so permissions aren't checked.
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the report's `id`
+    /// * `score` - the new spam score
+    pub async fn set_spam_score_by_id(
+        &self,
+        id: String,
+        score: u8,
+    ) -> DefaultReturn<Option<String>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"de_reports\" SET \"spam_score\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"de_reports\" SET \"spam_score\" = $1 WHERE \"id\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&score.min(100).to_string())
+            .bind::<&String>(&id)
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        // update cache
+        self.base.cachedb.remove(format!("report:{}", id)).await;
+        self.base
+            .cachedb
+            .remove_starting_with("reports:offset*".to_string())
+            .await;
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Spam score updated!"),
+            payload: Option::Some(id),
+        }
+    }
+
+    /// Set the Bayesian spam probability of a [`Report`] (capped at `100`)
+    ///
+    /// Only used when reports are created, so permissions aren't checked.
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the report's `id`
+    /// * `score` - the spam probability (as a percentage)
+    pub async fn set_bayes_score_by_id(
+        &self,
+        id: String,
+        score: u8,
+    ) -> DefaultReturn<Option<String>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"de_reports\" SET \"bayes_score\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"de_reports\" SET \"bayes_score\" = $1 WHERE \"id\" = $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&score.min(100).to_string())
+            .bind::<&String>(&id)
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        // update cache
+        self.base.cachedb.remove(format!("report:{}", id)).await;
+        self.base
+            .cachedb
+            .remove_starting_with("reports:offset*".to_string())
+            .await;
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Bayes score updated!"),
+            payload: Option::Some(id),
+        }
+    }
+
+    /// Get the username (or system name) which last changed the status of each report
+    ///
+    /// Reports whose status was never changed aren't included.
+    pub async fn get_status_editors(
+        &self,
+    ) -> Result<std::collections::HashMap<String, String>, String> {
+        let c = &self.base.db.client;
+        let rows = match sqlquery("SELECT * FROM \"Logs\" WHERE \"logtype\" = 'de_audit'")
+            .fetch_all(c)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let mut entries: Vec<(u128, AuditEntry)> = rows
+            .into_iter()
+            .filter_map(|row| {
+                let row = self.base.textify_row(row).data;
+                let timestamp = row.get("timestamp")?.parse::<u128>().ok()?;
+                let entry = serde_json::from_str::<AuditEntry>(row.get("content")?).ok()?;
+                Some((timestamp, entry))
+            })
+            .filter(|(_, entry)| entry.action.starts_with("status:"))
+            .collect();
+
+        // timestamps are stored as text
+        entries.sort_by_key(|(timestamp, _)| *timestamp);
+
+        Ok(entries
+            .into_iter()
+            .map(|(_, entry)| (entry.report, entry.user))
+            .collect())
+    }
+
+    /// Record an [`AuditEntry`] for a change made to a report
+    ///
+    /// # Arguments:
+    /// * `report` - the ID of the changed report
+    /// * `user` - the username of the staff member who made the change
+    /// * `action` - what was changed (ex: `status:Spam`)
+    pub async fn audit(&self, report: &str, user: &str, action: String) {
+        let entry = AuditEntry {
+            report: report.to_string(),
+            user: user.to_string(),
+            action,
+        };
+
+        let res = self
+            .logs
+            .create_log(
+                String::from("de_audit"),
+                serde_json::to_string(&entry).unwrap(),
+            )
+            .await;
+
+        if !res.success {
+            eprintln!("Failed to store audit entry: {}", res.message);
+        }
+    }
+
+    /// Get every [`Report`] matching a [`ReportFilter`] (not limited or cached, tags aren't included)
+    ///
+    /// # Arguments:
+    /// * `filter` - [`ReportFilter`]
+    pub async fn get_every_report_by_filter(
+        &self,
+        filter: &ReportFilter,
+    ) -> DefaultReturn<Option<Vec<Report>>> {
+        let (conditions, values) = self.filter_conditions(filter);
+        let query = format!("SELECT * FROM \"de_reports\"{conditions}");
+        let mut query = sqlquery(&query);
+
+        for value in &values {
+            query = query.bind::<&String>(value);
+        }
+
+        let c = &self.base.db.client;
+        let res = match query.fetch_all(c).await {
+            Ok(r) => r,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Found reports"),
+            payload: Option::Some(
+                res.into_iter()
+                    .map(|row| Self::report_from_row(&self.base.textify_row(row).data))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Get every [`Report`] created during a [`Period`](crate::stats::Period) (not cached)
+    ///
+    /// # Arguments:
+    /// * `from` - the start of the period (unix timestamp in milliseconds, inclusive)
+    /// * `to` - the end of the period (unix timestamp in milliseconds, exclusive)
+    pub async fn get_reports_in_period(
+        &self,
+        from: u128,
+        to: u128,
+    ) -> DefaultReturn<Option<Vec<Report>>> {
+        // timestamps are stored as text, which compares correctly for every (13 digit)
+        // timestamp between 2001 and 2286; they're checked again as numbers below
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_reports\" WHERE \"timestamp\" >= ? AND \"timestamp\" < ?"
+        } else {
+            "SELECT * FROM \"de_reports\" WHERE \"timestamp\" >= $1 AND \"timestamp\" < $2"
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(query)
+            .bind::<&String>(&format!("{from:013}"))
+            .bind::<&String>(&format!("{to:013}"))
+            .fetch_all(c)
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        let mut full_res: Vec<Report> = Vec::new();
+
+        for row in res {
+            let row = self.base.textify_row(row).data;
+            let report = Self::report_from_row(&row);
+
+            if (report.timestamp >= from) && (report.timestamp < to) {
+                full_res.push(report);
+            }
+        }
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Found reports"),
+            payload: Option::Some(full_res),
+        }
+    }
+
+    /// Get a report by its tracking token
+    ///
+    /// # Arguments:
+    /// * `token` - the unhashed tracking token
+    pub async fn get_report_by_tracking(&self, token: String) -> DefaultReturn<Option<Report>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_reports\" WHERE \"tracking_hashed\" = ?"
+        } else {
+            "SELECT * FROM \"de_reports\" WHERE \"tracking_hashed\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&dorsal::utility::hash(token))
+            .fetch_one(c)
+            .await;
+
+        match res {
+            Ok(row) => {
+                let row = self.base.textify_row(row).data;
+
+                DefaultReturn {
+                    success: true,
+                    message: String::from("Report exists"),
+                    payload: Option::Some(Self::report_from_row(&row)),
+                }
+            }
+            Err(_) => DefaultReturn {
+                success: false,
+                message: String::from("Report does not exist"),
+                payload: Option::None,
+            },
+        }
+    }
+
+    // snapshots
+
+    /// Store a [`Snapshot`]
+    ///
+    /// # Arguments:
+    /// * `snapshot` - [`Snapshot`]
+    pub async fn create_snapshot(&self, snapshot: &Snapshot) -> DefaultReturn<Option<String>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"de_snapshots\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"de_snapshots\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&snapshot.id)
+            .bind::<&String>(&snapshot.report)
+            .bind::<&String>(&snapshot.url)
+            .bind::<&String>(&snapshot.final_url)
+            .bind::<&String>(&snapshot.status.to_string())
+            .bind::<&String>(&serde_json::to_string(&snapshot.headers).unwrap())
+            .bind::<&String>(&snapshot.html)
+            .bind::<&String>(&snapshot.text)
+            .bind::<&String>(&snapshot.hash)
+            .bind::<&String>(&snapshot.timestamp.to_string())
+            .bind::<&String>(&snapshot.error)
+            .bind::<&String>(&snapshot.truncated.to_string())
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Snapshot stored!"),
+            payload: Option::Some(snapshot.id.clone()),
+        }
+    }
+
+    /// Get the [`Snapshot`] of a [`Report`]
+    ///
+    /// # Arguments:
+    /// * `report` - `String` of the report's `id`
+    pub async fn get_snapshot_by_report(&self, report: String) -> DefaultReturn<Option<Snapshot>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_snapshots\" WHERE \"report\" = ?"
+        } else {
+            "SELECT * FROM \"de_snapshots\" WHERE \"report\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(query).bind::<&String>(&report).fetch_one(c).await {
+            Ok(r) => self.base.textify_row(r).data,
+            Err(_) => {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Snapshot does not exist"),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Snapshot exists"),
+            payload: Option::Some(Snapshot {
+                id: res.get("id").unwrap().to_string(),
+                report: res.get("report").unwrap().to_string(),
+                url: res.get("url").unwrap().to_string(),
+                final_url: res.get("final_url").unwrap().to_string(),
+                status: res
+                    .get("status")
+                    .and_then(|s| s.parse::<u16>().ok())
+                    .unwrap_or(0),
+                headers: res
+                    .get("headers")
+                    .and_then(|h| serde_json::from_str(h).ok())
+                    .unwrap_or_default(),
+                html: res.get("html").cloned().unwrap_or_default(),
+                text: res.get("text").cloned().unwrap_or_default(),
+                hash: res.get("hash").cloned().unwrap_or_default(),
+                timestamp: res
+                    .get("timestamp")
+                    .and_then(|t| t.parse::<u128>().ok())
+                    .unwrap_or(0),
+                error: res.get("error").cloned().unwrap_or_default(),
+                truncated: res.get("truncated").is_some_and(|t| t == "true"),
+            }),
+        }
+    }
+
+    // attachments
+
+    /// Build an [`Attachment`] from a database row
+    fn attachment_from_row(row: &std::collections::HashMap<String, String>) -> Attachment {
+        Attachment {
+            id: row.get("id").unwrap().to_string(),
+            report: row.get("report").unwrap().to_string(),
+            name: row.get("name").unwrap().to_string(),
+            mime: row.get("mime").unwrap().to_string(),
+            size: row
+                .get("size")
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(0),
+            author: row.get("author").cloned().unwrap_or_default(),
+            timestamp: row
+                .get("timestamp")
+                .and_then(|t| t.parse::<u128>().ok())
+                .unwrap_or(0),
+        }
+    }
+
+    /// Store an [`Attachment`]
+    ///
+    /// # Arguments:
+    /// * `attachment` - [`Attachment`]
+    pub async fn create_attachment(
+        &self,
+        attachment: &Attachment,
+    ) -> DefaultReturn<Option<String>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"de_attachments\" VALUES (?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"de_attachments\" VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&attachment.id)
+            .bind::<&String>(&attachment.report)
+            .bind::<&String>(&attachment.name)
+            .bind::<&String>(&attachment.mime)
+            .bind::<&String>(&attachment.size.to_string())
+            .bind::<&String>(&attachment.author)
+            .bind::<&String>(&attachment.timestamp.to_string())
+            .execute(c)
+            .await;
+
+        if res.is_err() {
+            return DefaultReturn {
+                success: false,
+                message: res.err().unwrap().to_string(),
+                payload: Option::None,
+            };
+        }
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Attachment stored!"),
+            payload: Option::Some(attachment.id.clone()),
+        }
+    }
+
+    /// Get an [`Attachment`] by its ID
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the attachment's `id`
+    pub async fn get_attachment_by_id(&self, id: String) -> DefaultReturn<Option<Attachment>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_attachments\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"de_attachments\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&String>(&id).fetch_one(c).await {
+            Ok(r) => DefaultReturn {
+                success: true,
+                message: String::from("Attachment exists"),
+                payload: Option::Some(Self::attachment_from_row(&self.base.textify_row(r).data)),
+            },
+            Err(_) => DefaultReturn {
+                success: false,
+                message: String::from("Attachment does not exist"),
+                payload: Option::None,
+            },
+        }
+    }
+
+    /// Get every [`Attachment`] of a [`Report`] (oldest first)
+    ///
+    /// # Arguments:
+    /// * `report` - `String` of the report's `id`
+    pub async fn get_attachments_by_report(
+        &self,
+        report: String,
+    ) -> DefaultReturn<Option<Vec<Attachment>>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_attachments\" WHERE \"report\" = ? ORDER BY \"timestamp\" ASC"
+        } else {
+            "SELECT * FROM \"de_attachments\" WHERE \"report\" = $1 ORDER BY \"timestamp\" ASC"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&String>(&report).fetch_all(c).await {
+            Ok(rows) => DefaultReturn {
+                success: true,
+                message: String::from("Found attachments"),
+                payload: Option::Some(
+                    rows.into_iter()
+                        .map(|r| Self::attachment_from_row(&self.base.textify_row(r).data))
+                        .collect(),
+                ),
+            },
+            Err(e) => DefaultReturn {
+                success: false,
+                message: e.to_string(),
+                payload: Option::None,
+            },
+        }
+    }
+
+    /// Delete the row of an [`Attachment`] (its file is removed by the cleanup job)
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the attachment's `id`
+    pub async fn delete_attachment(&self, id: String) -> Result<(), String> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"de_attachments\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \"de_attachments\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        sqlquery(query)
+            .bind::<&String>(&id)
+            .execute(c)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    /// Get the ID of every stored [`Attachment`]
+    pub async fn get_attachment_ids(&self) -> Result<std::collections::HashSet<String>, String> {
+        let c = &self.base.db.client;
+        match sqlquery("SELECT \"id\" FROM \"de_attachments\"")
+            .fetch_all(c)
+            .await
+        {
+            Ok(rows) => Ok(rows
+                .into_iter()
+                .filter_map(|r| self.base.textify_row(r).data.get("id").cloned())
+                .collect()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    // rules
+
+    /// Build a [`Rule`](crate::rules::Rule) from a database row
+    fn rule_from_row(row: &std::collections::HashMap<String, String>) -> crate::rules::Rule {
+        crate::rules::Rule {
+            id: row.get("id").unwrap().to_string(),
+            name: row.get("name").unwrap().to_string(),
+            position: row
+                .get("position")
+                .and_then(|p| p.parse::<i32>().ok())
+                .unwrap_or(0),
+            enabled: row.get("enabled").map(|e| e == "true").unwrap_or(false),
+            events: row
+                .get("events")
+                .and_then(|e| serde_json::from_str(e).ok())
+                .unwrap_or_default(),
+            conditions: row
+                .get("conditions")
+                .and_then(|c| serde_json::from_str(c).ok())
+                .unwrap_or_default(),
+            actions: row
+                .get("actions")
+                .and_then(|a| serde_json::from_str(a).ok())
+                .unwrap_or_default(),
+            stop: row.get("stop").map(|s| s == "true").unwrap_or(false),
+            timestamp: row
+                .get("timestamp")
+                .and_then(|t| t.parse::<u128>().ok())
+                .unwrap_or(0),
+        }
+    }
+
+    /// Get every [`Rule`](crate::rules::Rule) in the order they're evaluated
+    pub async fn get_rules(&self) -> DefaultReturn<Option<Vec<crate::rules::Rule>>> {
+        // check in cache
+        if let Some(cached) = self.base.cachedb.get(String::from("rules")).await {
+            if let Ok(rules) = serde_json::from_str::<Vec<crate::rules::Rule>>(&cached) {
+                return DefaultReturn {
+                    success: true,
+                    message: String::from("Found rules (cache)"),
+                    payload: Option::Some(rules),
+                };
+            }
+        }
+
+        let c = &self.base.db.client;
+        let res = match sqlquery("SELECT * FROM \"de_rules\"").fetch_all(c).await {
+            Ok(r) => r,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        let mut rules: Vec<crate::rules::Rule> = res
+            .into_iter()
+            .map(|row| Self::rule_from_row(&self.base.textify_row(row).data))
+            .collect();
+
+        // positions are stored as text
+        rules.sort_by(|a, b| {
+            a.position
+                .cmp(&b.position)
+                .then(a.timestamp.cmp(&b.timestamp))
+        });
+
+        self.base
+            .cachedb
+            .set(
+                String::from("rules"),
+                serde_json::to_string(&rules).unwrap(),
+            )
+            .await;
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Found rules"),
+            payload: Option::Some(rules),
+        }
+    }
+
+    /// Get a [`Rule`](crate::rules::Rule) by its ID
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the rule's `id`
+    pub async fn get_rule_by_id(&self, id: String) -> DefaultReturn<Option<crate::rules::Rule>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_rules\" WHERE \"id\" = ?"
+        } else {
+            "SELECT * FROM \"de_rules\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&String>(&id).fetch_one(c).await {
+            Ok(r) => DefaultReturn {
+                success: true,
+                message: String::from("Rule exists"),
+                payload: Option::Some(Self::rule_from_row(&self.base.textify_row(r).data)),
+            },
+            Err(_) => DefaultReturn {
+                success: false,
+                message: String::from("Rule does not exist"),
+                payload: Option::None,
+            },
+        }
+    }
+
+    /// Create a new [`Rule`](crate::rules::Rule)
+    ///
+    /// # Arguments:
+    /// * `rule` - [`Rule`](crate::rules::Rule) (its `id` and `timestamp` are replaced)
+    /// * `editor` - the username of the user creating the rule
+    pub async fn create_rule(
+        &self,
+        mut rule: crate::rules::Rule,
+        editor: &str,
+    ) -> DefaultReturn<Option<crate::rules::Rule>> {
+        if let Err(e) = rule.validate() {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: Option::None,
+            };
+        }
+
+        rule.id = dorsal::utility::random_id();
+        rule.timestamp = dorsal::utility::unix_epoch_timestamp();
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"de_rules\" VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"de_rules\" VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&rule.id)
+            .bind::<&String>(&rule.name)
+            .bind::<&String>(&rule.position.to_string())
+            .bind::<&String>(&rule.enabled.to_string())
+            .bind::<&String>(&serde_json::to_string(&rule.events).unwrap())
+            .bind::<&String>(&serde_json::to_string(&rule.conditions).unwrap())
+            .bind::<&String>(&serde_json::to_string(&rule.actions).unwrap())
+            .bind::<&String>(&rule.stop.to_string())
+            .bind::<&String>(&rule.timestamp.to_string())
+            .execute(c)
+            .await;
+
+        if let Err(e) = res {
+            return DefaultReturn {
+                success: false,
+                message: e.to_string(),
+                payload: Option::None,
+            };
+        }
+
+        self.base.cachedb.remove(String::from("rules")).await;
+        self.audit(&rule.id, editor, String::from("rule:create"))
+            .await;
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Rule created!"),
+            payload: Option::Some(rule),
+        }
+    }
+
+    /// Replace an existing [`Rule`](crate::rules::Rule)
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the rule's `id`
+    /// * `rule` - the new [`Rule`](crate::rules::Rule) (its `id` and `timestamp` are kept)
+    /// * `editor` - the username of the user editing the rule
+    pub async fn update_rule(
+        &self,
+        id: String,
+        mut rule: crate::rules::Rule,
+        editor: &str,
+    ) -> DefaultReturn<Option<crate::rules::Rule>> {
+        let existing = match self.get_rule_by_id(id).await.payload {
+            Some(r) => r,
+            None => {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Rule does not exist"),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        if let Err(e) = rule.validate() {
+            return DefaultReturn {
+                success: false,
+                message: e,
+                payload: Option::None,
+            };
+        }
+
+        rule.id = existing.id;
+        rule.timestamp = existing.timestamp;
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "UPDATE \"de_rules\" SET \"name\" = ?, \"position\" = ?, \"enabled\" = ?, \"events\" = ?, \"conditions\" = ?, \"actions\" = ?, \"stop\" = ? WHERE \"id\" = ?"
+        } else {
+            "UPDATE \"de_rules\" SET (\"name\", \"position\", \"enabled\", \"events\", \"conditions\", \"actions\", \"stop\") = ($1, $2, $3, $4, $5, $6, $7) WHERE \"id\" = $8"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&rule.name)
+            .bind::<&String>(&rule.position.to_string())
+            .bind::<&String>(&rule.enabled.to_string())
+            .bind::<&String>(&serde_json::to_string(&rule.events).unwrap())
+            .bind::<&String>(&serde_json::to_string(&rule.conditions).unwrap())
+            .bind::<&String>(&serde_json::to_string(&rule.actions).unwrap())
+            .bind::<&String>(&rule.stop.to_string())
+            .bind::<&String>(&rule.id)
+            .execute(c)
+            .await;
+
+        if let Err(e) = res {
+            return DefaultReturn {
+                success: false,
+                message: e.to_string(),
+                payload: Option::None,
+            };
+        }
+
+        self.base.cachedb.remove(String::from("rules")).await;
+        self.audit(&rule.id, editor, String::from("rule:update"))
+            .await;
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Rule updated!"),
+            payload: Option::Some(rule),
+        }
+    }
+
+    /// Delete a [`Rule`](crate::rules::Rule)
+    ///
+    /// # Arguments:
+    /// * `id` - `String` of the rule's `id`
+    /// * `editor` - the username of the user deleting the rule
+    pub async fn delete_rule(&self, id: String, editor: &str) -> DefaultReturn<Option<String>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"de_rules\" WHERE \"id\" = ?"
+        } else {
+            "DELETE FROM \"de_rules\" WHERE \"id\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        match sqlquery(query).bind::<&String>(&id).execute(c).await {
+            Ok(r) if r.rows_affected() > 0 => (),
+            Ok(_) => {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Rule does not exist"),
+                    payload: Option::None,
+                }
+            }
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        }
+
+        self.base.cachedb.remove(String::from("rules")).await;
+        self.audit(&id, editor, String::from("rule:delete")).await;
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Rule deleted!"),
+            payload: Option::Some(id),
+        }
+    }
+
+    // subjects
+    /// Replace the subjects of a [`Report`] (see [`crate::subjects`])
+    ///
+    /// # Arguments:
+    /// * `report` - the ID of the report
+    /// * `subjects` - the keys of its subjects
+    pub async fn set_report_subjects(
+        &self,
+        report: &str,
+        subjects: &[String],
+    ) -> Result<(), String> {
+        let (delete, insert): (&str, &str) =
+            if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+                (
+                    "DELETE FROM \"de_report_subjects\" WHERE \"report\" = ?",
+                    "INSERT INTO \"de_report_subjects\" VALUES (?, ?)",
+                )
+            } else {
+                (
+                    "DELETE FROM \"de_report_subjects\" WHERE \"report\" = $1",
+                    "INSERT INTO \"de_report_subjects\" VALUES ($1, $2)",
+                )
+            };
+
+        let c = &self.base.db.client;
+        let mut tx = c.begin().await.map_err(|e| e.to_string())?;
+
+        sqlquery(delete)
+            .bind::<&str>(report)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        for subject in subjects {
+            sqlquery(insert)
+                .bind::<&str>(report)
+                .bind::<&String>(subject)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        tx.commit().await.map_err(|e| e.to_string())
+    }
+
+    /// Get the subjects of a [`Report`] along with how many reports are about each (sorted)
+    ///
+    /// # Arguments:
+    /// * `report` - the ID of the report
+    pub async fn get_report_subjects(&self, report: &str) -> Vec<(String, usize)> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_report_subjects\" WHERE \"subject\" IN (SELECT \"subject\" FROM \"de_report_subjects\" WHERE \"report\" = ?)"
+        } else {
+            "SELECT * FROM \"de_report_subjects\" WHERE \"subject\" IN (SELECT \"subject\" FROM \"de_report_subjects\" WHERE \"report\" = $1)"
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(query).bind::<&str>(report).fetch_all(c).await {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("Failed to get report subjects: {e}");
+                return Vec::new();
+            }
+        };
+
+        let mut counts: std::collections::BTreeMap<String, usize> =
+            std::collections::BTreeMap::new();
+
+        for row in res {
+            if let Some(subject) = self.base.textify_row(row).data.remove("subject") {
+                *counts.entry(subject).or_insert(0) += 1;
+            }
+        }
+
+        counts.into_iter().collect()
+    }
+
+    /// Get every [`Report`] about a subject (newest first)
+    ///
+    /// # Arguments:
+    /// * `subject` - the key of the subject
+    pub async fn get_reports_by_subject(
+        &self,
+        subject: &str,
+    ) -> DefaultReturn<Option<Vec<Report>>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_reports\" WHERE \"id\" IN (SELECT \"report\" FROM \"de_report_subjects\" WHERE \"subject\" = ?) ORDER BY \"timestamp\" DESC"
+        } else {
+            "SELECT * FROM \"de_reports\" WHERE \"id\" IN (SELECT \"report\" FROM \"de_report_subjects\" WHERE \"subject\" = $1) ORDER BY \"timestamp\" DESC"
+        };
+
+        let c = &self.base.db.client;
+        let res = match sqlquery(query).bind::<&str>(subject).fetch_all(c).await {
+            Ok(r) => r,
+            Err(e) => {
+                return DefaultReturn {
+                    success: false,
+                    message: e.to_string(),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        DefaultReturn {
+            success: true,
+            message: String::from("Found reports"),
+            payload: Option::Some(
+                res.into_iter()
+                    .map(|row| Self::report_from_row(&self.base.textify_row(row).data))
+                    .collect(),
+            ),
+        }
+    }
+
+    // reporters
+    /// Get the [`ReporterStats`] of the given reporters (reporters without statistics are missing)
+    ///
+    /// # Arguments:
+    /// * `reporters` - the reporters to get
+    pub async fn get_reporter_stats(
+        &self,
+        reporters: &[String],
+    ) -> std::collections::HashMap<String, ReporterStats> {
+        let mut out: std::collections::HashMap<String, ReporterStats> =
+            std::collections::HashMap::new();
+
+        if reporters.is_empty() {
+            return out;
+        }
+
+        let query = format!(
+            "SELECT * FROM \"de_reporters\" WHERE \"reporter\" IN ({})",
+            (1..=reporters.len())
+                .map(|n| self.placeholder(n))
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        let mut query = sqlquery(&query);
+
+        for reporter in reporters {
+            query = query.bind::<&String>(reporter);
+        }
+
+        let c = &self.base.db.client;
+        let res = match query.fetch_all(c).await {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("Failed to get reporter stats: {e}");
+                return out;
+            }
+        };
+
+        for row in res {
+            let row = self.base.textify_row(row).data;
+            let number = |name: &str| {
+                row.get(name)
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .unwrap_or(0)
+            };
+
+            let stats = ReporterStats {
+                reporter: row.get("reporter").cloned().unwrap_or_default(),
+                reports: number("reports"),
+                accepted: number("accepted"),
+                spam: number("spam"),
+                trust: number("trust").min(100) as u8,
+                updated: row
+                    .get("updated")
+                    .and_then(|t| t.parse::<u128>().ok())
+                    .unwrap_or(0),
+            };
+
+            out.insert(stats.reporter.clone(), stats);
+        }
+
+        out
+    }
+
+    /// Replace the statistics of every reporter
+    ///
+    /// # Arguments:
+    /// * `stats` - the new [`ReporterStats`] of every reporter
+    pub async fn replace_reporter_stats(&self, stats: &[ReporterStats]) -> Result<(), String> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"de_reporters\" VALUES (?, ?, ?, ?, ?, ?)"
+        } else {
+            "INSERT INTO \"de_reporters\" VALUES ($1, $2, $3, $4, $5, $6)"
+        };
+
+        let c = &self.base.db.client;
+        let mut tx = c.begin().await.map_err(|e| e.to_string())?;
+
+        sqlquery("DELETE FROM \"de_reporters\"")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        for s in stats {
+            sqlquery(query)
+                .bind::<&String>(&s.reporter)
+                .bind::<&String>(&s.reports.to_string())
+                .bind::<&String>(&s.accepted.to_string())
+                .bind::<&String>(&s.spam.to_string())
+                // padded so trust scores sort as text
+                .bind::<&String>(&format!("{:03}", s.trust.min(100)))
+                .bind::<&String>(&s.updated.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        tx.commit().await.map_err(|e| e.to_string())?;
+
+        // reports sorted by trust have moved
+        self.base
+            .cachedb
+            .remove_starting_with("reports:offset*".to_string())
+            .await;
+
+        Ok(())
+    }
+
+    /// Get the number of reports filed by a reporter since the given time
+    ///
+    /// # Arguments:
+    /// * `reporter` - the reporter
+    /// * `since` - the earliest report timestamp counted
+    pub async fn count_reports_by_reporter(
+        &self,
+        reporter: &str,
+        since: u128,
+    ) -> Result<usize, String> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"timestamp\" FROM \"de_reports\" WHERE \"reporter\" = ? AND \"timestamp\" >= ?"
+        } else {
+            "SELECT \"timestamp\" FROM \"de_reports\" WHERE \"reporter\" = $1 AND \"timestamp\" >= $2"
+        };
+
+        let c = &self.base.db.client;
+        let rows = sqlquery(query)
+            .bind::<&str>(reporter)
+            .bind::<&String>(&format!("{since:013}"))
+            .fetch_all(c)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.len())
+    }
+
+    /// Get the number of active reports a reporter filed about a canonical address
+    ///
+    /// # Arguments:
+    /// * `canonical_address` - the canonical address (see [`crate::canonical`])
+    /// * `reporter` - the reporter
+    pub async fn count_active_reports_by_address(
+        &self,
+        canonical_address: &str,
+        reporter: &str,
+    ) -> Result<usize, String> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT \"id\" FROM \"de_reports\" WHERE \"canonical_address\" = ? AND \"reporter\" = ? AND \"report_status\" = ?"
+        } else {
+            "SELECT \"id\" FROM \"de_reports\" WHERE \"canonical_address\" = $1 AND \"reporter\" = $2 AND \"report_status\" = $3"
+        };
+
+        let c = &self.base.db.client;
+        let rows = sqlquery(query)
+            .bind::<&str>(canonical_address)
+            .bind::<&str>(reporter)
+            .bind::<&String>(&serde_json::to_string(&ReportStatus::Active).unwrap())
+            .fetch_all(c)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.len())
+    }
+
+    /// Count the reports filed by a reporter, and how many of them were marked as spam
+    ///
+    /// # Arguments:
+    /// * `reporter` - the reporter (see [`crate::reputation::reporter`])
+    /// * `author` - only count reports created as this author
+    ///
+    /// # Returns:
+    /// * `(reports, spam)`
+    pub async fn count_report_history(
+        &self,
+        reporter: &str,
+        author: Option<&str>,
+    ) -> Result<(usize, usize), String> {
+        let (text, placeholders) = match self.base.db._type.as_str() {
+            "sqlite" => ("TEXT", ["?", "?", "?"]),
+            "mysql" => ("CHAR", ["?", "?", "?"]),
+            _ => ("TEXT", ["$1", "$2", "$3"]),
+        };
+
+        // counts are cast to text so every database returns them the same way
+        let mut query = format!(
+            "SELECT CAST(COUNT(*) AS {text}) AS \"reports\", CAST(COALESCE(SUM(CASE WHEN \"report_status\" = {} THEN 1 ELSE 0 END), 0) AS {text}) AS \"spam\" FROM \"de_reports\" WHERE \"reporter\" = {}",
+            placeholders[0], placeholders[1]
+        );
+
+        if author.is_some() {
+            query.push_str(&format!(" AND \"author\" = {}", placeholders[2]));
+        }
+
+        let mut query = sqlquery(&query)
+            .bind::<String>(serde_json::to_string(&ReportStatus::Spam).unwrap())
+            .bind::<&str>(reporter);
+
+        if let Some(author) = author {
+            query = query.bind::<&str>(author);
+        }
+
+        let c = &self.base.db.client;
+        let row = query.fetch_one(c).await.map_err(|e| e.to_string())?;
+
+        let row = self.base.textify_row(row).data;
+        let count = |name: &str| {
+            row.get(name)
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(0)
+        };
+
+        Ok((count("reports"), count("spam")))
+    }
+
+    // feed tokens
+
+    /// Create a new feed token for the given user, replacing their existing token
+    ///
+    /// Returns the (unhashed) token.
+    ///
+    /// # Arguments:
+    /// * `username` - the username of the user the token belongs to
+    pub async fn create_feed_token(&self, username: String) -> DefaultReturn<Option<String>> {
+        let c = &self.base.db.client;
+
+        // remove existing token
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "DELETE FROM \"de_feed_tokens\" WHERE \"username\" = ?"
+        } else {
+            "DELETE FROM \"de_feed_tokens\" WHERE \"username\" = $1"
+        };
+
+        if let Err(e) = sqlquery(query).bind::<&String>(&username).execute(c).await {
+            return DefaultReturn {
+                success: false,
+                message: e.to_string(),
+                payload: Option::None,
+            };
+        }
+
+        // create token
+        let token = dorsal::utility::random_id();
+
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "INSERT INTO \"de_feed_tokens\" VALUES (?, ?, ?)"
+        } else {
+            "INSERT INTO \"de_feed_tokens\" VALUES ($1, $2, $3)"
+        };
+
+        let res = sqlquery(query)
+            .bind::<&String>(&dorsal::utility::hash(token.clone()))
+            .bind::<&String>(&username)
+            .bind::<&String>(&dorsal::utility::unix_epoch_timestamp().to_string())
+            .execute(c)
+            .await;
+
+        if let Err(e) = res {
+            return DefaultReturn {
+                success: false,
+                message: e.to_string(),
+                payload: Option::None,
+            };
+        }
+
+        // return
+        DefaultReturn {
+            success: true,
+            message: String::from("Feed token created!"),
+            payload: Option::Some(token),
+        }
+    }
+
+    /// Get the user a feed token belongs to
+    ///
+    /// # Arguments:
+    /// * `token` - the unhashed feed token
+    pub async fn get_feed_token_user(
+        &self,
+        token: String,
+    ) -> DefaultReturn<Option<dorsal::db::special::auth_db::FullUser<String>>> {
+        let query: &str = if (self.base.db._type == "sqlite") | (self.base.db._type == "mysql") {
+            "SELECT * FROM \"de_feed_tokens\" WHERE \"token_hashed\" = ?"
+        } else {
+            "SELECT * FROM \"de_feed_tokens\" WHERE \"token_hashed\" = $1"
+        };
+
+        let c = &self.base.db.client;
+        let res = sqlquery(query)
+            .bind::<&String>(&dorsal::utility::hash(token))
+            .fetch_one(c)
+            .await;
+
+        let row = match res {
+            Ok(r) => self.base.textify_row(r).data,
+            Err(_) => {
+                return DefaultReturn {
+                    success: false,
+                    message: String::from("Invalid feed token"),
+                    payload: Option::None,
+                }
+            }
+        };
+
+        self.auth
+            .get_user_by_username(row.get("username").unwrap().to_string())
+            .await
+    }
+}
+
+/// Helpers for tests which need a [`Database`]
+#[cfg(all(test, not(any(feature = "postgres", feature = "mysql"))))]
+pub mod testing {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+
+    type Store = Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>;
+
+    /// Read a single command from a redis client
+    fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<Vec<u8>>> {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+
+        let count = line.trim().strip_prefix('*')?.parse::<usize>().ok()?;
+        let mut args: Vec<Vec<u8>> = Vec::new();
+
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line).ok()?;
+
+            let length = line.trim().strip_prefix('$')?.parse::<usize>().ok()?;
+            let mut arg = vec![0; length + 2];
+            reader.read_exact(&mut arg).ok()?;
+            arg.truncate(length);
+            args.push(arg);
+        }
+
+        Some(args)
+    }
+
+    fn bulk(value: Option<&Vec<u8>>) -> Vec<u8> {
+        match value {
+            Some(v) => [format!("${}\r\n", v.len()).as_bytes(), v, b"\r\n"].concat(),
+            None => b"$-1\r\n".to_vec(),
+        }
+    }
+
+    /// Answer redis commands from a connection (only what [`dorsal::CacheDB`] uses)
+    fn serve_redis(stream: TcpStream, store: Store) {
+        let mut writer = stream.try_clone().unwrap();
+        let mut reader = BufReader::new(stream);
+
+        while let Some(args) = read_command(&mut reader) {
+            let mut store = store.lock().unwrap();
+            let command = String::from_utf8_lossy(&args[0]).to_uppercase();
+
+            let out = match command.as_str() {
+                "GET" => bulk(store.get(&args[1])),
+                "SET" => {
+                    store.insert(args[1].clone(), args[2].clone());
+                    b"+OK\r\n".to_vec()
+                }
+                "DEL" => {
+                    let removed = args[1..]
+                        .iter()
+                        .filter(|k| store.remove(*k).is_some())
+                        .count();
+                    format!(":{removed}\r\n").into_bytes()
+                }
+                "KEYS" => {
+                    // only prefix patterns are used
+                    let prefix = args[1].strip_suffix(b"*").unwrap_or(&args[1]);
+                    let keys: Vec<&Vec<u8>> =
+                        store.keys().filter(|k| k.starts_with(prefix)).collect();
+
+                    let mut out = format!("*{}\r\n", keys.len()).into_bytes();
+                    for key in keys {
+                        out.extend(bulk(Some(key)));
+                    }
+
+                    out
+                }
+                _ => b"+OK\r\n".to_vec(),
+            };
+
+            if writer.write_all(&out).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Start an in-memory redis server, returning its address
+    fn redis() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let store: Store = Arc::new(Mutex::new(HashMap::new()));
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let store = store.clone();
+                std::thread::spawn(move || serve_redis(stream, store));
+            }
+        });
+
+        format!("redis://{addr}")
+    }
+
+    /// Create an empty, initialized [`Database`] in a temporary sqlite file
+    pub async fn database() -> Database {
+        let path = std::env::temp_dir().join(format!("deducktive-{}.db", dorsal::utility::uuid()));
+        let client = sqlx::SqlitePool::connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+
+        let db = Database::from_base(dorsal::StarterDatabase {
+            db: dorsal::db::sql::Database {
+                client,
+                _type: String::from("sqlite"),
+            },
+            options: dorsal::DatabaseOpts {
+                _type: None,
+                host: None,
+                user: String::new(),
+                pass: String::new(),
+                name: String::new(),
+            },
+            cachedb: dorsal::CacheDB {
+                client: redis::Client::open(redis()).unwrap(),
+            },
+        });
+
+        assert!(db.init().await);
+        db
+    }
+
+    /// Create a report with the given author and reporter
+    pub async fn report(db: &Database, author: &str, reporter: &str) -> Report {
+        db.create_report(&mut Report {
+            report_type: ReportType::Abuse,
+            status: ReportStatus::Active,
+            author: author.to_string(),
+            reporter: reporter.to_string(),
+            content: String::from("This page contains targeted harassment"),
+            // every report is about another page, so none are duplicates
+            address: format!("https://example.com/{}", dorsal::utility::random_id()),
+            ..Default::default()
+        })
+        .await
+        .payload
+        .unwrap()
+    }
+}
+
+#[cfg(all(test, not(any(feature = "postgres", feature = "mysql"))))]
+mod tests {
+    use super::testing;
+    use super::*;
+
+    #[actix_web::test]
+    async fn backfill_reporters() {
+        let db = testing::database().await;
+        let c = &db.base.db.client;
+
+        sqlquery("INSERT INTO \"Users\" (\"username\") VALUES ('alice')")
+            .execute(c)
+            .await
+            .unwrap();
+
+        let real = testing::report(&db, "alice", "").await;
+        let made_up = testing::report(&db, "mallory", "").await;
+
+        // the reporter migration
+        sqlquery(MIGRATIONS[9]).execute(c).await.unwrap();
+
+        for (report, reporter) in [(real, "alice"), (made_up, "")] {
+            let report = db.get_report_by_id(report.id).await.payload.unwrap();
+            assert_eq!(report.reporter, reporter);
+        }
+    }
+
+    #[actix_web::test]
+    async fn count_history() {
+        let db = testing::database().await;
+
+        let spam = testing::report(&db, "alice", "alice").await;
+        testing::report(&db, "alice", "alice").await;
+        // created as alice by someone else
+        testing::report(&db, "alice", "anon:0123456789abcdef").await;
+
+        db.apply_bulk_action(
+            vec![spam.id],
+            None,
+            BulkAction::Status(ReportStatus::Spam),
+            "staff",
+        )
+        .await;
+
+        assert_eq!(db.count_report_history("alice", None).await, Ok((2, 1)));
+        assert_eq!(
+            db.count_report_history("alice", Some("alice")).await,
+            Ok((2, 1))
+        );
+        assert_eq!(
+            db.count_report_history("anon:0123456789abcdef", Some("alice"))
+                .await,
+            Ok((1, 0))
+        );
+        assert_eq!(db.count_report_history("bob", None).await, Ok((0, 0)));
+    }
+
+    #[actix_web::test]
+    async fn erase_many_reports() {
+        let db = testing::database().await;
+
+        for i in 0..(MAX_BULK_REPORTS + 5) {
+            // half are filed by them, half created as them
+            if i % 2 == 0 {
+                testing::report(&db, "alice", "alice").await;
+            } else {
+                testing::report(&db, "", "alice").await;
+            }
+        }
+
+        let other = testing::report(&db, "bob", "bob").await;
+
+        db.replace_reporter_stats(&[ReporterStats {
+            reporter: String::from("alice"),
+            reports: MAX_BULK_REPORTS + 5,
+            ..Default::default()
+        }])
+        .await
+        .unwrap();
+
+        let res = db.erase_person("alice", "staff").await;
+        assert!(res.success, "{}", res.message);
+        assert_eq!(res.payload.unwrap().len(), MAX_BULK_REPORTS + 5);
+
+        let left = db
+            .get_every_report_by_filter(&ReportFilter {
+                person: Some(String::from("alice")),
+                ..Default::default()
+            })
+            .await
+            .payload
+            .unwrap();
+
+        assert!(left.is_empty());
+        assert!(db
+            .get_reporter_stats(&[String::from("alice")])
+            .await
+            .is_empty());
+
+        // nobody else is affected
+        let other = db.get_report_by_id(other.id).await.payload.unwrap();
+        assert_eq!(other.author, "bob");
+        assert_ne!(other.content, REDACTED_CONTENT);
+    }
+
+    #[actix_web::test]
+    async fn erase_nobody() {
+        let db = testing::database().await;
+        assert!(!db.erase_person("", "staff").await.success);
+    }
 }
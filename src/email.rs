@@ -0,0 +1,356 @@
+//! SMTP staff notifications
+use askama::Template;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::sync::{Arc, Mutex};
+
+use crate::config::{EmailConfig, EmailMode, SmtpSecurity};
+use crate::db::Report;
+use crate::notify::ReportEvent;
+
+/// The most events kept for the next digest, older events are dropped once it's full (so the
+/// queue can't grow without limit while digests fail or aren't sent)
+pub const MAX_DIGEST_ENTRIES: usize = 500;
+
+#[derive(Template)]
+#[template(path = "email/report.txt")]
+struct ReportEmailTemplate<'a> {
+    report: &'a Report,
+    action: &'a str,
+    reason: &'a str,
+    base_url: &'a str,
+}
+
+pub struct DigestEntry {
+    pub report: Report,
+    pub action: String,
+    pub reason: String,
+}
+
+#[derive(Template)]
+#[template(path = "email/digest.txt")]
+struct DigestEmailTemplate<'a> {
+    entries: &'a [DigestEntry],
+    /// The number of events dropped because the queue was full
+    dropped: usize,
+    base_url: &'a str,
+}
+
+/// Events waiting for the next digest
+#[derive(Default)]
+struct DigestQueue {
+    entries: Vec<DigestEntry>,
+    /// The number of events dropped because the queue was full
+    dropped: usize,
+}
+
+/// Sends [`ReportEvent`]s to staff by email
+#[derive(Clone)]
+pub struct Mailer {
+    pub config: EmailConfig,
+    pub base_url: String,
+    transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    /// Events waiting for the next digest
+    queue: Arc<Mutex<DigestQueue>>,
+}
+
+impl Mailer {
+    /// Create a new [`Mailer`], no emails are sent if email is disabled
+    ///
+    /// # Arguments:
+    /// * `config` - [`EmailConfig`]
+    /// * `base_url` - the public URL of this server, used for links
+    pub fn new(config: EmailConfig, base_url: String) -> Mailer {
+        let transport = if config.enabled {
+            let builder = match config.security {
+                SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                    &config.host,
+                )),
+                SmtpSecurity::StartTls => {
+                    AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+                }
+                SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host),
+            };
+
+            let mut builder = match builder {
+                Ok(b) => b.port(config.port),
+                Err(e) => panic!("Failed to create SMTP transport: {e}"),
+            };
+
+            if !config.username.is_empty() {
+                builder = builder.credentials(Credentials::new(
+                    config.username.clone(),
+                    config.password.clone(),
+                ));
+            }
+
+            Some(builder.build())
+        } else {
+            None
+        };
+
+        Mailer {
+            config,
+            base_url,
+            transport,
+            queue: Arc::new(Mutex::new(DigestQueue::default())),
+        }
+    }
+
+    /// Handle a [`ReportEvent`], either sending it now or queueing it for the next digest
+    pub async fn notify(&self, event: ReportEvent) {
+        if self.transport.is_none() {
+            return;
+        }
+
+        let entry = DigestEntry {
+            report: event.report().to_owned(),
+            action: event.action().to_string(),
+            reason: event.reason().to_string(),
+        };
+
+        if self.config.mode == EmailMode::Digest {
+            let mut queue = self.queue.lock().unwrap();
+
+            if queue.entries.len() >= MAX_DIGEST_ENTRIES {
+                queue.entries.remove(0);
+                queue.dropped += 1;
+            }
+
+            queue.entries.push(entry);
+            return;
+        }
+
+        let body = ReportEmailTemplate {
+            report: &entry.report,
+            action: &entry.action,
+            reason: &entry.reason,
+            base_url: &self.base_url,
+        }
+        .render()
+        .unwrap();
+
+        self.send(
+            format!(
+                "{} {} report {}",
+                self.config.subject_prefix, entry.report.report_type, entry.action
+            ),
+            body,
+        )
+        .await;
+    }
+
+    /// Send every queued event in a single email, returns the number of sent events
+    pub async fn send_digest(&self) -> usize {
+        let queue: DigestQueue = std::mem::take(&mut *self.queue.lock().unwrap());
+
+        if queue.entries.is_empty() {
+            return 0;
+        }
+
+        let body = DigestEmailTemplate {
+            entries: &queue.entries,
+            dropped: queue.dropped,
+            base_url: &self.base_url,
+        }
+        .render()
+        .unwrap();
+
+        self.send(
+            format!(
+                "{} {} report update(s)",
+                self.config.subject_prefix,
+                queue.entries.len() + queue.dropped
+            ),
+            body,
+        )
+        .await;
+
+        queue.entries.len()
+    }
+
+    /// Send an email to every configured staff address
+    async fn send(&self, subject: String, body: String) {
+        let transport = match &self.transport {
+            Some(t) => t,
+            None => return,
+        };
+
+        let from = match self.config.from.parse::<Mailbox>() {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("Invalid email sender: {e}");
+                return;
+            }
+        };
+
+        for to in &self.config.to {
+            let to = match to.parse::<Mailbox>() {
+                Ok(t) => t,
+                Err(e) => {
+                    eprintln!("Invalid email recipient \"{to}\": {e}");
+                    continue;
+                }
+            };
+
+            let message = match Message::builder()
+                .from(from.clone())
+                .to(to)
+                .subject(subject.trim())
+                .body(body.clone())
+            {
+                Ok(m) => m,
+                Err(e) => {
+                    eprintln!("Failed to build email: {e}");
+                    continue;
+                }
+            };
+
+            if let Err(e) = transport.send(message).await {
+                eprintln!("Failed to send email: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::ReportType;
+    use actix_web::rt::net::TcpListener;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    /// Start a local SMTP sink, returning its port and the messages it receives
+    async fn sink() -> (u16, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let messages: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let received = messages.clone();
+
+        actix_web::rt::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                let received = received.clone();
+
+                actix_web::rt::spawn(async move {
+                    write.write_all(b"220 sink\r\n").await.unwrap();
+
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let command = line.to_uppercase();
+
+                        let reply: &[u8] = if command.starts_with("DATA") {
+                            write.write_all(b"354 go ahead\r\n").await.unwrap();
+
+                            let mut message = String::new();
+                            while let Ok(Some(line)) = lines.next_line().await {
+                                if line == "." {
+                                    break;
+                                }
+
+                                message.push_str(&line);
+                                message.push('\n');
+                            }
+
+                            received.lock().unwrap().push(message);
+                            b"250 queued\r\n"
+                        } else if command.starts_with("QUIT") {
+                            write.write_all(b"221 bye\r\n").await.unwrap();
+                            break;
+                        } else {
+                            b"250 ok\r\n"
+                        };
+
+                        write.write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+
+        (port, messages)
+    }
+
+    fn mailer(port: u16, mode: EmailMode) -> Mailer {
+        Mailer::new(
+            EmailConfig {
+                enabled: true,
+                host: String::from("127.0.0.1"),
+                port,
+                security: SmtpSecurity::None,
+                from: String::from("Deducktive <deducktive@example.com>"),
+                to: vec![String::from("staff@example.com")],
+                mode,
+                ..Default::default()
+            },
+            String::from("https://reports.example.com"),
+        )
+    }
+
+    fn report(id: &str) -> Report {
+        Report {
+            id: id.to_string(),
+            report_type: ReportType::Abuse,
+            content: String::from("Buy cheap watches"),
+            address: String::from("https://example.com/post/1"),
+            ..Default::default()
+        }
+    }
+
+    #[actix_web::test]
+    async fn sends_reports() {
+        let (port, messages) = sink().await;
+
+        mailer(port, EmailMode::Each)
+            .notify(ReportEvent::Created(report("r1")))
+            .await;
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("Subject: [Deducktive] Abuse report created"));
+        assert!(messages[0].contains("Buy cheap watches"));
+        assert!(messages[0].contains("https://reports.example.com/report/r1"));
+    }
+
+    #[actix_web::test]
+    async fn sends_digests() {
+        let (port, messages) = sink().await;
+        let mailer = mailer(port, EmailMode::Digest);
+
+        mailer.notify(ReportEvent::Created(report("r1"))).await;
+        mailer
+            .notify(ReportEvent::Escalated(report("r2"), String::from("SLA")))
+            .await;
+
+        assert!(messages.lock().unwrap().is_empty());
+        assert_eq!(mailer.send_digest().await, 2);
+        assert_eq!(mailer.send_digest().await, 0);
+
+        let messages = messages.lock().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("Subject: [Deducktive] 2 report update(s)"));
+        assert!(messages[0].contains("Abuse report escalated: https://example.com/post/1"));
+        assert!(messages[0].contains("Reason: SLA"));
+        assert!(messages[0].contains("https://reports.example.com/report/r2"));
+    }
+
+    #[actix_web::test]
+    async fn caps_digests() {
+        let (port, messages) = sink().await;
+        let mailer = mailer(port, EmailMode::Digest);
+
+        for i in 0..(MAX_DIGEST_ENTRIES + 5) {
+            mailer
+                .notify(ReportEvent::Created(report(&format!("r{i}"))))
+                .await;
+        }
+
+        assert_eq!(mailer.send_digest().await, MAX_DIGEST_ENTRIES);
+
+        let messages = messages.lock().unwrap();
+        assert!(messages[0].contains("5 older update(s) are left out"));
+        assert!(!messages[0].contains("/report/r4\n"));
+        assert!(messages[0].contains("/report/r5\n"));
+    }
+}
@@ -0,0 +1,73 @@
+//! The embed form and its `postMessage` protocol
+//!
+//! Sites show the form at `/api/v1/reports/embed` in an iframe, usually through the host SDK
+//! (`/static/js/Embed.js`), which opens it in a modal:
+//!
+//! ```html
+//! <script type="module">
+//!     import { open } from "https://reports.example.com/static/js/Embed.js";
+//!
+//!     open({
+//!         as_user: "alice",
+//!         report_type: "Harassment",
+//!         on_submitted: (event) => console.log(event.tracking),
+//!     });
+//! </script>
+//! ```
+//!
+//! The embed accepts the `site` (origin of the host page, see
+//! [`crate::config::SiteConfig`]) and `lang` (see [`crate::i18n`]) query parameters.
+//!
+//! # Protocol
+//!
+//! Every message is an object with `protocol` set to `"deducktive"`, `version` set to
+//! [`PROTOCOL_VERSION`] and a `type`. Messages with another protocol or version are ignored.
+//!
+//! Messages from the host page are only accepted from [`allowed_origins`] (every origin when no
+//! sites are configured). The embed only posts messages to the origin of the host page (taken
+//! from its first accepted message, or from the referrer before that).
+//!
+//! ## Host to embed
+//!
+//! * `init` - prefill the form, every property is optional:
+//!     * `address` - the reported address (required to submit, the SDK defaults to the host page)
+//!     * `as_user` - the username of the reporter
+//!     * `report_type` - the selected [`crate::db::ReportType`]
+//!     * `content` - the report content
+//!     * `fields` - the values of custom fields by name (see [`crate::fields`])
+//!
+//! ## Embed to host
+//!
+//! * `ready` - the embed is loaded and waiting for `init` (posted to every allowed origin)
+//! * `submitted` - a report was created, with its `tracking` token (left out when no sites are
+//!   configured, since any page can embed the form then)
+//! * `cancelled` - the reporter closed the form without submitting
+//! * `resize` - the content of the embed changed size, with its new `height` in pixels
+//!
+//! ## Version 0
+//!
+//! Embeds before this protocol accepted `{ assign: "REAL_HREF" | "REPORT_AS_USER", value }`
+//! messages (the same as `init` with `address` or `as_user`). These are still accepted, from
+//! allowed origins only.
+use crate::config::SiteConfig;
+
+/// The version of the `postMessage` protocol
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Get the origins the embed accepts messages from (any origin if empty)
+///
+/// The embed of a configured site only talks to that site, embeds without a known site talk to
+/// every configured site.
+///
+/// # Arguments:
+/// * `sites` - the configured [`SiteConfig`]s
+/// * `site` - the [`SiteConfig`] of the embed's `site` parameter (if it has one)
+pub fn allowed_origins(sites: &[SiteConfig], site: Option<&SiteConfig>) -> Vec<String> {
+    match site {
+        Some(s) => crate::canonical::origin(&s.origin).into_iter().collect(),
+        None => sites
+            .iter()
+            .filter_map(|s| crate::canonical::origin(&s.origin))
+            .collect(),
+    }
+}
@@ -0,0 +1,98 @@
+//! Custom report fields and embed theming of configured sites
+//!
+//! Sites can ask extra questions in the embed form for some report types (ex: "Impersonating
+//! who?" for impersonation reports). Values are checked against the site's [`FieldConfig`]s when
+//! a report is created and stored on the [`crate::db::Report`] by field name.
+use std::collections::BTreeMap;
+
+use crate::config::{FieldConfig, FieldKind, SiteConfig};
+use crate::db::ReportType;
+
+/// If a field is asked for reports of the given type
+pub fn applies(field: &FieldConfig, report_type: &ReportType) -> bool {
+    field.report_types.is_empty() || field.report_types.contains(report_type)
+}
+
+/// Check the custom field values of a new report, returning the values to store
+///
+/// Values are trimmed and empty optional values are left out.
+///
+/// # Arguments:
+/// * `site` - the [`SiteConfig`] of the reported address (`None` if it has none)
+/// * `report_type` - the [`ReportType`] of the report
+/// * `values` - the submitted values by field name
+pub fn validate(
+    site: Option<&SiteConfig>,
+    report_type: &ReportType,
+    values: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, String> {
+    let fields: Vec<&FieldConfig> = match site {
+        Some(s) => s
+            .fields
+            .iter()
+            .filter(|f| applies(f, report_type))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    if let Some(name) = values
+        .keys()
+        .find(|n| !fields.iter().any(|f| &&f.name == n))
+    {
+        return Err(format!("Unknown field: {name}"));
+    }
+
+    let mut out: BTreeMap<String, String> = BTreeMap::new();
+
+    for field in fields {
+        let value = values
+            .get(&field.name)
+            .map(|v| v.trim())
+            .unwrap_or_default();
+
+        if value.is_empty() {
+            if field.required {
+                return Err(format!("{} is required", field.label));
+            }
+
+            continue;
+        }
+
+        if value.chars().count() > field.max_length {
+            return Err(format!("{} is too long", field.label));
+        }
+
+        if (field.kind == FieldKind::Select) && !field.options.iter().any(|o| o == value) {
+            return Err(format!("{} is invalid", field.label));
+        }
+
+        out.insert(field.name.clone(), value.to_string());
+    }
+
+    Ok(out)
+}
+
+/// Get the CSS declarations of a site theme (ex: `--color-primary: #2e7d32;`)
+///
+/// Only custom properties (`--name`) with simple values (colors, lengths and font names) are
+/// kept, so themes can't break out of the embed's `style` element.
+pub fn theme_css(theme: &BTreeMap<String, String>) -> String {
+    theme
+        .iter()
+        .filter(|(name, _)| {
+            name.starts_with("--")
+                && (name.len() > 2)
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || (c == '-') || (c == '_'))
+        })
+        .filter(|(_, value)| {
+            !value.trim().is_empty()
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || " #%.,()-_\"".contains(c))
+        })
+        .map(|(name, value)| format!("{name}: {};", value.trim()))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
@@ -0,0 +1,270 @@
+//! Translations of the dashboard and embed form
+//!
+//! Catalogs are flat JSON objects of message keys and their text, one file per locale
+//! (`locales/<locale>.json`). The catalogs in `locales/` are built in, more can be loaded from the
+//! configured directory. Every page negotiates its locale from the `lang` query parameter or the
+//! `Accept-Language` header, messages missing from a catalog fall back to the default locale.
+use actix_web::HttpRequest;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use crate::config::I18nConfig;
+
+/// The messages of a single locale
+pub type Catalog = HashMap<String, String>;
+
+/// The catalogs built into the binary
+pub const BUILT_IN: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("es", include_str!("../locales/es.json")),
+    ("fr", include_str!("../locales/fr.json")),
+];
+
+/// Every loaded catalog
+#[derive(Clone, Default)]
+pub struct Catalogs {
+    catalogs: HashMap<String, Arc<Catalog>>,
+    default_locale: String,
+}
+
+impl Catalogs {
+    /// Load the built-in catalogs and the catalogs of the configured directory
+    ///
+    /// # Arguments:
+    /// * `config` - [`I18nConfig`]
+    pub fn load(config: &I18nConfig) -> Catalogs {
+        let mut catalogs: HashMap<String, Catalog> = HashMap::new();
+
+        for (locale, source) in BUILT_IN {
+            match serde_json::from_str::<Catalog>(source) {
+                Ok(c) => catalogs.entry(locale.to_string()).or_default().extend(c),
+                Err(e) => panic!("Built-in catalog {locale} is invalid: {e}"),
+            }
+        }
+
+        if !config.directory.is_empty() {
+            let entries = match std::fs::read_dir(&config.directory) {
+                Ok(e) => e.filter_map(|e| e.ok()).collect::<Vec<_>>(),
+                Err(e) => panic!("Failed to read catalog directory: {e}"),
+            };
+
+            for path in entries.iter().map(|e| e.path()) {
+                if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                    continue;
+                }
+
+                let locale = match path.file_stem().and_then(|s| s.to_str()) {
+                    Some(l) => l.to_lowercase(),
+                    None => continue,
+                };
+
+                match std::fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|c| serde_json::from_str::<Catalog>(&c).map_err(|e| e.to_string()))
+                {
+                    Ok(c) => catalogs.entry(locale).or_default().extend(c),
+                    Err(e) => panic!("Failed to load catalog {}: {e}", path.display()),
+                }
+            }
+        }
+
+        let default_locale = config.default_locale.to_lowercase();
+        if !catalogs.contains_key(&default_locale) {
+            panic!("Default locale {default_locale} has no catalog");
+        }
+
+        Catalogs {
+            catalogs: catalogs
+                .into_iter()
+                .map(|(l, c)| (l, Arc::new(c)))
+                .collect(),
+            default_locale,
+        }
+    }
+
+    /// Get the locale which has a catalog for a language tag (ex: `de` for `de-AT`)
+    fn find(&self, tag: &str) -> Option<String> {
+        let tag = tag.trim().to_lowercase().replace('_', "-");
+
+        if self.catalogs.contains_key(&tag) {
+            return Some(tag);
+        }
+
+        let primary = tag.split('-').next().unwrap_or_default();
+        if self.catalogs.contains_key(primary) {
+            return Some(primary.to_string());
+        }
+
+        None
+    }
+
+    /// Get the [`Translator`] of the best locale
+    ///
+    /// # Arguments:
+    /// * `requested` - an explicitly requested locale (ex: the `lang` embed parameter)
+    /// * `accept_language` - the value of the `Accept-Language` header
+    pub fn negotiate(&self, requested: Option<&str>, accept_language: Option<&str>) -> Translator {
+        let locale = requested
+            .and_then(|l| self.find(l))
+            .or_else(|| {
+                accept_language
+                    .map(parse_accept_language)
+                    .unwrap_or_default()
+                    .iter()
+                    .find_map(|l| self.find(l))
+            })
+            .unwrap_or(self.default_locale.clone());
+
+        self.translator(&locale)
+    }
+
+    /// Get the [`Translator`] of a locale (the default locale if it has no catalog)
+    pub fn translator(&self, locale: &str) -> Translator {
+        let fallback = self
+            .catalogs
+            .get(&self.default_locale)
+            .cloned()
+            .unwrap_or_default();
+
+        match self.catalogs.get(locale) {
+            Some(c) => Translator {
+                locale: locale.to_string(),
+                catalog: c.clone(),
+                fallback,
+            },
+            None => Translator {
+                locale: self.default_locale.clone(),
+                catalog: fallback.clone(),
+                fallback,
+            },
+        }
+    }
+
+    /// Get the [`Translator`] for a request, from its `lang` query parameter or its
+    /// `Accept-Language` header
+    pub fn request(&self, req: &HttpRequest) -> Translator {
+        let requested = url::form_urlencoded::parse(req.query_string().as_bytes())
+            .find(|(k, _)| k == "lang")
+            .map(|(_, v)| v.to_string());
+
+        let accept_language = req
+            .headers()
+            .get("Accept-Language")
+            .and_then(|h| h.to_str().ok());
+
+        self.negotiate(requested.as_deref(), accept_language)
+    }
+}
+
+/// Get the language tags of an `Accept-Language` header, most preferred first
+///
+/// Tags with a quality of `0` and the `*` wildcard are left out.
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut tags: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|part| {
+            let mut parts = part.split(';');
+            let tag = parts.next()?.trim();
+
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map(|q| q.trim().parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+
+            if tag.is_empty() || (tag == "*") || (quality <= 0.0) {
+                return None;
+            }
+
+            Some((tag.to_string(), quality))
+        })
+        .collect();
+
+    // stable, so tags of the same quality keep their order
+    tags.sort_by(|a, b| b.1.total_cmp(&a.1));
+    tags.into_iter().map(|(t, _)| t).collect()
+}
+
+/// The messages of the negotiated locale, used by templates
+#[derive(Clone, Default)]
+pub struct Translator {
+    /// The negotiated locale (ex: `en`)
+    pub locale: String,
+    catalog: Arc<Catalog>,
+    fallback: Arc<Catalog>,
+}
+
+impl Translator {
+    /// Get a message (the message of the default locale if it isn't translated, or the key if it
+    /// doesn't exist)
+    pub fn t<'a>(&'a self, key: &'a str) -> &'a str {
+        self.catalog
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map(|m| m.as_str())
+            .unwrap_or(key)
+    }
+
+    /// Get the label of a value (ex: `label("report_type", ReportType::Abuse)` is the message
+    /// `report_type.Abuse`)
+    pub fn label(&self, group: &str, value: &impl Display) -> String {
+        self.t(&format!("{group}.{value}")).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_language() {
+        assert_eq!(
+            parse_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5"),
+            vec!["fr-CH", "fr", "en", "de"]
+        );
+        assert_eq!(
+            parse_accept_language("en;q=0.2, de , es;q=0.9, fr;q=0.9"),
+            vec!["de", "es", "fr", "en"]
+        );
+        assert_eq!(
+            parse_accept_language("de;q=0, en;q=abc, *"),
+            Vec::<String>::new()
+        );
+        assert_eq!(parse_accept_language(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn negotiate_locales() {
+        let catalogs = Catalogs::load(&I18nConfig::default());
+        let locale = |requested: Option<&str>, header: Option<&str>| {
+            catalogs.negotiate(requested, header).locale
+        };
+
+        assert_eq!(locale(None, None), "en");
+        assert_eq!(locale(None, Some("de")), "de");
+        assert_eq!(locale(None, Some("de-AT")), "de");
+        assert_eq!(locale(None, Some("ES_mx")), "es");
+        assert_eq!(locale(None, Some("ja, fr;q=0.5, de;q=0.8")), "de");
+
+        // `*` and unknown locales fall back to the default locale
+        assert_eq!(locale(None, Some("*")), "en");
+        assert_eq!(locale(None, Some("ja, zh-TW")), "en");
+        assert_eq!(locale(Some("ja"), None), "en");
+
+        // the `lang` parameter wins over the header, unless it's unknown
+        assert_eq!(locale(Some("fr"), Some("de")), "fr");
+        assert_eq!(locale(Some("ja"), Some("de")), "de");
+    }
+
+    #[test]
+    fn labels() {
+        let catalogs = Catalogs::load(&I18nConfig::default());
+        let de = catalogs.translator("de");
+
+        assert_eq!(catalogs.translator("ja").locale, "en");
+        assert_eq!(de.t("missing.key"), "missing.key");
+        assert_ne!(de.label("report_type", &"Abuse"), "report_type.Abuse");
+        assert_ne!(de.label("report_status", &"Spam"), "report_status.Spam");
+    }
+}
@@ -0,0 +1,293 @@
+//! In-process scheduler for periodic background work
+//!
+//! Job state is stored in the database so schedules survive restarts. Exclusive jobs are only
+//! run by the instance holding their database lock, so running multiple replicas never runs
+//! them twice; local jobs (which only touch this instance's memory) run on every instance.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::attachments::AttachmentStore;
+use crate::config::{Config, EmailMode};
+use crate::db::{Database, JobRun};
+use crate::email::Mailer;
+
+/// How often the scheduler checks for due jobs
+pub const TICK: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How long a job lock is held if the instance running it stops renewing it (milliseconds)
+pub const LOCK_TIMEOUT: u128 = 15 * 60 * 1000;
+
+/// How often the lock of a running job is renewed, well within [`LOCK_TIMEOUT`]
+pub const LOCK_RENEWAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Every kind of scheduled job
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JobKind {
+    /// Enforce retention rules (see [`crate::retention`])
+    Retention,
+    /// Send queued digest emails (see [`Mailer::send_digest`])
+    EmailDigest,
+    /// Escalate reports breaching their SLA (see [`crate::sla`])
+    SlaEscalation,
+    /// Remove files of deleted attachments (see [`crate::attachments::cleanup`])
+    AttachmentCleanup,
+    /// Retrain the Bayesian spam model (see [`crate::bayes::retrain`])
+    BayesTraining,
+    /// Recalculate reporter trust scores (see [`crate::reputation::recalculate`])
+    Reputation,
+}
+
+impl JobKind {
+    /// The name the job is stored as
+    pub fn name(&self) -> &'static str {
+        match self {
+            JobKind::Retention => "retention",
+            JobKind::EmailDigest => "email_digest",
+            JobKind::SlaEscalation => "sla_escalation",
+            JobKind::AttachmentCleanup => "attachment_cleanup",
+            JobKind::BayesTraining => "bayes_training",
+            JobKind::Reputation => "reputation",
+        }
+    }
+
+    /// If the job may only run on one instance at a time
+    pub fn exclusive(&self) -> bool {
+        match self {
+            // digests are queued in memory, every instance sends its own
+            JobKind::EmailDigest => false,
+            _ => true,
+        }
+    }
+}
+
+/// A job and how often it runs
+#[derive(Clone)]
+pub struct Job {
+    pub kind: JobKind,
+    /// Milliseconds between runs
+    pub interval: u128,
+}
+
+/// Runs every configured [`Job`]
+#[derive(Clone)]
+pub struct Scheduler {
+    pub db: Database,
+    pub config: Config,
+    pub mailer: Mailer,
+    pub attachments: Arc<dyn AttachmentStore>,
+    pub jobs: Vec<Job>,
+    /// The ID of this instance (used for job locks)
+    pub instance: String,
+    /// The HTTP client shared by jobs (ex: for webhooks)
+    pub http_client: awc::Client,
+    /// The Bayesian spam model used by this instance, replaced after training
+    pub bayes: crate::bayes::ModelCache,
+}
+
+impl Scheduler {
+    /// Create a new [`Scheduler`] with every job enabled in the config
+    pub fn new(
+        db: Database,
+        config: Config,
+        mailer: Mailer,
+        attachments: Arc<dyn AttachmentStore>,
+        bayes: crate::bayes::ModelCache,
+    ) -> Scheduler {
+        let mut jobs: Vec<Job> = Vec::new();
+
+        if !config.retention.rules.is_empty() {
+            jobs.push(Job {
+                kind: JobKind::Retention,
+                interval: config.retention.interval.max(1) as u128 * 60_000,
+            });
+        }
+
+        if config.email.enabled && (config.email.mode == EmailMode::Digest) {
+            jobs.push(Job {
+                kind: JobKind::EmailDigest,
+                interval: config.email.digest_interval.max(1) as u128 * 60_000,
+            });
+        }
+
+        if !config.sla.targets.is_empty() {
+            jobs.push(Job {
+                kind: JobKind::SlaEscalation,
+                interval: config.sla.interval.max(1) as u128 * 60_000,
+            });
+        }
+
+        if config.attachments.enabled {
+            jobs.push(Job {
+                kind: JobKind::AttachmentCleanup,
+                interval: 60 * 60_000,
+            });
+        }
+
+        if config.bayes.enabled {
+            jobs.push(Job {
+                kind: JobKind::BayesTraining,
+                interval: config.bayes.interval.max(1) as u128 * 60_000,
+            });
+        }
+
+        if config.reputation.enabled {
+            jobs.push(Job {
+                kind: JobKind::Reputation,
+                interval: config.reputation.interval.max(1) as u128 * 60_000,
+            });
+        }
+
+        Scheduler {
+            db,
+            config,
+            mailer,
+            attachments,
+            jobs,
+            instance: dorsal::utility::uuid(),
+            http_client: awc::Client::default(),
+            bayes,
+        }
+    }
+
+    /// Start running jobs in the background once `ready` is set
+    pub fn start(self, ready: Arc<AtomicBool>) {
+        if self.jobs.is_empty() {
+            return;
+        }
+
+        actix_web::rt::spawn(async move {
+            let mut timer = actix_web::rt::time::interval(TICK);
+
+            // wait for migrations
+            loop {
+                timer.tick().await;
+
+                if ready.load(Ordering::SeqCst) {
+                    break;
+                }
+            }
+
+            for job in &self.jobs {
+                if let Err(e) = self.db.ensure_job(job.kind.name()).await {
+                    eprintln!("Failed to create job {}: {e}", job.kind.name());
+                }
+            }
+
+            // local jobs are scheduled in memory
+            let start = dorsal::utility::unix_epoch_timestamp();
+            let mut local_next: Vec<u128> = self.jobs.iter().map(|j| start + j.interval).collect();
+
+            loop {
+                for (i, job) in self.jobs.iter().enumerate() {
+                    let now = dorsal::utility::unix_epoch_timestamp();
+
+                    if !job.kind.exclusive() {
+                        if now >= local_next[i] {
+                            self.run(job).await;
+                            local_next[i] = now + job.interval;
+                        }
+
+                        continue;
+                    }
+
+                    if self
+                        .db
+                        .acquire_job(job.kind.name(), &self.instance, LOCK_TIMEOUT)
+                        .await
+                    {
+                        // jobs can outlast the lock (ex: a full table scan), so it's renewed
+                        // until they finish
+                        let renewal = {
+                            let db = self.db.clone();
+                            let instance = self.instance.clone();
+                            let name = job.kind.name();
+
+                            actix_web::rt::spawn(async move {
+                                let mut timer = actix_web::rt::time::interval(LOCK_RENEWAL);
+                                timer.tick().await;
+
+                                loop {
+                                    timer.tick().await;
+                                    db.renew_job(name, &instance, LOCK_TIMEOUT).await;
+                                }
+                            })
+                        };
+
+                        self.run(job).await;
+                        renewal.abort();
+
+                        self.db
+                            .release_job(
+                                job.kind.name(),
+                                &self.instance,
+                                dorsal::utility::unix_epoch_timestamp() + job.interval,
+                            )
+                            .await;
+                    }
+                }
+
+                timer.tick().await;
+            }
+        });
+    }
+
+    /// Run a single job and store its [`JobRun`]
+    async fn run(&self, job: &Job) {
+        let started = dorsal::utility::unix_epoch_timestamp();
+
+        let res: Result<String, String> = match job.kind {
+            JobKind::Retention => {
+                let changed = crate::retention::enforce(
+                    &self.db,
+                    &self.config.retention.rules,
+                    self.config.retention.dry_run,
+                )
+                .await;
+
+                Ok(format!("Changed {changed} report(s)"))
+            }
+            JobKind::EmailDigest => {
+                let sent = self.mailer.send_digest().await;
+                Ok(format!("Sent {sent} queued event(s)"))
+            }
+            JobKind::SlaEscalation => {
+                crate::sla::escalate(&self.db, &self.config, &self.mailer, &self.http_client)
+                    .await
+                    .map(|n| format!("Escalated {n} report(s)"))
+            }
+            JobKind::AttachmentCleanup => {
+                crate::attachments::cleanup(&self.db, self.attachments.clone())
+                    .await
+                    .map(|n| format!("Removed {n} file(s)"))
+            }
+            JobKind::BayesTraining => crate::bayes::retrain(&self.db).await.map(|m| {
+                let message = format!("Trained on {} spam and {} other decision(s)", m.spam, m.ham);
+                self.bayes.set(m);
+                message
+            }),
+            JobKind::Reputation => crate::reputation::recalculate(&self.db)
+                .await
+                .map(|n| format!("Updated {n} reporter(s)")),
+        };
+
+        let (success, message) = match res {
+            Ok(m) => (true, m),
+            Err(e) => {
+                eprintln!("Job {} failed: {e}", job.kind.name());
+                (false, e)
+            }
+        };
+
+        self.db
+            .create_job_run(&JobRun {
+                id: dorsal::utility::random_id(),
+                name: job.kind.name().to_string(),
+                instance: self.instance.clone(),
+                started,
+                finished: dorsal::utility::unix_epoch_timestamp(),
+                success,
+                message,
+            })
+            .await;
+    }
+}
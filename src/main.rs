@@ -1,11 +1,37 @@
 use actix_files as fs;
-use actix_web::{web, App, HttpServer};
+use actix_web::dev::Service;
+use actix_web::{web, App, HttpResponse, HttpServer};
 use db::Database;
 use dotenv;
+use futures_util::future::{self, Either, FutureExt};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+pub mod activitypub;
 pub mod api;
+pub mod attachments;
+pub mod bayes;
+pub mod canonical;
+pub mod config;
+pub mod csrf;
+pub mod dates;
 pub mod db;
+pub mod email;
+pub mod embed;
+pub mod fields;
+pub mod i18n;
+pub mod jobs;
+pub mod notify;
 pub mod pages;
+pub mod reputation;
+pub mod retention;
+pub mod rules;
+pub mod sla;
+pub mod snapshots;
+pub mod spam;
+pub mod stats;
+pub mod subjects;
+pub mod webhooks;
 
 use crate::db::AppData;
 
@@ -26,6 +52,18 @@ async fn main() -> std::io::Result<()> {
 
     let static_dir_flag: Option<String> = dorsal::get_named_argument(&args, "static-dir");
 
+    let config_flag: Option<String> = dorsal::get_named_argument(&args, "config");
+    let config = config::Config::load(config_flag.as_deref().unwrap_or("./config.json"));
+
+    let actor_key = if config.activitypub.enabled {
+        match activitypub::load_private_key(&config.activitypub.private_key) {
+            Some(k) => Some(k),
+            None => panic!("ActivityPub is enabled but the private key could not be loaded!"),
+        }
+    } else {
+        None
+    };
+
     // create database
     let db_type: Option<String> = dorsal::get_named_argument(&args, "db-type");
     let db_host: Option<String> = dorsal::get_var("DB_HOST");
@@ -62,15 +100,91 @@ async fn main() -> std::io::Result<()> {
     })
     .await;
 
-    db.init().await;
+    // bayes model commands (`--bayes train` or `--bayes evaluate`), run instead of the server
+    if let Some(command) = dorsal::get_named_argument(&args, "bayes") {
+        if !db.init().await {
+            panic!("Failed to initialize the database!");
+        }
+
+        let res: Result<(), String> = match command.as_str() {
+            "train" => bayes::retrain(&db)
+                .await
+                .map(|m| println!("Trained on {} spam and {} other decision(s)", m.spam, m.ham))
+                .map_err(|e| format!("Failed to train model: {e}")),
+            "evaluate" => bayes::evaluate(&db, &config.bayes)
+                .await
+                .map(|e| println!("{e}"))
+                .map_err(|e| format!("Failed to evaluate model: {e}")),
+            _ => Err(String::from(
+                "Unknown bayes command (expected \"train\" or \"evaluate\")",
+            )),
+        };
+
+        if let Err(e) = res {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    // relink every report to its subjects (`--subjects rebuild`), run instead of the server
+    if let Some(command) = dorsal::get_named_argument(&args, "subjects") {
+        if !db.init().await {
+            panic!("Failed to initialize the database!");
+        }
+
+        let res: Result<(), String> = match command.as_str() {
+            "rebuild" => subjects::rebuild(&db, &config.subjects.patterns)
+                .await
+                .map(|n| println!("Linked {n} report(s) to their subjects"))
+                .map_err(|e| format!("Failed to rebuild subjects: {e}")),
+            _ => Err(String::from(
+                "Unknown subjects command (expected \"rebuild\")",
+            )),
+        };
+
+        if let Err(e) = res {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    let mailer = email::Mailer::new(config.email.clone(), config.base_url.clone());
+    let attachments = attachments::store(&config.attachments);
+    let catalogs = i18n::Catalogs::load(&config.i18n);
+    let bayes_model = bayes::ModelCache::default();
 
     // start server
+    let ready = Arc::new(AtomicBool::new(false));
+    let init_db = db.clone();
+    let init_ready = ready.clone();
+
+    // background jobs
+    jobs::Scheduler::new(
+        db.clone(),
+        config.clone(),
+        mailer.clone(),
+        attachments.clone(),
+        bayes_model.clone(),
+    )
+    .start(ready.clone());
+
     println!("Starting server at: http://localhost:{port}");
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         let client = awc::Client::default();
         let data = web::Data::new(AppData {
             db: db.clone(),
             http_client: client,
+            config: config.clone(),
+            attachments: attachments.clone(),
+            actor_key: actor_key.clone(),
+            mailer: mailer.clone(),
+            ready: ready.clone(),
+            i18n: catalogs.clone(),
+            bayes: bayes_model.clone(),
         });
 
         let cors = actix_cors::Cors::default()
@@ -80,6 +194,19 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::clone(&data))
             // middleware
+            .wrap_fn(|req, srv| {
+                // nothing but the probes is served while migrations are running
+                if crate::api::health::is_starting(&req) {
+                    let res = HttpResponse::ServiceUnavailable()
+                        .append_header(("Retry-After", "5"))
+                        .body("Server is starting")
+                        .map_into_right_body();
+
+                    return Either::Left(future::ready(Ok(req.into_response(res))));
+                }
+
+                Either::Right(srv.call(req).map(|res| res.map(|r| r.map_into_left_body())))
+            })
             .wrap(actix_web::middleware::Logger::default())
             .wrap(cors)
             // static dir
@@ -98,16 +225,66 @@ async fn main() -> std::io::Result<()> {
             .service(fs::Files::new("/api/docs", "./target/doc").show_files_listing())
             // POST api
             .service(crate::api::auth::callback_request)
+            .service(crate::api::activitypub::inbox_request)
+            .service(crate::api::activitypub::forward_request)
+            .service(crate::api::feed::create_token_request)
             .service(crate::api::reports::create_request)
+            .service(crate::pages::form::submit_form_request)
+            .service(crate::api::reports::bulk_request)
+            .service(crate::api::reports::add_tag_request)
+            .service(crate::api::reports::redact_request)
+            .service(crate::api::reports::erasure_request)
+            .service(crate::api::reports::escalate_request)
+            .service(crate::api::attachments::upload_request)
+            .service(crate::api::attachments::track_upload_request)
+            .service(crate::api::rules::create_request)
+            .service(crate::api::rules::simulate_request)
+            .service(crate::api::rules::update_request)
             .service(crate::api::reports::edit_status_request)
+            // DELETE api
+            .service(crate::api::reports::remove_tag_request)
+            .service(crate::api::reports::delete_request)
+            .service(crate::api::rules::delete_request)
             // GET api
             .service(crate::api::auth::logout)
+            .service(crate::api::health::healthz_request)
+            .service(crate::api::health::readyz_request)
+            .service(crate::api::activitypub::actor_request)
+            .service(crate::api::feed::feed_request)
+            .service(crate::api::reports::track_request)
+            .service(crate::api::reports::tags_request)
+            .service(crate::api::stats::stats_request)
+            .service(crate::api::retention::preview_request)
+            .service(crate::api::sla::sla_request)
+            .service(crate::api::snapshots::snapshot_request)
+            .service(crate::api::snapshots::frame_request)
+            .service(crate::api::attachments::list_request)
+            .service(crate::api::attachments::file_request)
+            .service(crate::api::rules::list_request)
+            .service(crate::api::subjects::subject_request)
             // GET root
             .service(crate::pages::home::embed_request)
+            .service(crate::pages::form::form_request)
             .service(crate::pages::home::manage_report_request)
+            .service(crate::pages::home::track_request)
+            .service(crate::pages::home::my_reports_request)
+            .service(crate::pages::stats::stats_request)
+            .service(crate::pages::stats::transparency_request)
+            .service(crate::pages::jobs::jobs_request)
+            .service(crate::pages::sla::sla_request)
+            .service(crate::pages::rules::rules_request)
+            .service(crate::pages::subjects::subject_request)
             .service(crate::pages::home::home_request)
     })
     .bind(("0.0.0.0", port))?
-    .run()
-    .await
+    .run();
+
+    // init database (only the probes are served until this is done)
+    actix_web::rt::spawn(async move {
+        if init_db.init().await {
+            init_ready.store(true, Ordering::SeqCst);
+        }
+    });
+
+    server.await
 }
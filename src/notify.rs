@@ -0,0 +1,72 @@
+//! Staff notifications for report events
+use actix_web::web;
+
+use crate::config::Config;
+use crate::db::{AppData, Report};
+use crate::email::Mailer;
+
+/// Something that happened to a [`Report`] which staff should know about
+#[derive(Clone)]
+pub enum ReportEvent {
+    /// A new report was created
+    Created(Report),
+    /// A report was escalated (with the given reason)
+    Escalated(Report, String),
+    /// A report's status was changed (the report includes the new status)
+    StatusChanged(Report),
+}
+
+impl ReportEvent {
+    /// Get the [`Report`] the event is about
+    pub fn report(&self) -> &Report {
+        match self {
+            ReportEvent::Created(r) => r,
+            ReportEvent::Escalated(r, _) => r,
+            ReportEvent::StatusChanged(r) => r,
+        }
+    }
+
+    /// A short description of what happened (ex: "created")
+    pub fn action(&self) -> &'static str {
+        match self {
+            ReportEvent::Created(_) => "created",
+            ReportEvent::Escalated(_, _) => "escalated",
+            ReportEvent::StatusChanged(_) => "updated",
+        }
+    }
+
+    /// The reason given for the event (empty if none)
+    pub fn reason(&self) -> &str {
+        match self {
+            ReportEvent::Escalated(_, reason) => reason,
+            _ => "",
+        }
+    }
+}
+
+/// Notify staff about a [`ReportEvent`] through every configured channel
+///
+/// Notifications are sent in the background, this never blocks the request.
+pub fn dispatch(data: &web::Data<AppData>, event: ReportEvent) {
+    send(
+        data.http_client.clone(),
+        data.mailer.clone(),
+        &data.config,
+        event,
+    );
+}
+
+/// Notify staff about a [`ReportEvent`] outside of a request (see [`dispatch`])
+pub fn send(client: awc::Client, mailer: Mailer, config: &Config, event: ReportEvent) {
+    let base_url = config.base_url.clone();
+    let webhooks = config.webhooks.clone();
+
+    actix_web::rt::spawn(async move {
+        // emails are only sent for new and escalated reports
+        if !matches!(event, ReportEvent::StatusChanged(_)) {
+            mailer.notify(event.clone()).await;
+        }
+
+        crate::webhooks::post_event(&client, &base_url, &webhooks, &event).await;
+    });
+}
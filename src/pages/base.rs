@@ -60,3 +60,23 @@ pub async fn check_auth_status(
     // return
     (set_cookie.to_string(), token_cookie, token_user)
 }
+
+/// Check if the user returned by [`check_auth_status`] has the given permission
+///
+/// # Arguments:
+/// * `token_user` - the user returned by [`check_auth_status`]
+/// * `permission` - the permission to check for (ex: "StaffDashboard")
+pub fn has_permission(
+    token_user: &Option<
+        dorsal::DefaultReturn<Option<dorsal::db::special::auth_db::FullUser<String>>>,
+    >,
+    permission: &str,
+) -> bool {
+    match token_user {
+        Some(ua) => match &ua.payload {
+            Some(ua) => ua.level.permissions.iter().any(|p| p == permission),
+            None => false,
+        },
+        None => false,
+    }
+}
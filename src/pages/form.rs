@@ -0,0 +1,264 @@
+use crate::api::reports::{file_report, FileError, PCreateReport};
+use crate::config::{FieldConfig, FieldKind, SiteConfig};
+use crate::db::{AppData, CreatedReport, ReportType};
+use crate::i18n::Translator;
+use actix_web::http::StatusCode;
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use std::collections::{BTreeMap, HashMap};
+
+use super::base;
+use askama::Template;
+
+#[derive(Default, PartialEq, serde::Deserialize)]
+pub struct FormQueryProps {
+    /// The reported address
+    pub address: Option<String>,
+    /// The origin of the site the form was opened from (see [`crate::config::SiteConfig`])
+    pub site: Option<String>,
+    /// The username of the reporter
+    pub as_user: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "report_form.html")]
+struct ReportFormTemplate {
+    address: String,
+    as_user: String,
+    site: String,
+    /// The CSRF token of the form (see [`crate::csrf`])
+    csrf: String,
+    /// The selected report type
+    report_type: String,
+    content: String,
+    /// The values of the custom fields by name
+    values: BTreeMap<String, String>,
+    /// Why the last submission was refused (empty if there was none)
+    error: String,
+    /// The CSS variables of the site's theme
+    theme: String,
+    /// The site's notice (the default notice if empty)
+    notice: String,
+    /// The site's custom fields
+    fields: Vec<FieldConfig>,
+    i18n: Translator,
+}
+
+impl ReportFormTemplate {
+    /// The submitted value of a custom field
+    fn value(&self, field: &FieldConfig) -> &str {
+        self.values
+            .get(&field.name)
+            .map(|v| v.as_str())
+            .unwrap_or_default()
+    }
+
+    /// If a report type was selected
+    fn is_selected(&self, report_type: &impl AsRef<str>) -> bool {
+        self.report_type == report_type.as_ref()
+    }
+
+    /// The translated report types a field is asked for (empty for every type)
+    fn report_types(&self, field: &FieldConfig) -> String {
+        field
+            .report_types
+            .iter()
+            .map(|t| self.i18n.label("report_type", t))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+#[derive(Template)]
+#[template(path = "report_submitted.html")]
+struct ReportSubmittedTemplate {
+    report: CreatedReport,
+    theme: String,
+    i18n: Translator,
+}
+
+/// Get the configured site of the form: the site of the reported address (whose custom fields
+/// reports are checked against), or the site the form was opened from
+fn form_site<'a>(
+    data: &'a web::Data<AppData>,
+    address: &str,
+    site: &str,
+) -> Option<&'a SiteConfig> {
+    crate::canonical::find_site(&data.config.sites, address)
+        .or_else(|| crate::canonical::find_site(&data.config.sites, site))
+}
+
+/// Build the form for an address
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `site` - the [`SiteConfig`] of the form (see [`form_site`])
+/// * `session` - the session of the reporter (see [`crate::csrf::session`])
+/// * `address` - the reported address
+/// * `i18n` - [`Translator`]
+async fn form(
+    data: &web::Data<AppData>,
+    site: Option<&SiteConfig>,
+    session: &str,
+    address: String,
+    i18n: Translator,
+) -> Result<ReportFormTemplate, String> {
+    let secret = crate::csrf::secret(&data.db).await?;
+
+    Ok(ReportFormTemplate {
+        csrf: crate::csrf::issue(&secret, session, &address),
+        address,
+        as_user: String::new(),
+        site: site.map(|s| s.origin.clone()).unwrap_or_default(),
+        report_type: String::new(),
+        content: String::new(),
+        values: BTreeMap::new(),
+        error: String::new(),
+        theme: site
+            .map(|s| crate::fields::theme_css(&s.theme))
+            .unwrap_or_default(),
+        notice: site.map(|s| s.notice.clone()).unwrap_or_default(),
+        fields: site.map(|s| s.fields.clone()).unwrap_or_default(),
+        i18n,
+    })
+}
+
+#[get("/api/v1/reports/form")]
+/// The report form without JavaScript (the address is given as a query parameter)
+pub async fn form_request(
+    req: HttpRequest,
+    data: web::Data<AppData>,
+    info: web::Query<FormQueryProps>,
+) -> impl Responder {
+    let address = info.address.clone().unwrap_or_default();
+    let site = form_site(&data, &address, info.site.as_deref().unwrap_or_default());
+    let session = crate::csrf::session(&req);
+
+    match form(&data, site, &session, address, data.i18n.request(&req)).await {
+        Ok(mut template) => {
+            template.as_user = info.as_user.clone().unwrap_or_default();
+
+            HttpResponse::Ok()
+                .append_header(("Content-Type", "text/html"))
+                .body(template.render().unwrap())
+        }
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
+
+#[post("/api/v1/reports/form")]
+/// Create a new report from the report form (`application/x-www-form-urlencoded`)
+///
+/// Runs the same checks as [`crate::api::reports::create_request`], custom fields are sent as
+/// `field:<name>`.
+pub async fn submit_form_request(
+    req: HttpRequest,
+    body: web::Form<HashMap<String, String>>,
+    data: web::Data<AppData>,
+) -> impl Responder {
+    let i18n = data.i18n.request(&req);
+    let ip = crate::rules::request_ip(&req, &data.config.trusted_proxies);
+    let session = crate::csrf::session(&req);
+
+    let value = |name: &str| body.get(name).cloned().unwrap_or_default();
+    let address = value("address");
+
+    let site = form_site(&data, &address, &value("site"));
+    let mut template = match form(&data, site, &session, address.clone(), i18n.clone()).await {
+        Ok(t) => t,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+
+    template.as_user = value("as_user");
+    template.report_type = value("report_type");
+    template.content = value("content");
+    template.values = body
+        .iter()
+        .filter_map(|(k, v)| {
+            k.strip_prefix("field:")
+                .map(|name| (name.to_string(), v.to_string()))
+        })
+        .collect();
+
+    // check csrf token
+    let secret = match crate::csrf::secret(&data.db).await {
+        Ok(s) => s,
+        Err(e) => return HttpResponse::InternalServerError().body(e),
+    };
+
+    if !crate::csrf::verify(&secret, &session, &address, &value("csrf")) {
+        template.error = i18n.t("form.expired").to_string();
+        return HttpResponse::Forbidden()
+            .append_header(("Content-Type", "text/html"))
+            .body(template.render().unwrap());
+    }
+
+    let report_type: ReportType =
+        match serde_json::from_value(serde_json::Value::String(value("report_type"))) {
+            Ok(t) => t,
+            Err(_) => {
+                template.error = i18n.t("form.invalid_type").to_string();
+                return HttpResponse::BadRequest()
+                    .append_header(("Content-Type", "text/html"))
+                    .body(template.render().unwrap());
+            }
+        };
+
+    // every field is shown without JavaScript, only send the fields of the selected type
+    let applies = |name: &str| match site {
+        Some(s) => s
+            .fields
+            .iter()
+            .any(|f| (f.name == name) && crate::fields::applies(f, &report_type)),
+        None => true,
+    };
+
+    let fields: BTreeMap<String, String> = template
+        .values
+        .iter()
+        .filter(|(name, value)| !value.trim().is_empty() && applies(name))
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let (author, authenticated) = match token_user.and_then(|ua| ua.payload) {
+        Some(ua) if !ua.user.username.is_empty() => (ua.user.username, true),
+        _ => (template.as_user.clone(), false),
+    };
+
+    let props = PCreateReport {
+        report_type,
+        content: template.content.clone(),
+        address,
+        as_user: template.as_user.clone(),
+        fields,
+    };
+
+    match file_report(&data, ip, author, authenticated, &props).await {
+        Ok(report) => HttpResponse::Ok()
+            .append_header(("Set-Cookie", set_cookie))
+            .append_header(("Content-Type", "text/html"))
+            .body(
+                ReportSubmittedTemplate {
+                    report: report.into(),
+                    theme: template.theme,
+                    i18n,
+                }
+                .render()
+                .unwrap(),
+            ),
+        Err(e) => {
+            let status = match e {
+                FileError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+                FileError::Invalid(_) => StatusCode::BAD_REQUEST,
+            };
+
+            template.error = e.message().to_string();
+            HttpResponse::build(status)
+                .append_header(("Set-Cookie", set_cookie))
+                .append_header(("Content-Type", "text/html"))
+                .body(template.render().unwrap())
+        }
+    }
+}
@@ -1,12 +1,21 @@
-use crate::db::Report;
+use crate::config::{FieldConfig, FieldKind, ReputationConfig, SlaConfig};
+use crate::db::{Attachment, Report, ReportFilter, ReporterStats, Snapshot, TrackedReport};
+use crate::i18n::Translator;
+use crate::reputation::TrustTier;
 use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use std::collections::HashMap;
 
 use super::base;
 use askama::Template;
 
 #[derive(Default, PartialEq, serde::Deserialize)]
-pub struct OffsetQueryProps {
+pub struct ReportsQueryProps {
     pub offset: Option<i32>,
+    pub status: Option<String>,
+    #[serde(rename = "type")]
+    pub report_type: Option<String>,
+    pub tag: Option<String>,
+    pub sort: Option<String>,
 }
 
 #[derive(Template)]
@@ -23,39 +32,175 @@ struct AuthPickerTemplate {
 struct HomeTemplate {
     reports: Vec<Report>,
     offset: i32,
+    filter: ReportFilter,
+    known_tags: Vec<String>,
+    sla: SlaConfig,
+    now: u128,
+    reputation: ReputationConfig,
+    /// The statistics of the reporters of `reports`
+    reporters: HashMap<String, ReporterStats>,
+    i18n: Translator,
     // required fields (super::base)
     auth_state: bool,
     guppy: String,
     body_embed: String,
 }
 
+impl HomeTemplate {
+    /// The statistics of the report's reporter (if they have any)
+    fn reporter(&self, report: &Report) -> Option<&ReporterStats> {
+        self.reporters.get(&report.reporter)
+    }
+
+    /// The [`TrustTier`] of a reporter
+    fn tier(&self, stats: &ReporterStats) -> TrustTier {
+        TrustTier::of(&self.reputation, Some(stats))
+    }
+
+    /// If the report breaches its SLA target
+    fn is_breached(&self, report: &Report) -> bool {
+        self.sla.is_breached(report, self.now)
+    }
+
+    /// How long the report has had its current status (ex: `2d 4h`)
+    fn waiting(&self, report: &Report) -> String {
+        crate::dates::duration_text(report.time_in_status(self.now))
+    }
+}
+
 #[derive(Template)]
 #[template(path = "manage_report.html")]
 struct ViewReportTemplate {
     report: Report,
+    federation: bool,
+    known_tags: Vec<String>,
+    sla: SlaConfig,
+    now: u128,
+    snapshot: Option<Snapshot>,
+    attachments: Vec<Attachment>,
+    /// If staff can attach files
+    attachments_enabled: bool,
+    /// The subjects of the report and how many reports are about each
+    subjects: Vec<(String, usize)>,
+    i18n: Translator,
+    // required fields (super::base)
+    auth_state: bool,
+    guppy: String,
+    body_embed: String,
+}
+
+impl ViewReportTemplate {
+    /// If the report breaches its SLA target
+    fn is_breached(&self, report: &Report) -> bool {
+        self.sla.is_breached(report, self.now)
+    }
+
+    /// How long the report has had its current status (ex: `2d 4h`)
+    fn waiting(&self, report: &Report) -> String {
+        crate::dates::duration_text(report.time_in_status(self.now))
+    }
+}
+
+#[derive(Template)]
+#[template(path = "track.html")]
+struct TrackTemplate {
+    report: TrackedReport,
+    i18n: Translator,
+    // required fields (super::base)
+    auth_state: bool,
+    guppy: String,
+    body_embed: String,
+}
+
+#[derive(Template)]
+#[template(path = "my_reports.html")]
+struct MyReportsTemplate {
+    reports: Vec<Report>,
+    offset: i32,
+    i18n: Translator,
     // required fields (super::base)
     auth_state: bool,
     guppy: String,
     body_embed: String,
 }
 
+#[derive(Default, PartialEq, serde::Deserialize)]
+pub struct EmbedQueryProps {
+    /// The origin of the site the form is embedded in (see [`crate::config::SiteConfig`])
+    pub site: Option<String>,
+}
+
 #[derive(Template)]
 #[template(path = "embed.html")]
-struct EmbedTemplate {}
+struct EmbedTemplate {
+    /// If reporters can attach files
+    attachments: bool,
+    /// The allowed attachment MIME types (comma separated)
+    accept: String,
+    /// The CSS variables of the site's theme
+    theme: String,
+    /// The site's notice (the default notice if empty)
+    notice: String,
+    /// The site's custom fields
+    fields: Vec<FieldConfig>,
+    /// The origins messages are accepted from (comma separated, see [`crate::embed`])
+    origins: String,
+    /// The query string of the embed, passed to the form without JavaScript
+    query: String,
+    i18n: Translator,
+}
+
+impl EmbedTemplate {
+    /// The report types a field is asked for (comma separated, empty for every type)
+    fn report_types(&self, field: &FieldConfig) -> String {
+        field
+            .report_types
+            .iter()
+            .map(|t| t.to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
 
 #[get("/api/v1/reports/embed")]
-pub async fn embed_request() -> impl Responder {
+pub async fn embed_request(
+    req: HttpRequest,
+    data: web::Data<crate::db::AppData>,
+    info: web::Query<EmbedQueryProps>,
+) -> impl Responder {
+    let site = info
+        .site
+        .as_ref()
+        .and_then(|s| crate::canonical::find_site(&data.config.sites, s));
+
     return HttpResponse::Ok()
         .append_header(("Content-Type", "text/html"))
-        .body(EmbedTemplate {}.render().unwrap());
+        .body(
+            EmbedTemplate {
+                attachments: data.config.attachments.enabled,
+                accept: data.config.attachments.mime_types.join(","),
+                theme: site
+                    .map(|s| crate::fields::theme_css(&s.theme))
+                    .unwrap_or_default(),
+                notice: site.map(|s| s.notice.clone()).unwrap_or_default(),
+                fields: site.map(|s| s.fields.clone()).unwrap_or_default(),
+                origins: crate::embed::allowed_origins(&data.config.sites, site).join(","),
+                query: req.query_string().to_string(),
+                i18n: data.i18n.request(&req),
+            }
+            .render()
+            .unwrap(),
+        );
 }
 
 #[get("/")]
 pub async fn home_request(
     req: HttpRequest,
     data: web::Data<crate::db::AppData>,
-    info: web::Query<OffsetQueryProps>,
+    info: web::Query<ReportsQueryProps>,
 ) -> impl Responder {
+    let i18n = data.i18n.request(&req);
+
     // verify auth status
     let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
 
@@ -78,12 +223,28 @@ pub async fn home_request(
 
     // ...
     // get reports
-    let res = data.db.get_all_reports(info.offset).await;
+    let filter = ReportFilter::from_query(&info.status, &info.report_type, &info.tag, &info.sort);
+    let res = data.db.get_reports_by_filter(&filter, info.offset).await;
 
     if res.success == false {
         return HttpResponse::NotAcceptable().body(res.message);
     }
 
+    let reports = res.payload.unwrap();
+    let reporters = if data.config.reputation.enabled {
+        let mut reporters: Vec<String> = reports
+            .iter()
+            .filter(|r| !r.reporter.is_empty())
+            .map(|r| r.reporter.clone())
+            .collect();
+
+        reporters.sort();
+        reporters.dedup();
+        data.db.get_reporter_stats(&reporters).await
+    } else {
+        HashMap::new()
+    };
+
     // ...
     let base = base::get_base_values(token_user.is_some());
     return HttpResponse::Ok()
@@ -91,11 +252,18 @@ pub async fn home_request(
         .append_header(("Content-Type", "text/html"))
         .body(
             HomeTemplate {
-                reports: res.payload.unwrap(),
+                reports,
                 offset: match info.offset {
                     Some(i) => i,
                     None => 0,
                 },
+                filter,
+                known_tags: data.db.get_known_tags(&data.config.tags).await,
+                sla: data.config.sla.clone(),
+                now: dorsal::utility::unix_epoch_timestamp(),
+                reputation: data.config.reputation.clone(),
+                reporters,
+                i18n,
                 // required fields
                 auth_state: base.auth_state,
                 guppy: base.guppy,
@@ -167,6 +335,20 @@ pub async fn manage_report_request(
         .body(
             ViewReportTemplate {
                 report: res.payload.unwrap(),
+                federation: data.config.activitypub.enabled,
+                known_tags: data.db.get_known_tags(&data.config.tags).await,
+                sla: data.config.sla.clone(),
+                now: dorsal::utility::unix_epoch_timestamp(),
+                snapshot: data.db.get_snapshot_by_report(id.to_string()).await.payload,
+                attachments: data
+                    .db
+                    .get_attachments_by_report(id.to_string())
+                    .await
+                    .payload
+                    .unwrap_or_default(),
+                attachments_enabled: data.config.attachments.enabled,
+                subjects: data.db.get_report_subjects(id).await,
+                i18n: data.i18n.request(&req),
                 // required fields
                 auth_state: base.auth_state,
                 guppy: base.guppy,
@@ -176,3 +358,89 @@ pub async fn manage_report_request(
             .unwrap(),
         );
 }
+
+#[get("/track/{token}")]
+/// The reporter-facing status page of a report
+pub async fn track_request(
+    req: HttpRequest,
+    data: web::Data<crate::db::AppData>,
+) -> impl Responder {
+    let token = req.match_info().get("token").unwrap();
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req.clone(), data.clone()).await;
+
+    // get report
+    let res = data.db.get_report_by_tracking(token.to_string()).await;
+
+    if !res.success {
+        return HttpResponse::NotFound().body(res.message);
+    }
+
+    // ...
+    let base = base::get_base_values(token_user.is_some());
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", "text/html"))
+        .body(
+            TrackTemplate {
+                report: TrackedReport::from(res.payload.unwrap()),
+                i18n: data.i18n.request(&req),
+                // required fields
+                auth_state: base.auth_state,
+                guppy: base.guppy,
+                body_embed: base.body_embed,
+            }
+            .render()
+            .unwrap(),
+        )
+}
+
+#[get("/mine")]
+/// The reports created by the current user
+pub async fn my_reports_request(
+    req: HttpRequest,
+    data: web::Data<crate::db::AppData>,
+    info: web::Query<ReportsQueryProps>,
+) -> impl Responder {
+    let i18n = data.i18n.request(&req);
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    let username = match token_user.clone().and_then(|ua| ua.payload) {
+        Some(ua) => ua.user.username,
+        None => return auth_picker(token_user.is_some(), set_cookie).await,
+    };
+
+    // get reports
+    let filter = ReportFilter {
+        author: Some(username),
+        ..Default::default()
+    };
+
+    let res = data.db.get_reports_by_filter(&filter, info.offset).await;
+
+    if !res.success {
+        return HttpResponse::NotAcceptable().body(res.message);
+    }
+
+    // ...
+    let base = base::get_base_values(token_user.is_some());
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", "text/html"))
+        .body(
+            MyReportsTemplate {
+                reports: res.payload.unwrap(),
+                offset: info.offset.unwrap_or(0),
+                i18n,
+                // required fields
+                auth_state: base.auth_state,
+                guppy: base.guppy,
+                body_embed: base.body_embed,
+            }
+            .render()
+            .unwrap(),
+        )
+}
@@ -0,0 +1,57 @@
+use crate::db::{JobRun, JobState};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+
+use super::base;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "jobs.html")]
+struct JobsTemplate {
+    jobs: Vec<JobState>,
+    runs: Vec<JobRun>,
+    // required fields (super::base)
+    auth_state: bool,
+    guppy: String,
+    body_embed: String,
+}
+
+#[get("/jobs")]
+/// Background job state and history (staff only)
+pub async fn jobs_request(req: HttpRequest, data: web::Data<crate::db::AppData>) -> impl Responder {
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    if !base::has_permission(&token_user, "StaffDashboard") {
+        return super::home::auth_picker(token_user.is_some(), set_cookie).await;
+    }
+
+    // get jobs
+    let jobs = data.db.get_jobs().await;
+    let runs = data.db.get_job_runs().await;
+
+    if !jobs.success | !runs.success {
+        return HttpResponse::NotAcceptable().body(if jobs.success {
+            runs.message
+        } else {
+            jobs.message
+        });
+    }
+
+    // ...
+    let base = base::get_base_values(token_user.is_some());
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", "text/html"))
+        .body(
+            JobsTemplate {
+                jobs: jobs.payload.unwrap(),
+                runs: runs.payload.unwrap(),
+                // required fields
+                auth_state: base.auth_state,
+                guppy: base.guppy,
+                body_embed: base.body_embed,
+            }
+            .render()
+            .unwrap(),
+        )
+}
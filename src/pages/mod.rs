@@ -1,2 +1,8 @@
 pub mod base;
+pub mod form;
 pub mod home;
+pub mod jobs;
+pub mod rules;
+pub mod sla;
+pub mod stats;
+pub mod subjects;
@@ -0,0 +1,78 @@
+use crate::i18n::Translator;
+use crate::rules::{Rule, RuleAction};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+
+use super::base;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "rules.html")]
+struct RulesTemplate {
+    rules: Vec<Rule>,
+    i18n: Translator,
+    // required fields (super::base)
+    auth_state: bool,
+    guppy: String,
+    body_embed: String,
+}
+
+impl RulesTemplate {
+    /// Get the JSON of a value (rules are loaded into the editor as JSON)
+    fn json<T: serde::Serialize>(&self, value: &T) -> String {
+        serde_json::to_string(value).unwrap()
+    }
+
+    /// Describe an action (ex: `Set status to Spam`, with the status translated)
+    fn action(&self, action: &RuleAction) -> String {
+        match action {
+            RuleAction::SetStatus(status) => {
+                format!("Set status to {}", self.i18n.label("report_status", status))
+            }
+            RuleAction::SetPriority(priority) => format!("Set priority to {priority}"),
+            RuleAction::Tag(tag) => format!("Tag {tag}"),
+            RuleAction::Assign(username) => format!("Assign to {username}"),
+            RuleAction::Notify => String::from("Notify staff"),
+        }
+    }
+}
+
+#[get("/rules")]
+/// Triage rules editor (requires `ManageRules`)
+pub async fn rules_request(
+    req: HttpRequest,
+    data: web::Data<crate::db::AppData>,
+) -> impl Responder {
+    let i18n = data.i18n.request(&req);
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    if !base::has_permission(&token_user, "ManageRules") {
+        return super::home::auth_picker(token_user.is_some(), set_cookie).await;
+    }
+
+    // get rules
+    let rules = data.db.get_rules().await;
+
+    if !rules.success {
+        return HttpResponse::NotAcceptable().body(rules.message);
+    }
+
+    // ...
+    let base = base::get_base_values(token_user.is_some());
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", "text/html"))
+        .body(
+            RulesTemplate {
+                rules: rules.payload.unwrap(),
+                i18n,
+                // required fields
+                auth_state: base.auth_state,
+                guppy: base.guppy,
+                body_embed: base.body_embed,
+            }
+            .render()
+            .unwrap(),
+        )
+}
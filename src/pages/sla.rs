@@ -0,0 +1,64 @@
+use crate::i18n::Translator;
+use crate::sla::{self, SlaReport};
+use crate::stats::Period;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+
+use super::base;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "sla.html")]
+struct SlaTemplate {
+    report: SlaReport,
+    i18n: Translator,
+    // required fields (super::base)
+    auth_state: bool,
+    guppy: String,
+    body_embed: String,
+}
+
+#[get("/sla")]
+/// SLA compliance report (staff only)
+pub async fn sla_request(
+    req: HttpRequest,
+    data: web::Data<crate::db::AppData>,
+    info: web::Query<crate::api::stats::StatsQueryProps>,
+) -> impl Responder {
+    let i18n = data.i18n.request(&req);
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    if !base::has_permission(&token_user, "StaffDashboard") {
+        return super::home::auth_picker(token_user.is_some(), set_cookie).await;
+    }
+
+    let period = match Period::from_query(&info.period) {
+        Some(p) => p,
+        None => return HttpResponse::NotAcceptable().body("Period is invalid"),
+    };
+
+    let res = sla::get_report(&data.db, &data.config.sla, period).await;
+
+    if !res.success {
+        return HttpResponse::NotAcceptable().body(res.message);
+    }
+
+    // ...
+    let base = base::get_base_values(token_user.is_some());
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", "text/html"))
+        .body(
+            SlaTemplate {
+                report: res.payload.unwrap(),
+                i18n,
+                // required fields
+                auth_state: base.auth_state,
+                guppy: base.guppy,
+                body_embed: base.body_embed,
+            }
+            .render()
+            .unwrap(),
+        )
+}
@@ -0,0 +1,96 @@
+use crate::i18n::Translator;
+use crate::stats::{self, Period, ReportStats};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+
+use super::base;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "stats.html")]
+struct StatsTemplate {
+    stats: ReportStats,
+    /// If this is the public transparency page
+    public: bool,
+    i18n: Translator,
+    // required fields (super::base)
+    auth_state: bool,
+    guppy: String,
+    body_embed: String,
+}
+
+/// Render the statistics page for the requested period
+async fn render_stats(
+    data: &web::Data<crate::db::AppData>,
+    info: &crate::api::stats::StatsQueryProps,
+    public: bool,
+    i18n: Translator,
+    auth_state: bool,
+    set_cookie: String,
+) -> HttpResponse {
+    let period = match Period::from_query(&info.period) {
+        Some(p) => p,
+        None => return HttpResponse::NotAcceptable().body("Period is invalid"),
+    };
+
+    let res = stats::get_stats(&data.db, period).await;
+
+    if !res.success {
+        return HttpResponse::NotAcceptable().body(res.message);
+    }
+
+    // ...
+    let base = base::get_base_values(auth_state);
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", "text/html"))
+        .body(
+            StatsTemplate {
+                stats: res.payload.unwrap(),
+                public,
+                i18n,
+                // required fields
+                auth_state: base.auth_state,
+                guppy: base.guppy,
+                body_embed: base.body_embed,
+            }
+            .render()
+            .unwrap(),
+        )
+}
+
+#[get("/stats")]
+/// Report statistics (staff only)
+pub async fn stats_request(
+    req: HttpRequest,
+    data: web::Data<crate::db::AppData>,
+    info: web::Query<crate::api::stats::StatsQueryProps>,
+) -> impl Responder {
+    let i18n = data.i18n.request(&req);
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    if !base::has_permission(&token_user, "StaffDashboard") {
+        return super::home::auth_picker(token_user.is_some(), set_cookie).await;
+    }
+
+    render_stats(&data, &info, false, i18n, token_user.is_some(), set_cookie).await
+}
+
+#[get("/transparency")]
+/// Public report statistics (if `stats.public` is enabled)
+pub async fn transparency_request(
+    req: HttpRequest,
+    data: web::Data<crate::db::AppData>,
+    info: web::Query<crate::api::stats::StatsQueryProps>,
+) -> impl Responder {
+    if !data.config.stats.public {
+        return HttpResponse::NotFound().body("Transparency reports are not enabled");
+    }
+
+    let i18n = data.i18n.request(&req);
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+    render_stats(&data, &info, true, i18n, token_user.is_some(), set_cookie).await
+}
@@ -0,0 +1,58 @@
+use crate::i18n::Translator;
+use crate::subjects::{self, Subject};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+
+use super::base;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "subject.html")]
+struct SubjectTemplate {
+    subject: Subject,
+    i18n: Translator,
+    // required fields (super::base)
+    auth_state: bool,
+    guppy: String,
+    body_embed: String,
+}
+
+#[get("/subjects/{key:.*}")]
+/// Every report about a subject (staff only)
+pub async fn subject_request(
+    req: HttpRequest,
+    data: web::Data<crate::db::AppData>,
+) -> impl Responder {
+    let key = req.match_info().get("key").unwrap().to_string();
+    let i18n = data.i18n.request(&req);
+
+    // verify auth status
+    let (set_cookie, _, token_user) = base::check_auth_status(req, data.clone()).await;
+
+    if !base::has_permission(&token_user, "StaffDashboard") {
+        return super::home::auth_picker(token_user.is_some(), set_cookie).await;
+    }
+
+    let res = subjects::get(&data.db, &key).await;
+
+    if !res.success {
+        return HttpResponse::NotAcceptable().body(res.message);
+    }
+
+    // ...
+    let base = base::get_base_values(token_user.is_some());
+    HttpResponse::Ok()
+        .append_header(("Set-Cookie", set_cookie))
+        .append_header(("Content-Type", "text/html"))
+        .body(
+            SubjectTemplate {
+                subject: res.payload.unwrap(),
+                i18n,
+                // required fields
+                auth_state: base.auth_state,
+                guppy: base.guppy,
+                body_embed: base.body_embed,
+            }
+            .render()
+            .unwrap(),
+        )
+}
@@ -0,0 +1,251 @@
+//! Reporter reputation
+//!
+//! Reports are grouped by reporter: the account they were filed from, or a fingerprint (a salted
+//! hash of the IP address they were sent from) for reports from anyone who isn't logged in. Staff decisions about a reporter's reports
+//! give them a trust score, which sets their rate limit tier and can mark their new reports as
+//! spam. Decisions made automatically (by rules, spam scores or this module) are never counted.
+use actix_web::web;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::config::ReputationConfig;
+use crate::dates::MINUTE;
+use crate::db::{AppData, BulkAction, Database, Report, ReportFilter, ReportStatus, ReporterStats};
+
+/// The name the fingerprint salt is stored as in the meta table
+pub const SALT_NAME: &str = "reporter_salt";
+
+/// The name stored in the audit entries of reports marked as spam because of their reporter
+pub const REPUTATION_EDITOR: &str = "system:reputation";
+
+/// How reporters are treated based on their trust score
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrustTier {
+    /// Mostly files spam
+    Untrusted,
+    /// New or mixed reporters
+    Normal,
+    /// Mostly files reports staff act on
+    Trusted,
+}
+
+impl std::fmt::Display for TrustTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl TrustTier {
+    /// Get the tier of a reporter (reporters without enough decisions are [`TrustTier::Normal`])
+    ///
+    /// # Arguments:
+    /// * `config` - [`ReputationConfig`]
+    /// * `stats` - the [`ReporterStats`] of the reporter (`None` if they have none)
+    pub fn of(config: &ReputationConfig, stats: Option<&ReporterStats>) -> TrustTier {
+        match stats {
+            Some(s) if s.decisions() >= config.min_decisions.max(1) => {
+                if s.trust < config.untrusted_below {
+                    TrustTier::Untrusted
+                } else if s.trust >= config.trusted_from {
+                    TrustTier::Trusted
+                } else {
+                    TrustTier::Normal
+                }
+            }
+            _ => TrustTier::Normal,
+        }
+    }
+
+    /// The most reports reporters in this tier can file every window (`0` is unlimited)
+    pub fn limit(&self, config: &ReputationConfig) -> usize {
+        match self {
+            TrustTier::Untrusted => config.untrusted_limit,
+            TrustTier::Normal => config.limit,
+            TrustTier::Trusted => config.trusted_limit,
+        }
+    }
+}
+
+/// Get the trust score (`0` to `100`) of a reporter from the decisions about their reports
+///
+/// Reporters without decisions start at `50`, every decision moves them towards the share of
+/// their reports staff resolved.
+pub fn trust(accepted: usize, spam: usize) -> u8 {
+    (((accepted + 1) * 100) as f64 / (accepted + spam + 2) as f64).round() as u8
+}
+
+/// Get the salt anonymous fingerprints are hashed with, creating it if it doesn't exist yet
+pub async fn salt(db: &Database) -> Result<String, String> {
+    if let Some(salt) = db.get_meta(SALT_NAME).await {
+        return Ok(salt);
+    }
+
+    let salt = dorsal::utility::random_id();
+    db.set_meta(SALT_NAME, &salt).await?;
+    Ok(salt)
+}
+
+/// Get who is filing a report: their account, or the fingerprint of a reporter who isn't logged in
+///
+/// Authors given by the client (ex: `as_user`) are never used, since anyone can claim them.
+/// Fingerprints are used even if reputation is disabled, since duplicate reports are found by
+/// reporter. Returns an empty string if the reporter isn't logged in and their IP address is
+/// unknown.
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `account` - the username of the authenticated session the report was filed from
+/// * `ip` - the IP address the report was sent from
+pub async fn reporter(
+    data: &web::Data<AppData>,
+    account: Option<&str>,
+    ip: Option<IpAddr>,
+) -> String {
+    if let Some(account) = account.filter(|a| !a.is_empty()) {
+        return account.to_string();
+    }
+
+    let ip = match ip {
+        Some(ip) => ip,
+        None => return String::new(),
+    };
+
+    match salt(&data.db).await {
+        Ok(salt) => format!(
+            "anon:{}",
+            &dorsal::utility::hash(format!("{salt}{ip}"))[..16]
+        ),
+        Err(e) => {
+            eprintln!("Failed to get reporter salt: {e}");
+            String::new()
+        }
+    }
+}
+
+/// Check if a reporter may file another report, returning their [`TrustTier`]
+///
+/// Returns an error message if the reporter reached the rate limit of their tier.
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `reporter` - the reporter (see [`reporter`])
+pub async fn admit(data: &web::Data<AppData>, reporter: &str) -> Result<TrustTier, String> {
+    let config = &data.config.reputation;
+
+    if !config.enabled || reporter.is_empty() {
+        return Ok(TrustTier::Normal);
+    }
+
+    let stats = data.db.get_reporter_stats(&[reporter.to_string()]).await;
+    let tier = TrustTier::of(config, stats.get(reporter));
+    let limit = tier.limit(config);
+
+    if limit == 0 {
+        return Ok(tier);
+    }
+
+    let since = dorsal::utility::unix_epoch_timestamp()
+        .saturating_sub(config.window.max(1) as u128 * MINUTE);
+
+    match data.db.count_reports_by_reporter(reporter, since).await {
+        Ok(count) if count >= limit => {
+            Err(String::from("Too many reports, please try again later"))
+        }
+        Ok(_) => Ok(tier),
+        Err(e) => {
+            // never refuse reports because the limit couldn't be checked
+            eprintln!("Failed to count reports of {reporter}: {e}");
+            Ok(tier)
+        }
+    }
+}
+
+/// Mark a new [`Report`] as spam if its reporter is untrusted (if enabled)
+///
+/// The given report is updated with the changes made.
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `report` - [`Report`]
+/// * `tier` - the [`TrustTier`] of its reporter (see [`admit`])
+pub async fn check(data: &web::Data<AppData>, report: &mut Report, tier: TrustTier) {
+    let config = &data.config.reputation;
+
+    if !config.enabled
+        || !config.mark_untrusted_as_spam
+        || (tier != TrustTier::Untrusted)
+        || (report.status == ReportStatus::Spam)
+    {
+        return;
+    }
+
+    let res = data
+        .db
+        .apply_bulk_action(
+            vec![report.id.clone()],
+            None,
+            BulkAction::Status(ReportStatus::Spam),
+            REPUTATION_EDITOR,
+        )
+        .await;
+
+    if res.success {
+        report.status = ReportStatus::Spam;
+        report.status_timestamp = dorsal::utility::unix_epoch_timestamp();
+    } else {
+        eprintln!("Failed to mark {} as spam: {}", report.id, res.message);
+    }
+}
+
+/// Recalculate the [`ReporterStats`] of every reporter from staff decisions, returning the number
+/// of reporters
+pub async fn recalculate(db: &Database) -> Result<usize, String> {
+    let editors = db.get_status_editors().await?;
+
+    let res = db
+        .get_every_report_by_filter(&ReportFilter::default())
+        .await;
+    let reports = match res.payload {
+        Some(r) => r,
+        None => return Err(res.message),
+    };
+
+    let now = dorsal::utility::unix_epoch_timestamp();
+    let mut stats: HashMap<String, ReporterStats> = HashMap::new();
+
+    for report in reports.iter().filter(|r| !r.reporter.is_empty()) {
+        let entry = stats
+            .entry(report.reporter.clone())
+            .or_insert_with(|| ReporterStats {
+                reporter: report.reporter.clone(),
+                updated: now,
+                ..Default::default()
+            });
+
+        entry.reports += 1;
+
+        let by_staff = match editors.get(&report.id) {
+            Some(editor) => !editor.starts_with("system:"),
+            None => false,
+        };
+
+        if by_staff {
+            match report.status {
+                ReportStatus::Archived => entry.accepted += 1,
+                ReportStatus::Spam => entry.spam += 1,
+                ReportStatus::Active => (),
+            }
+        }
+    }
+
+    let stats: Vec<ReporterStats> = stats
+        .into_values()
+        .map(|mut s| {
+            s.trust = trust(s.accepted, s.spam);
+            s
+        })
+        .collect();
+
+    db.replace_reporter_stats(&stats).await?;
+    Ok(stats.len())
+}
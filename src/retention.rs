@@ -0,0 +1,136 @@
+//! Automatic purging and redaction of old reports
+use serde::{Deserialize, Serialize};
+
+use crate::config::{RetentionAction, RetentionRule};
+use crate::dates::DAY;
+use crate::db::{BulkAction, Database, Report, ReportFilter, MAX_BULK_REPORTS, REDACTED_CONTENT};
+
+/// The name stored in the audit entries of retention changes
+pub const RETENTION_EDITOR: &str = "system:retention";
+
+/// The reports a single [`RetentionRule`] affects
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RetentionMatch {
+    /// The index of the rule in the config
+    pub rule: usize,
+    pub action: RetentionAction,
+    /// The IDs of the affected reports
+    pub reports: Vec<String>,
+}
+
+/// Check if a [`RetentionRule`] applies to a [`Report`]
+///
+/// # Arguments:
+/// * `rule` - [`RetentionRule`]
+/// * `report` - [`Report`]
+/// * `now` - the current unix timestamp (in milliseconds)
+pub fn rule_applies(rule: &RetentionRule, report: &Report, now: u128) -> bool {
+    // reports are aged from their last status change
+    if report.time_in_status(now) < rule.after_days as u128 * DAY {
+        return false;
+    }
+
+    // don't redact reports again
+    if (rule.action == RetentionAction::Redact)
+        && report.author.is_empty()
+        && (report.content == REDACTED_CONTENT)
+    {
+        return false;
+    }
+
+    true
+}
+
+/// Find every report affected by the given rules
+///
+/// A report is only included in the first rule it matches.
+pub async fn find_matches(
+    db: &Database,
+    rules: &[RetentionRule],
+) -> Result<Vec<RetentionMatch>, String> {
+    let now = dorsal::utility::unix_epoch_timestamp();
+    let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut out: Vec<RetentionMatch> = Vec::new();
+
+    for (i, rule) in rules.iter().enumerate() {
+        let res = db
+            .get_every_report_by_filter(&ReportFilter {
+                status: rule.status.clone(),
+                report_type: rule.report_type.clone(),
+                ..Default::default()
+            })
+            .await;
+
+        let reports = match res.payload {
+            Some(r) => r,
+            None => return Err(res.message),
+        };
+
+        let mut ids: Vec<String> = Vec::new();
+        for report in reports {
+            if rule_applies(rule, &report, now) && seen.insert(report.id.clone()) {
+                ids.push(report.id);
+            }
+        }
+
+        out.push(RetentionMatch {
+            rule: i,
+            action: rule.action.clone(),
+            reports: ids,
+        });
+    }
+
+    Ok(out)
+}
+
+/// Apply the given rules, returns the number of changed reports
+///
+/// # Arguments:
+/// * `db` - [`Database`]
+/// * `rules` - every [`RetentionRule`]
+/// * `dry_run` - only log what would be affected
+pub async fn enforce(db: &Database, rules: &[RetentionRule], dry_run: bool) -> usize {
+    let matches = match find_matches(db, rules).await {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Failed to find reports for retention: {e}");
+            return 0;
+        }
+    };
+
+    let mut changed: usize = 0;
+    for m in matches {
+        if m.reports.is_empty() {
+            continue;
+        }
+
+        if dry_run {
+            println!(
+                "Retention (dry run): rule {} would {:?} {} report(s)",
+                m.rule,
+                m.action,
+                m.reports.len()
+            );
+
+            continue;
+        }
+
+        let action = match m.action {
+            RetentionAction::Purge => BulkAction::Delete,
+            RetentionAction::Redact => BulkAction::Redact,
+        };
+
+        for chunk in m.reports.chunks(MAX_BULK_REPORTS) {
+            let res = db
+                .apply_bulk_action(chunk.to_vec(), None, action.clone(), RETENTION_EDITOR)
+                .await;
+
+            match res.payload {
+                Some(ids) => changed += ids.len(),
+                None => eprintln!("Failed to apply retention rule {}: {}", m.rule, res.message),
+            }
+        }
+    }
+
+    changed
+}
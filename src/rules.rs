@@ -0,0 +1,772 @@
+//! Declarative rules for automatic report triage
+//!
+//! Rules are stored in the database and evaluated in order (by [`Rule::position`]) when a report
+//! is created and when staff change its status. Changes made by rules (or background jobs) never
+//! trigger rules again.
+use actix_web::{web, HttpRequest};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+
+use crate::db::{
+    normalize_tag, AppData, BulkAction, Database, Report, ReportFilter, ReportStatus, ReportType,
+    MAX_BULK_REPORTS,
+};
+use crate::notify::{self, ReportEvent};
+
+/// The name stored in the audit entries of rule actions
+pub const RULES_EDITOR: &str = "system:rules";
+
+/// The largest compiled size of a rule pattern (keeps patterns from using too much memory)
+pub const MAX_PATTERN_SIZE: usize = 100_000;
+
+/// The most compiled patterns kept in memory
+const MAX_CACHED_PATTERNS: usize = 1000;
+
+/// When a [`Rule`] is evaluated
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum RuleEvent {
+    /// A report was created
+    Created,
+    /// Staff changed the status of a report
+    StatusChanged,
+}
+
+/// The conditions of a [`Rule`], empty conditions match everything
+///
+/// Every non-empty condition must match for the rule to match.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct RuleConditions {
+    /// The report must have one of these types
+    #[serde(default)]
+    pub report_types: Vec<ReportType>,
+    /// The report must have one of these statuses
+    #[serde(default)]
+    pub statuses: Vec<ReportStatus>,
+    /// A regular expression the address must match (ex: `^https?://(www\.)?example\.com/`)
+    #[serde(default)]
+    pub address_pattern: String,
+    /// A regular expression the content must match (prefix with `(?i)` to ignore case)
+    #[serde(default)]
+    pub content_pattern: String,
+    /// The (trimmed) content must be shorter than this many characters
+    #[serde(default)]
+    pub content_shorter_than: Option<usize>,
+    /// The report must be created by one of these usernames (empty string for anonymous reports)
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// The author must have created at least this many reports (including this one)
+    ///
+    /// Only authenticated authors have a history, so this never matches anonymous reports.
+    #[serde(default)]
+    pub author_min_reports: Option<usize>,
+    /// At least this many of the author's reports must have been marked as spam
+    #[serde(default)]
+    pub author_min_spam: Option<usize>,
+    /// The reporter must have filed at least this many reports (including this one)
+    ///
+    /// Anonymous reporters are identified by their IP address fingerprint (see
+    /// [`crate::reputation::reporter`]).
+    #[serde(default)]
+    pub reporter_min_reports: Option<usize>,
+    /// At least this many of the reporter's reports must have been marked as spam
+    #[serde(default)]
+    pub reporter_min_spam: Option<usize>,
+    /// The report must be sent from one of these IP addresses or CIDR ranges (ex: `203.0.113.0/24`)
+    ///
+    /// IP addresses aren't stored, so this only ever matches when a report is created.
+    #[serde(default)]
+    pub ips: Vec<String>,
+}
+
+/// Something a [`Rule`] does to the reports it matches
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum RuleAction {
+    SetStatus(ReportStatus),
+    /// Set the priority (capped at [`crate::db::MAX_PRIORITY`])
+    SetPriority(u8),
+    Tag(String),
+    /// Assign the report to a staff member (by username)
+    Assign(String),
+    /// Notify staff through every configured channel (like an escalation)
+    Notify,
+}
+
+/// A stored triage rule
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Rule {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// Rules are evaluated from the lowest to the highest position
+    #[serde(default)]
+    pub position: i32,
+    #[serde(default)]
+    pub enabled: bool,
+    /// When the rule is evaluated
+    pub events: Vec<RuleEvent>,
+    #[serde(default)]
+    pub conditions: RuleConditions,
+    pub actions: Vec<RuleAction>,
+    /// Don't evaluate any later rule if this rule matches
+    #[serde(default)]
+    pub stop: bool,
+    #[serde(default)]
+    pub timestamp: u128,
+}
+
+/// The number of reports someone created and how many of them were marked as spam
+#[derive(Clone, Debug, Default)]
+pub struct ReportCounts {
+    pub reports: usize,
+    pub spam: usize,
+}
+
+/// The history of a report's author and reporter, used by the `author_min_*` and
+/// `reporter_min_*` [`RuleConditions`]
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    /// Reports created by the author (empty unless the author is authenticated)
+    pub author: ReportCounts,
+    /// Reports filed by the reporter
+    pub reporter: ReportCounts,
+}
+
+/// A report matched by [`simulate`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RuleMatch {
+    pub report: String,
+    pub address: String,
+    pub status: ReportStatus,
+}
+
+/// Compile a rule pattern (`None` if the pattern is empty)
+fn compile(pattern: &str) -> Result<Option<Regex>, String> {
+    if pattern.is_empty() {
+        return Ok(None);
+    }
+
+    RegexBuilder::new(pattern)
+        .size_limit(MAX_PATTERN_SIZE)
+        .build()
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+/// Compile a rule pattern once, reusing the compiled pattern for every later report
+fn compile_cached(pattern: &str) -> Result<Option<Regex>, String> {
+    static PATTERNS: OnceLock<Mutex<HashMap<String, Regex>>> = OnceLock::new();
+
+    if pattern.is_empty() {
+        return Ok(None);
+    }
+
+    let patterns = PATTERNS.get_or_init(|| Mutex::new(HashMap::new()));
+
+    if let Some(re) = patterns.lock().unwrap().get(pattern) {
+        return Ok(Some(re.clone()));
+    }
+
+    let re = compile(pattern)?;
+
+    if let Some(ref re) = re {
+        let mut patterns = patterns.lock().unwrap();
+
+        // edited rules leave their old patterns behind
+        if patterns.len() >= MAX_CACHED_PATTERNS {
+            patterns.clear();
+        }
+
+        patterns.insert(pattern.to_string(), re.clone());
+    }
+
+    Ok(re)
+}
+
+/// Check if an IP address matches an address or CIDR range
+pub fn ip_matches(range: &str, ip: IpAddr) -> bool {
+    let (addr, bits) = match range.trim().split_once('/') {
+        Some((addr, bits)) => match bits.parse::<u32>() {
+            Ok(b) => (addr, Some(b)),
+            Err(_) => return false,
+        },
+        None => (range.trim(), None),
+    };
+
+    match (addr.parse::<IpAddr>(), ip) {
+        (Ok(IpAddr::V4(net)), IpAddr::V4(ip)) => {
+            let bits = bits.unwrap_or(32).min(32);
+            let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+            (u32::from(net) & mask) == (u32::from(ip) & mask)
+        }
+        (Ok(IpAddr::V6(net)), IpAddr::V6(ip)) => {
+            let bits = bits.unwrap_or(128).min(128);
+            let mask = u128::MAX.checked_shl(128 - bits).unwrap_or(0);
+            (u128::from(net) & mask) == (u128::from(ip) & mask)
+        }
+        _ => false,
+    }
+}
+
+/// Get the IP address a request was sent from
+///
+/// `X-Forwarded-For` is only used when the connection comes from a trusted proxy, and then the
+/// right-most hop which isn't a trusted proxy is used (hops left of it can be set by the client).
+///
+/// # Arguments:
+/// * `req` - [`HttpRequest`]
+/// * `trusted_proxies` - addresses or CIDR ranges of trusted reverse proxies
+pub fn request_ip(req: &HttpRequest, trusted_proxies: &[String]) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    let forwarded = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .filter_map(|h| h.to_str().ok())
+        .collect::<Vec<&str>>()
+        .join(",");
+
+    forwarded_ip(peer, &forwarded, trusted_proxies)
+}
+
+/// Get the client address from the peer address and `X-Forwarded-For` chain of a request
+fn forwarded_ip(peer: IpAddr, forwarded: &str, trusted_proxies: &[String]) -> Option<IpAddr> {
+    let trusted = |ip: IpAddr| trusted_proxies.iter().any(|r| ip_matches(r, ip));
+
+    if !trusted(peer) {
+        return Some(peer);
+    }
+
+    let mut client = peer;
+
+    for hop in forwarded.split(',').rev() {
+        let hop = hop.trim();
+        let ip = match hop
+            .parse::<IpAddr>()
+            .ok()
+            .or_else(|| hop.parse::<std::net::SocketAddr>().ok().map(|a| a.ip()))
+        {
+            Some(ip) => ip,
+            // an unreadable hop can't be trusted any further
+            None => break,
+        };
+
+        client = ip;
+
+        if !trusted(ip) {
+            break;
+        }
+    }
+
+    Some(client)
+}
+
+impl RuleConditions {
+    /// If matching requires the [`History`] of the report
+    pub fn needs_history(&self) -> bool {
+        self.author_min_reports.is_some()
+            | self.author_min_spam.is_some()
+            | self.reporter_min_reports.is_some()
+            | self.reporter_min_spam.is_some()
+    }
+}
+
+/// A [`Rule`] with its patterns compiled
+pub struct CompiledRule<'a> {
+    pub rule: &'a Rule,
+    /// `None` if the pattern is empty
+    address: Option<Regex>,
+    /// `None` if the pattern is empty
+    content: Option<Regex>,
+}
+
+impl Rule {
+    /// Compile the patterns of the rule (see [`CompiledRule::matches`])
+    pub fn compile(&self) -> Result<CompiledRule<'_>, String> {
+        Ok(CompiledRule {
+            rule: self,
+            address: compile_cached(&self.conditions.address_pattern)
+                .map_err(|e| format!("Address pattern: {e}"))?,
+            content: compile_cached(&self.conditions.content_pattern)
+                .map_err(|e| format!("Content pattern: {e}"))?,
+        })
+    }
+
+    /// Check if the rule can be stored, normalizing its tags
+    pub fn validate(&mut self) -> Result<(), String> {
+        self.name = self.name.trim().to_string();
+
+        if self.name.is_empty() | (self.name.len() > 200) {
+            return Err(String::from("Name is invalid"));
+        }
+
+        if self.events.is_empty() {
+            return Err(String::from("Rule must have at least one event"));
+        }
+
+        if self.actions.is_empty() {
+            return Err(String::from("Rule must have at least one action"));
+        }
+
+        self.compile()?;
+
+        for range in &self.conditions.ips {
+            let addr = range.split('/').next().unwrap_or_default();
+            if addr.trim().parse::<IpAddr>().is_err() {
+                return Err(format!("IP address is invalid: {range}"));
+            }
+        }
+
+        for action in self.actions.iter_mut() {
+            if let RuleAction::Tag(tag) = action {
+                *tag = normalize_tag(tag).ok_or(String::from("Tag is invalid"))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CompiledRule<'_> {
+    /// Check if the rule matches a [`Report`]
+    ///
+    /// # Arguments:
+    /// * `report` - [`Report`]
+    /// * `ip` - the IP address the report was sent from (if known)
+    /// * `history` - the [`History`] of the report (only required if
+    ///   [`RuleConditions::needs_history`])
+    pub fn matches(&self, report: &Report, ip: Option<IpAddr>, history: &History) -> bool {
+        let c = &self.rule.conditions;
+
+        if !c.report_types.is_empty() && !c.report_types.contains(&report.report_type) {
+            return false;
+        }
+
+        if !c.statuses.is_empty() && !c.statuses.contains(&report.status) {
+            return false;
+        }
+
+        for (pattern, value) in [
+            (&self.address, &report.address),
+            (&self.content, &report.content),
+        ] {
+            if let Some(re) = pattern {
+                if !re.is_match(value) {
+                    return false;
+                }
+            }
+        }
+
+        if let Some(length) = c.content_shorter_than {
+            if report.content.trim().chars().count() >= length {
+                return false;
+            }
+        }
+
+        if !c.authors.is_empty() && !c.authors.contains(&report.author) {
+            return false;
+        }
+
+        let authenticated = authenticated_author(report);
+        for (min, count) in [
+            (c.author_min_reports, history.author.reports),
+            (c.author_min_spam, history.author.spam),
+        ] {
+            if let Some(min) = min {
+                if !authenticated || (count < min) {
+                    return false;
+                }
+            }
+        }
+
+        for (min, count) in [
+            (c.reporter_min_reports, history.reporter.reports),
+            (c.reporter_min_spam, history.reporter.spam),
+        ] {
+            if let Some(min) = min {
+                if report.reporter.is_empty() || (count < min) {
+                    return false;
+                }
+            }
+        }
+
+        if !c.ips.is_empty() {
+            match ip {
+                Some(ip) if c.ips.iter().any(|r| ip_matches(r, ip)) => (),
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
+/// If the author of a report is the account that created it
+///
+/// The author of anonymous reports is supplied by the client, so it can't be trusted.
+fn authenticated_author(report: &Report) -> bool {
+    !report.author.is_empty() && (report.author == report.reporter)
+}
+
+/// Get the [`History`] of a report's author and reporter
+pub async fn history(db: &Database, report: &Report) -> Result<History, String> {
+    let mut history = History::default();
+
+    if report.reporter.is_empty() {
+        return Ok(history);
+    }
+
+    let (reports, spam) = db.count_report_history(&report.reporter, None).await?;
+    history.reporter = ReportCounts { reports, spam };
+
+    if authenticated_author(report) {
+        let (reports, spam) = db
+            .count_report_history(&report.reporter, Some(&report.author))
+            .await?;
+        history.author = ReportCounts { reports, spam };
+    }
+
+    Ok(history)
+}
+
+/// Evaluate every enabled rule for an event and apply the actions of the matching rules
+///
+/// The given report is updated with the changes made.
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `report` - [`Report`]
+/// * `event` - [`RuleEvent`]
+/// * `ip` - the IP address the report was sent from (if known)
+pub async fn apply(
+    data: &web::Data<AppData>,
+    report: &mut Report,
+    event: RuleEvent,
+    ip: Option<IpAddr>,
+) {
+    let rules = match data.db.get_rules().await.payload {
+        Some(r) => r,
+        None => return,
+    };
+
+    let mut history: Option<History> = None;
+
+    for rule in rules
+        .iter()
+        .filter(|r| r.enabled && r.events.contains(&event))
+    {
+        // patterns are checked when rules are stored, so this only fails for edited rows
+        let compiled = match rule.compile() {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("Failed to compile rule \"{}\": {e}", rule.name);
+                continue;
+            }
+        };
+
+        if rule.conditions.needs_history() && history.is_none() {
+            match self::history(&data.db, report).await {
+                Ok(h) => history = Some(h),
+                Err(e) => {
+                    eprintln!("Failed to get history of {}: {e}", report.id);
+                    continue;
+                }
+            }
+        }
+
+        if !compiled.matches(report, ip, &history.clone().unwrap_or_default()) {
+            continue;
+        }
+
+        for action in &rule.actions {
+            let bulk = match action {
+                RuleAction::SetStatus(status) => BulkAction::Status(status.clone()),
+                RuleAction::SetPriority(priority) => BulkAction::Priority(*priority),
+                RuleAction::Tag(tag) => BulkAction::Tag(tag.clone()),
+                RuleAction::Assign(username) => BulkAction::Assign(username.clone()),
+                RuleAction::Notify => {
+                    notify::dispatch(
+                        data,
+                        ReportEvent::Escalated(
+                            report.clone(),
+                            format!("Matched rule \"{}\"", rule.name),
+                        ),
+                    );
+
+                    continue;
+                }
+            };
+
+            let res = data
+                .db
+                .apply_bulk_action(vec![report.id.clone()], None, bulk.clone(), RULES_EDITOR)
+                .await;
+
+            if !res.success {
+                eprintln!(
+                    "Failed to apply rule \"{}\" to {}: {}",
+                    rule.name, report.id, res.message
+                );
+                continue;
+            }
+
+            match bulk {
+                BulkAction::Status(status) => {
+                    report.status = status;
+                    report.status_timestamp = dorsal::utility::unix_epoch_timestamp();
+                }
+                BulkAction::Priority(priority) => {
+                    report.priority = priority.min(crate::db::MAX_PRIORITY)
+                }
+                BulkAction::Tag(tag) if !report.tags.contains(&tag) => report.tags.push(tag),
+                BulkAction::Assign(username) => report.assignee = username,
+                _ => (),
+            }
+        }
+
+        if rule.stop {
+            break;
+        }
+    }
+}
+
+/// Find every existing report a rule matches as it is now (without changing anything)
+///
+/// Only the newest [`MAX_BULK_REPORTS`] reports are checked, [`RuleConditions::ips`] never match.
+pub async fn simulate(db: &Database, rule: &Rule) -> Result<Vec<RuleMatch>, String> {
+    let res = db
+        .get_every_report_by_filter(&ReportFilter::default())
+        .await;
+
+    let mut reports = match res.payload {
+        Some(r) => r,
+        None => return Err(res.message),
+    };
+
+    reports.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+    reports.truncate(MAX_BULK_REPORTS);
+
+    let compiled = rule.compile()?;
+    let mut histories: HashMap<(String, String), History> = HashMap::new();
+    let mut out: Vec<RuleMatch> = Vec::new();
+
+    for report in reports {
+        let key = (report.author.clone(), report.reporter.clone());
+        if rule.conditions.needs_history() && !histories.contains_key(&key) {
+            let history = history(db, &report).await?;
+            histories.insert(key.clone(), history);
+        }
+
+        let history = histories.get(&key).cloned().unwrap_or_default();
+
+        if compiled.matches(&report, None, &history) {
+            out.push(RuleMatch {
+                report: report.id,
+                address: report.address,
+                status: report.status,
+            });
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(conditions: RuleConditions) -> Rule {
+        Rule {
+            id: String::new(),
+            name: String::from("test"),
+            position: 0,
+            enabled: true,
+            events: vec![RuleEvent::Created],
+            conditions,
+            actions: vec![RuleAction::Notify],
+            stop: false,
+            timestamp: 0,
+        }
+    }
+
+    /// A report created by an authenticated author (if `author` isn't empty)
+    fn report(address: &str, content: &str, author: &str) -> Report {
+        Report {
+            address: address.to_string(),
+            content: content.to_string(),
+            author: author.to_string(),
+            reporter: author.to_string(),
+            report_type: ReportType::Abuse,
+            status: ReportStatus::Active,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ip_ranges() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+
+        assert!(ip_matches("203.0.113.5", ip("203.0.113.5")));
+        assert!(!ip_matches("203.0.113.5", ip("203.0.113.6")));
+        assert!(ip_matches("203.0.113.0/24", ip("203.0.113.200")));
+        assert!(!ip_matches("203.0.113.0/24", ip("203.0.114.1")));
+        assert!(ip_matches("0.0.0.0/0", ip("198.51.100.1")));
+        assert!(ip_matches("2001:db8::/32", ip("2001:db8:1::1")));
+        assert!(!ip_matches("2001:db8::/32", ip("2001:db9::1")));
+
+        // families never match each other, invalid ranges never match
+        assert!(!ip_matches("::/0", ip("203.0.113.5")));
+        assert!(!ip_matches("203.0.113.0/abc", ip("203.0.113.5")));
+        assert!(!ip_matches("example", ip("203.0.113.5")));
+    }
+
+    #[test]
+    fn forwarded_hops() {
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        let proxies = vec![String::from("10.0.0.0/8")];
+
+        // untrusted peers can't set their address
+        assert_eq!(
+            forwarded_ip(ip("198.51.100.1"), "203.0.113.5", &proxies),
+            Some(ip("198.51.100.1"))
+        );
+
+        // the right-most untrusted hop is used, not the client-controlled left-most one
+        assert_eq!(
+            forwarded_ip(ip("10.0.0.1"), "1.1.1.1, 203.0.113.5, 10.0.0.2", &proxies),
+            Some(ip("203.0.113.5"))
+        );
+
+        assert_eq!(
+            forwarded_ip(ip("10.0.0.1"), "", &proxies),
+            Some(ip("10.0.0.1"))
+        );
+        assert_eq!(
+            forwarded_ip(ip("10.0.0.1"), "203.0.113.5", &[]),
+            Some(ip("10.0.0.1"))
+        );
+    }
+
+    #[test]
+    fn matches_conditions() {
+        let history = History::default();
+
+        let r = rule(RuleConditions {
+            address_pattern: String::from(r"^https://example\.com/"),
+            content_pattern: String::from("(?i)buy now"),
+            ..Default::default()
+        });
+        let compiled = r.compile().unwrap();
+
+        assert!(compiled.matches(
+            &report("https://example.com/a", "BUY NOW", ""),
+            None,
+            &history
+        ));
+        assert!(!compiled.matches(
+            &report("https://example.org/a", "buy now", ""),
+            None,
+            &history
+        ));
+        assert!(!compiled.matches(
+            &report("https://example.com/a", "hello", ""),
+            None,
+            &history
+        ));
+
+        let r = rule(RuleConditions {
+            report_types: vec![ReportType::Illegal],
+            ..Default::default()
+        });
+        assert!(!r
+            .compile()
+            .unwrap()
+            .matches(&report("a", "b", ""), None, &history));
+
+        let r = rule(RuleConditions {
+            content_shorter_than: Some(5),
+            authors: vec![String::from("alice")],
+            ..Default::default()
+        });
+        let compiled = r.compile().unwrap();
+        assert!(compiled.matches(&report("a", " hi ", "alice"), None, &history));
+        assert!(!compiled.matches(&report("a", "hello", "alice"), None, &history));
+        assert!(!compiled.matches(&report("a", "hi", "bob"), None, &history));
+    }
+
+    #[test]
+    fn matches_history_and_ips() {
+        let r = rule(RuleConditions {
+            author_min_reports: Some(3),
+            author_min_spam: Some(1),
+            ..Default::default()
+        });
+        let compiled = r.compile().unwrap();
+        let history = History {
+            author: ReportCounts {
+                reports: 3,
+                spam: 1,
+            },
+            ..Default::default()
+        };
+
+        assert!(compiled.matches(&report("a", "b", "alice"), None, &history));
+        assert!(!compiled.matches(
+            &report("a", "b", "alice"),
+            None,
+            &History {
+                author: ReportCounts {
+                    reports: 3,
+                    spam: 0,
+                },
+                ..Default::default()
+            }
+        ));
+        // anonymous authors have no history, even if they claim to be someone
+        assert!(!compiled.matches(&report("a", "b", ""), None, &history));
+        let mut spoofed = report("a", "b", "alice");
+        spoofed.reporter = String::from("anon:0123456789abcdef");
+        assert!(!compiled.matches(&spoofed, None, &history));
+
+        let r = rule(RuleConditions {
+            reporter_min_spam: Some(2),
+            ..Default::default()
+        });
+        let compiled = r.compile().unwrap();
+        let history = History {
+            reporter: ReportCounts {
+                reports: 5,
+                spam: 2,
+            },
+            ..Default::default()
+        };
+
+        assert!(compiled.matches(&spoofed, None, &history));
+        assert!(!compiled.matches(&spoofed, None, &History::default()));
+        // reports without a known reporter have no history
+        assert!(!compiled.matches(&report("a", "b", ""), None, &history));
+
+        let r = rule(RuleConditions {
+            ips: vec![String::from("203.0.113.0/24")],
+            ..Default::default()
+        });
+        let compiled = r.compile().unwrap();
+        let history = History::default();
+
+        assert!(compiled.matches(&report("a", "b", ""), "203.0.113.9".parse().ok(), &history));
+        assert!(!compiled.matches(&report("a", "b", ""), "198.51.100.1".parse().ok(), &history));
+        assert!(!compiled.matches(&report("a", "b", ""), None, &history));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        let mut r = rule(RuleConditions {
+            content_pattern: String::from("("),
+            ..Default::default()
+        });
+
+        assert!(r.compile().is_err());
+        assert!(r.validate().is_err());
+    }
+}
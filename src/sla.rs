@@ -0,0 +1,242 @@
+//! Handling time targets (SLAs) for reports and escalation of reports breaching them
+use dorsal::DefaultReturn;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, SlaAction, SlaConfig, SlaTarget};
+use crate::dates::{self, HOUR};
+use crate::db::{BulkAction, Database, Report, ReportFilter, ReportStatus};
+use crate::email::Mailer;
+use crate::notify::{self, ReportEvent};
+use crate::stats::Period;
+
+/// The name stored in the audit entries of SLA escalations
+pub const SLA_EDITOR: &str = "system:sla";
+
+impl SlaConfig {
+    /// Get the [`SlaTarget`] of a report type
+    pub fn target(&self, report_type: &crate::db::ReportType) -> Option<&SlaTarget> {
+        self.targets.iter().find(|t| &t.report_type == report_type)
+    }
+
+    /// Check if a [`Report`] is active and has been waiting longer than its target
+    ///
+    /// # Arguments:
+    /// * `report` - [`Report`]
+    /// * `now` - the current unix timestamp (in milliseconds)
+    pub fn is_breached(&self, report: &Report, now: u128) -> bool {
+        if report.status != ReportStatus::Active {
+            return false;
+        }
+
+        match self.target(&report.report_type) {
+            Some(target) => report.time_in_status(now) > target.hours as u128 * HOUR,
+            None => false,
+        }
+    }
+}
+
+/// SLA compliance of a single report type during a [`Period`]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SlaCompliance {
+    pub report_type: crate::db::ReportType,
+    /// The target (in hours)
+    pub hours: u64,
+    /// The number of reports created during the period which have been handled
+    pub handled: usize,
+    /// The number of handled reports which were handled within the target
+    pub within: usize,
+    /// The number of reports (created at any time) currently breaching the target
+    pub breaching: usize,
+}
+
+impl SlaCompliance {
+    /// The share of handled reports handled within the target as a percentage (ex: `97.5%`)
+    pub fn percentage(&self) -> String {
+        if self.handled == 0 {
+            return String::from("n/a");
+        }
+
+        format!("{:.1}%", self.within as f64 / self.handled as f64 * 100.0)
+    }
+}
+
+/// SLA compliance of every report type with a target during a [`Period`]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SlaReport {
+    pub period: Period,
+    pub compliance: Vec<SlaCompliance>,
+}
+
+/// Calculate the [`SlaReport`] of a [`Period`]
+///
+/// Reports count as handled once their status changed from [`ReportStatus::Active`], using
+/// their last status change as the time they were handled.
+pub async fn get_report(
+    db: &Database,
+    config: &SlaConfig,
+    period: Period,
+) -> DefaultReturn<Option<SlaReport>> {
+    let now = dorsal::utility::unix_epoch_timestamp();
+
+    let res = db.get_reports_in_period(period.from, period.to).await;
+    let reports = match res.payload {
+        Some(r) => r,
+        None => {
+            return DefaultReturn {
+                success: false,
+                message: res.message,
+                payload: None,
+            }
+        }
+    };
+
+    let res = db
+        .get_every_report_by_filter(&ReportFilter {
+            status: Some(ReportStatus::Active),
+            ..Default::default()
+        })
+        .await;
+
+    let active = match res.payload {
+        Some(r) => r,
+        None => {
+            return DefaultReturn {
+                success: false,
+                message: res.message,
+                payload: None,
+            }
+        }
+    };
+
+    let compliance = config
+        .targets
+        .iter()
+        .map(|target| {
+            let handled: Vec<&Report> = reports
+                .iter()
+                .filter(|r| {
+                    (r.report_type == target.report_type)
+                        && (r.status != ReportStatus::Active)
+                        && (r.status_timestamp > 0)
+                })
+                .collect();
+
+            SlaCompliance {
+                report_type: target.report_type.clone(),
+                hours: target.hours,
+                within: handled
+                    .iter()
+                    .filter(|r| {
+                        r.status_timestamp.saturating_sub(r.timestamp)
+                            <= target.hours as u128 * HOUR
+                    })
+                    .count(),
+                handled: handled.len(),
+                breaching: active
+                    .iter()
+                    .filter(|r| (r.report_type == target.report_type) && config.is_breached(r, now))
+                    .count(),
+            }
+        })
+        .collect();
+
+    DefaultReturn {
+        success: true,
+        message: String::from("Calculated SLA compliance"),
+        payload: Some(SlaReport { period, compliance }),
+    }
+}
+
+/// Escalate every report breaching its target which hasn't been escalated since it got its
+/// current status, returns the number of escalated reports
+///
+/// # Arguments:
+/// * `db` - [`Database`]
+/// * `config` - [`Config`]
+/// * `mailer` - [`Mailer`] used for [`SlaAction::Notify`]
+/// * `client` - the HTTP client used for webhooks
+pub async fn escalate(
+    db: &Database,
+    config: &Config,
+    mailer: &Mailer,
+    client: &awc::Client,
+) -> Result<usize, String> {
+    let now = dorsal::utility::unix_epoch_timestamp();
+
+    let res = db
+        .get_every_report_by_filter(&ReportFilter {
+            status: Some(ReportStatus::Active),
+            ..Default::default()
+        })
+        .await;
+
+    let reports = match res.payload {
+        Some(r) => r,
+        None => return Err(res.message),
+    };
+
+    let mut escalated: usize = 0;
+
+    for report in reports {
+        if !config.sla.is_breached(&report, now) || (report.escalated >= report.status_since()) {
+            continue;
+        }
+
+        let target = config.sla.target(&report.report_type).unwrap();
+        let mut priority = report.priority;
+
+        if target.actions.contains(&SlaAction::BumpPriority) {
+            priority = priority.saturating_add(1);
+        }
+
+        // mark as escalated first so a failed action isn't retried forever
+        let res = db
+            .escalate_report_by_id(report.id.clone(), priority, SLA_EDITOR)
+            .await;
+
+        if !res.success {
+            eprintln!("Failed to escalate report {}: {}", report.id, res.message);
+            continue;
+        }
+
+        escalated += 1;
+
+        for action in &target.actions {
+            match action {
+                SlaAction::BumpPriority => (),
+                SlaAction::Notify => notify::send(
+                    client.clone(),
+                    mailer.clone(),
+                    config,
+                    ReportEvent::Escalated(
+                        Report {
+                            priority,
+                            ..report.clone()
+                        },
+                        format!(
+                            "Waiting for longer than the {}h target ({})",
+                            target.hours,
+                            dates::duration_text(report.time_in_status(now))
+                        ),
+                    ),
+                ),
+                SlaAction::SetStatus(status) => {
+                    let res = db
+                        .apply_bulk_action(
+                            vec![report.id.clone()],
+                            None,
+                            BulkAction::Status(status.clone()),
+                            SLA_EDITOR,
+                        )
+                        .await;
+
+                    if !res.success {
+                        eprintln!("Failed to change status of {}: {}", report.id, res.message);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(escalated)
+}
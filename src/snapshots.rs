@@ -0,0 +1,518 @@
+//! Snapshots of reported addresses, taken when reports are created
+//!
+//! Addresses are resolved before every request (including redirects) and requests are pinned to
+//! the checked address, so a report can't be used to reach private networks.
+use actix_web::web;
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+use url::{Host, Url};
+
+use crate::config::SnapshotConfig;
+use crate::db::{AppData, Database, Report, Snapshot};
+
+/// The most redirects followed before giving up
+pub const MAX_REDIRECTS: usize = 5;
+
+/// The largest body that can be stored, whatever `max_size` is (the size of a database column)
+pub const MAX_SIZE: usize = 1_000_000;
+
+thread_local! {
+    /// The HTTP client of snapshots, redirects are followed manually so every address is checked
+    static CLIENT: awc::Client = awc::Client::builder().disable_redirects().finish();
+}
+
+/// Get the HTTP client used to fetch untrusted addresses (doesn't follow redirects)
+pub fn client() -> awc::Client {
+    CLIENT.with(|c| c.clone())
+}
+
+/// Check if an IP address is publicly routable
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (a == 0)
+                // shared address space (100.64.0.0/10)
+                || ((a == 100) && (b & 0xc0 == 64))
+                // benchmarking (198.18.0.0/15)
+                || ((a == 198) && (b & 0xfe == 18))
+                // reserved (240.0.0.0/4)
+                || (a >= 240))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(ip));
+            }
+
+            let segments = ip.segments();
+            let first = segments[0];
+
+            // NAT64 (64:ff9b::/96) and 6to4 (2002::/16) embed the IPv4 address they reach
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [a, b] = segments[6].to_be_bytes();
+                let [c, d] = segments[7].to_be_bytes();
+                return is_public_ip(IpAddr::V4([a, b, c, d].into()));
+            }
+
+            if first == 0x2002 {
+                let [a, b] = segments[1].to_be_bytes();
+                let [c, d] = segments[2].to_be_bytes();
+                return is_public_ip(IpAddr::V4([a, b, c, d].into()));
+            }
+
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // unique local (fc00::/7)
+                || (first & 0xfe00 == 0xfc00)
+                // link-local (fe80::/10)
+                || (first & 0xffc0 == 0xfe80)
+                // deprecated site-local (fec0::/10)
+                || (first & 0xffc0 == 0xfec0)
+                // documentation (2001:db8::/32)
+                || ((first == 0x2001) && (segments[1] == 0x0db8)))
+        }
+    }
+}
+
+/// Resolve the host of a URL to the address it should be fetched from
+///
+/// # Arguments:
+/// * `url` - the URL (must be `http` or `https`)
+/// * `allow_private` - don't reject addresses which aren't publicly routable
+pub async fn resolve(url: &Url, allow_private: bool) -> Result<SocketAddr, String> {
+    if (url.scheme() != "http") && (url.scheme() != "https") {
+        return Err(String::from("Address must be http or https"));
+    }
+
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+        Some(Host::Domain(domain)) => {
+            let domain = domain.to_string();
+
+            match web::block(move || (domain.as_str(), port).to_socket_addrs()).await {
+                Ok(Ok(addrs)) => addrs.collect(),
+                _ => return Err(String::from("Failed to resolve host")),
+            }
+        }
+        None => return Err(String::from("Address has no host")),
+    };
+
+    // every address must be public, otherwise the host could pick which one is used
+    if !allow_private && addrs.iter().any(|a| !is_public_ip(a.ip())) {
+        return Err(String::from("Address resolves to a private network"));
+    }
+
+    match addrs.first() {
+        Some(a) => Ok(*a),
+        None => Err(String::from("Failed to resolve host")),
+    }
+}
+
+/// Build a `GET` request to a URL, pinned to its checked address (see [`resolve`])
+///
+/// The client must not follow redirects itself (see [`client`]), redirects have to be requested
+/// through this again so their address is checked too.
+///
+/// # Arguments:
+/// * `client` - the HTTP client
+/// * `url` - the URL (must be `http` or `https`)
+/// * `allow_private` - don't reject addresses which aren't publicly routable
+pub async fn get(
+    client: &awc::Client,
+    url: &Url,
+    allow_private: bool,
+) -> Result<awc::ClientRequest, String> {
+    let addr = resolve(url, allow_private).await?;
+    Ok(client.get(url.as_str()).address(addr))
+}
+
+/// Get the readable text of an HTML document
+pub fn html_to_text(html: &str) -> String {
+    // ASCII lowercasing keeps byte offsets the same
+    let lower = html.to_ascii_lowercase();
+    let mut out = String::new();
+    let mut i: usize = 0;
+
+    while i < html.len() {
+        let rest = &lower[i..];
+
+        // skip elements without readable content
+        let skip = [
+            ("<script", "</script>"),
+            ("<style", "</style>"),
+            ("<!--", "-->"),
+        ]
+        .into_iter()
+        .find(|(start, _)| rest.starts_with(start));
+
+        if let Some((_, end)) = skip {
+            match rest.find(end) {
+                Some(e) => i += e + end.len(),
+                None => break,
+            }
+
+            out.push(' ');
+            continue;
+        }
+
+        if rest.starts_with('<') {
+            let end = match rest.find('>') {
+                Some(e) => e,
+                None => break,
+            };
+
+            // keep lines of block elements apart
+            let tag = rest[1..end].trim_start_matches('/');
+            if [
+                "br", "p", "div", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6",
+            ]
+            .iter()
+            .any(|t| tag.split_whitespace().next() == Some(t))
+            {
+                out.push('\n');
+            } else {
+                out.push(' ');
+            }
+
+            i += end + 1;
+            continue;
+        }
+
+        let next = rest.find('<').map(|n| i + n).unwrap_or(html.len());
+        out.push_str(&html[i..next]);
+        i = next;
+    }
+
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .lines()
+        .map(|l| l.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Fetch an address and build its [`Snapshot`]
+///
+/// Failures are recorded in [`Snapshot::error`] along with whatever was received. Bodies larger
+/// than `max_size` are cut off and marked as [`Snapshot::truncated`].
+///
+/// # Arguments:
+/// * `client` - the HTTP client (must not follow redirects itself, see [`client`])
+/// * `config` - [`SnapshotConfig`]
+/// * `report` - the ID of the report
+/// * `address` - the reported address
+pub async fn take(
+    client: &awc::Client,
+    config: &SnapshotConfig,
+    report: &str,
+    address: &str,
+) -> Snapshot {
+    let mut snapshot = Snapshot {
+        id: dorsal::utility::random_id(),
+        report: report.to_string(),
+        url: address.to_string(),
+        final_url: address.to_string(),
+        timestamp: dorsal::utility::unix_epoch_timestamp(),
+        ..Default::default()
+    };
+
+    let mut url = match Url::parse(address) {
+        Ok(u) => u,
+        Err(_) => {
+            snapshot.error = String::from("Address is invalid");
+            return snapshot;
+        }
+    };
+
+    let mut redirects: usize = 0;
+    let mut res = loop {
+        snapshot.final_url = url.to_string();
+
+        let req = match get(client, &url, config.allow_private).await {
+            Ok(r) => r,
+            Err(e) => {
+                snapshot.error = e;
+                return snapshot;
+            }
+        };
+
+        let res = match req
+            .timeout(Duration::from_secs(config.timeout))
+            .insert_header(("Accept", "text/html, */*;q=0.8"))
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                snapshot.error = format!("Failed to fetch address: {e}");
+                return snapshot;
+            }
+        };
+
+        let location = res
+            .headers()
+            .get("Location")
+            .and_then(|l| l.to_str().ok())
+            .map(|l| l.to_string());
+
+        match location {
+            Some(location) if res.status().is_redirection() => {
+                redirects += 1;
+
+                if redirects > MAX_REDIRECTS {
+                    snapshot.error = String::from("Too many redirects");
+                    return snapshot;
+                }
+
+                url = match url.join(&location) {
+                    Ok(u) => u,
+                    Err(_) => {
+                        snapshot.error = String::from("Redirect address is invalid");
+                        return snapshot;
+                    }
+                };
+            }
+            _ => break res,
+        }
+    };
+
+    snapshot.status = res.status().as_u16();
+    snapshot.headers = res
+        .headers()
+        .iter()
+        .filter(|(name, _)| name.as_str() != "set-cookie")
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).to_string(),
+            )
+        })
+        .collect();
+
+    let content_type = res
+        .headers()
+        .get("Content-Type")
+        .and_then(|t| t.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+
+    // read up to the limit, larger bodies are stored truncated instead of not at all
+    let limit = config.max_size.min(MAX_SIZE);
+    let mut body: Vec<u8> = Vec::new();
+
+    let read = actix_web::rt::time::timeout(Duration::from_secs(config.timeout), async {
+        while let Some(chunk) = res.next().await {
+            let chunk = chunk.map_err(|e| e.to_string())?;
+
+            if body.len() + chunk.len() > limit {
+                body.extend_from_slice(&chunk[..limit - body.len()]);
+                return Ok(true);
+            }
+
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok::<bool, String>(false)
+    })
+    .await;
+
+    match read {
+        Ok(Ok(truncated)) => snapshot.truncated = truncated,
+        Ok(Err(e)) => {
+            snapshot.error = format!("Failed to read body: {e}");
+            return snapshot;
+        }
+        Err(_) => {
+            snapshot.error = String::from("Failed to read body: timed out");
+            return snapshot;
+        }
+    }
+
+    snapshot.hash = Sha256::digest(&body)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    if content_type.contains("html") {
+        snapshot.html = String::from_utf8_lossy(&body).to_string();
+        snapshot.text = html_to_text(&snapshot.html);
+    } else if content_type.starts_with("text/") {
+        snapshot.text = String::from_utf8_lossy(&body).to_string();
+    }
+
+    snapshot
+}
+
+/// Take and store the [`Snapshot`] of a new [`Report`] in the background (if enabled)
+pub fn capture(data: &web::Data<AppData>, report: &Report) {
+    if !data.config.snapshots.enabled {
+        return;
+    }
+
+    let client = client();
+    let db: Database = data.db.clone();
+    let config = data.config.snapshots.clone();
+    let id = report.id.clone();
+    let address = report.address.clone();
+
+    actix_web::rt::spawn(async move {
+        let snapshot = take(&client, &config, &id, &address).await;
+        let res = db.create_snapshot(&snapshot).await;
+
+        if !res.success {
+            eprintln!("Failed to store snapshot of {id}: {}", res.message);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, HttpResponse, HttpServer};
+
+    /// Start a local server to take snapshots of, returning its address
+    fn serve() -> String {
+        let server = HttpServer::new(|| {
+            App::new()
+                .route(
+                    "/page",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("text/html")
+                            .body("<p>Hello</p><script>ignored()</script><p>world</p>")
+                    }),
+                )
+                .route(
+                    "/redirect",
+                    web::get().to(|| async {
+                        HttpResponse::Found()
+                            .append_header(("Location", "/page"))
+                            .finish()
+                    }),
+                )
+                .route(
+                    "/loop",
+                    web::get().to(|| async {
+                        HttpResponse::Found()
+                            .append_header(("Location", "/loop"))
+                            .finish()
+                    }),
+                )
+                .route(
+                    "/large",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("text/plain")
+                            .body("a".repeat(10_000))
+                    }),
+                )
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        format!("http://{addr}")
+    }
+
+    fn config(allow_private: bool) -> SnapshotConfig {
+        SnapshotConfig {
+            enabled: true,
+            max_size: 1024,
+            timeout: 5,
+            allow_private,
+        }
+    }
+
+    #[test]
+    fn private_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "::ffff:10.0.0.1",
+            "fc00::1",
+            "fe80::1",
+            "fec0::1",
+            "64:ff9b::a00:1",
+            "64:ff9b::7f00:1",
+            "2002:a00:1::1",
+            "2002:c0a8:101::1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{ip} is private");
+        }
+
+        for ip in [
+            "1.1.1.1",
+            "93.184.216.34",
+            "2606:4700::1111",
+            "64:ff9b::101:101",
+            "2002:101:101::1",
+        ] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{ip} is public");
+        }
+    }
+
+    #[actix_web::test]
+    async fn rejects_private_addresses() {
+        let server = serve();
+        let snapshot = take(&client(), &config(false), "r", &format!("{server}/page")).await;
+
+        assert_eq!(snapshot.error, "Address resolves to a private network");
+        assert_eq!(snapshot.status, 0);
+    }
+
+    #[actix_web::test]
+    async fn follows_redirects() {
+        let server = serve();
+        let snapshot = take(&client(), &config(true), "r", &format!("{server}/redirect")).await;
+
+        assert_eq!(snapshot.error, "");
+        assert_eq!(snapshot.status, 200);
+        assert_eq!(snapshot.final_url, format!("{server}/page"));
+        assert_eq!(snapshot.text, "Hello\nworld");
+        assert!(!snapshot.truncated);
+    }
+
+    #[actix_web::test]
+    async fn stops_redirect_loops() {
+        let server = serve();
+        let snapshot = take(&client(), &config(true), "r", &format!("{server}/loop")).await;
+
+        assert_eq!(snapshot.error, "Too many redirects");
+    }
+
+    #[actix_web::test]
+    async fn truncates_large_bodies() {
+        let server = serve();
+        let snapshot = take(&client(), &config(true), "r", &format!("{server}/large")).await;
+
+        assert_eq!(snapshot.error, "");
+        assert!(snapshot.truncated);
+        assert_eq!(snapshot.text.len(), 1024);
+    }
+}
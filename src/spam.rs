@@ -0,0 +1,204 @@
+//! Spam scoring of new reports
+//!
+//! Scores combine a few local signals (no external services are used), reports scoring at least
+//! the configured threshold are marked as spam as soon as they're created.
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+
+use crate::config::SpamConfig;
+use crate::dates::MINUTE;
+use crate::db::{AppData, BulkAction, Database, Report, ReportStatus, REDACTED_CONTENT};
+
+/// The name stored in the audit entries of reports marked as spam by their score
+pub const SPAM_EDITOR: &str = "system:spam";
+
+/// The highest spam score
+pub const MAX_SCORE: u8 = 100;
+
+/// The signals a spam score is calculated from
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SpamSignals {
+    /// The blocklisted words and phrases found in the content or address
+    pub blocklist: Vec<String>,
+    /// The number of other recent reports with the same content
+    pub repeated: usize,
+    /// The share of words in the content which are links
+    pub link_density: f64,
+    /// The number of recent reports by the same reporter (or about the same address if the
+    /// reporter is unknown), including this one
+    ///
+    /// Reporters are used instead of authors since anyone can claim an author (see
+    /// [`crate::reputation::reporter`]).
+    pub velocity: usize,
+}
+
+impl SpamSignals {
+    /// Calculate the spam score (`0` to [`MAX_SCORE`])
+    pub fn score(&self, config: &SpamConfig) -> u8 {
+        let mut score: u32 = self.blocklist.len() as u32 * config.blocklist_weight as u32;
+
+        if self.repeated > 0 {
+            score += config.repeated_weight as u32;
+        }
+
+        if self.link_density > config.max_link_density {
+            score += config.link_weight as u32;
+        }
+
+        if self.velocity > config.max_velocity {
+            score += config.velocity_weight as u32;
+        }
+
+        score.min(MAX_SCORE as u32) as u8
+    }
+}
+
+/// Lowercase text and replace everything but letters and numbers with single spaces
+///
+/// The result starts and ends with a space so whole words can be found with `contains`.
+pub fn normalize_words(text: &str) -> String {
+    let words: Vec<String> = text
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_string())
+        .collect();
+
+    format!(" {} ", words.join(" "))
+}
+
+/// Get every blocklisted word or phrase found in the given text
+pub fn blocklist_matches(blocklist: &[String], text: &str) -> Vec<String> {
+    let text = normalize_words(text);
+
+    blocklist
+        .iter()
+        .filter(|term| {
+            let term = normalize_words(term);
+            (term.trim() != "") && text.contains(&term)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Get the share (`0.0` to `1.0`) of words in the given text which are links
+pub fn link_density(text: &str) -> f64 {
+    let words: Vec<&str> = text.split_whitespace().collect();
+
+    if words.is_empty() {
+        return 0.0;
+    }
+
+    let links = words
+        .iter()
+        .filter(|w| {
+            let w = w.to_lowercase();
+            w.contains("://") || w.starts_with("www.")
+        })
+        .count();
+
+    links as f64 / words.len() as f64
+}
+
+/// Collect the [`SpamSignals`] of a (stored) [`Report`]
+///
+/// # Arguments:
+/// * `db` - [`Database`]
+/// * `config` - [`SpamConfig`]
+/// * `report` - [`Report`]
+pub async fn signals(
+    db: &Database,
+    config: &SpamConfig,
+    report: &Report,
+) -> Result<SpamSignals, String> {
+    let from = report
+        .timestamp
+        .saturating_sub(config.window as u128 * MINUTE);
+
+    let res = db.get_reports_in_period(from, report.timestamp + 1).await;
+    let recent = match res.payload {
+        Some(r) => r,
+        None => return Err(res.message),
+    };
+
+    let content = normalize_words(&report.content);
+    let others = recent.iter().filter(|r| r.id != report.id);
+
+    Ok(SpamSignals {
+        blocklist: blocklist_matches(
+            &config.blocklist,
+            &format!("{} {}", report.content, report.address),
+        ),
+        repeated: others
+            .clone()
+            .filter(|r| (r.content != REDACTED_CONTENT) && (normalize_words(&r.content) == content))
+            .count(),
+        link_density: link_density(&report.content),
+        velocity: others
+            .filter(|r| {
+                if report.reporter.is_empty() {
+                    r.reporter.is_empty() && (r.canonical_address == report.canonical_address)
+                } else {
+                    r.reporter == report.reporter
+                }
+            })
+            .count()
+            + 1,
+    })
+}
+
+/// Score a new [`Report`] and mark it as spam if it scores at least the threshold (if enabled)
+///
+/// The given report is updated with the changes made.
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `report` - [`Report`]
+pub async fn check(data: &web::Data<AppData>, report: &mut Report) {
+    let config = &data.config.spam;
+
+    if !config.enabled {
+        return;
+    }
+
+    let score = match signals(&data.db, config, report).await {
+        Ok(s) => s.score(config),
+        Err(e) => {
+            eprintln!("Failed to score report {}: {e}", report.id);
+            return;
+        }
+    };
+
+    let res = data.db.set_spam_score_by_id(report.id.clone(), score).await;
+
+    if !res.success {
+        eprintln!(
+            "Failed to store spam score of {}: {}",
+            report.id, res.message
+        );
+        return;
+    }
+
+    report.spam_score = score;
+
+    if (config.threshold == 0) || (score < config.threshold) {
+        return;
+    }
+
+    let res = data
+        .db
+        .apply_bulk_action(
+            vec![report.id.clone()],
+            None,
+            BulkAction::Status(ReportStatus::Spam),
+            SPAM_EDITOR,
+        )
+        .await;
+
+    if res.success {
+        report.status = ReportStatus::Spam;
+        report.status_timestamp = dorsal::utility::unix_epoch_timestamp();
+    } else {
+        eprintln!("Failed to mark {} as spam: {}", report.id, res.message);
+    }
+}
@@ -0,0 +1,258 @@
+//! Aggregated report statistics for transparency reports
+use dorsal::DefaultReturn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::dates;
+use crate::db::{Report, ReportStatus, ReportType};
+
+/// A range of time statistics are calculated over
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Period {
+    /// The name of the period (ex: `2024-Q2`)
+    pub label: String,
+    /// The start of the period (unix timestamp in milliseconds, inclusive)
+    pub from: u128,
+    /// The end of the period (unix timestamp in milliseconds, exclusive)
+    pub to: u128,
+}
+
+impl Period {
+    /// Parse a period from its label
+    ///
+    /// Accepted formats are a year (`2024`), a quarter (`2024-Q2`) or a month (`2024-05`).
+    pub fn parse(label: &str) -> Option<Period> {
+        let label = label.trim().to_uppercase();
+        let (year, rest) = match label.split_once('-') {
+            Some((year, rest)) => (year, Some(rest)),
+            None => (label.as_str(), None),
+        };
+
+        let year = year
+            .parse::<i64>()
+            .ok()
+            .filter(|y| (1970..=9999).contains(y))?;
+
+        // get months
+        let (first, last) = match rest {
+            None => (1, 12),
+            Some(quarter) if quarter.starts_with('Q') => {
+                let quarter = quarter[1..]
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|q| (1..=4).contains(q))?;
+                (quarter * 3 - 2, quarter * 3)
+            }
+            Some(month) => {
+                let month = month.parse::<i64>().ok().filter(|m| (1..=12).contains(m))?;
+                (month, month)
+            }
+        };
+
+        Some(Period {
+            label,
+            from: dates::timestamp_from_civil(year, first, 1),
+            to: if last == 12 {
+                dates::timestamp_from_civil(year + 1, 1, 1)
+            } else {
+                dates::timestamp_from_civil(year, last + 1, 1)
+            },
+        })
+    }
+
+    /// The quarter the given unix timestamp (in milliseconds) is in
+    pub fn quarter_of(timestamp: u128) -> Period {
+        let (year, month, _) = dates::civil_from_timestamp(timestamp);
+        Self::parse(&format!("{year}-Q{}", (month - 1) / 3 + 1)).unwrap()
+    }
+
+    /// Parse a period from an (optional) query string value, defaulting to the current quarter
+    pub fn from_query(value: &Option<String>) -> Option<Period> {
+        match value {
+            Some(v) if !v.is_empty() => Self::parse(v),
+            _ => Some(Self::quarter_of(dorsal::utility::unix_epoch_timestamp())),
+        }
+    }
+}
+
+/// Aggregated statistics of every [`Report`] created during a [`Period`]
+///
+/// Only contains totals, never any data about individual reports.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReportStats {
+    pub period: Period,
+    /// The number of reports created
+    pub total: usize,
+    /// The number of reports of each [`ReportType`]
+    pub by_type: BTreeMap<String, usize>,
+    /// The number of reports with each [`ReportStatus`]
+    pub by_status: BTreeMap<String, usize>,
+    /// The median time (in milliseconds) between a report being created and it being archived
+    ///
+    /// `None` if no report from the period has been archived.
+    pub median_resolution: Option<u128>,
+    /// The share of reports marked as spam (between 0 and 1)
+    pub spam_ratio: f64,
+}
+
+impl ReportStats {
+    /// Calculate statistics over the given reports (which should all be from `period`)
+    pub fn calculate(period: Period, reports: &[Report]) -> ReportStats {
+        let mut by_type: BTreeMap<String, usize> = [
+            ReportType::Harassment,
+            ReportType::Abuse,
+            ReportType::Illegal,
+            ReportType::Harmful,
+            ReportType::Other,
+        ]
+        .iter()
+        .map(|t| (t.to_string(), 0))
+        .collect();
+
+        let mut by_status: BTreeMap<String, usize> = [
+            ReportStatus::Active,
+            ReportStatus::Archived,
+            ReportStatus::Spam,
+        ]
+        .iter()
+        .map(|s| (s.to_string(), 0))
+        .collect();
+
+        let mut resolution_times: Vec<u128> = Vec::new();
+
+        for report in reports {
+            *by_type.entry(report.report_type.to_string()).or_default() += 1;
+            *by_status.entry(report.status.to_string()).or_default() += 1;
+
+            if (report.status == ReportStatus::Archived) && (report.status_timestamp > 0) {
+                resolution_times.push(report.status_timestamp.saturating_sub(report.timestamp));
+            }
+        }
+
+        // median
+        resolution_times.sort_unstable();
+        let median_resolution = match resolution_times.len() {
+            0 => None,
+            n if n % 2 == 0 => Some((resolution_times[n / 2 - 1] + resolution_times[n / 2]) / 2),
+            n => Some(resolution_times[n / 2]),
+        };
+
+        ReportStats {
+            period,
+            total: reports.len(),
+            spam_ratio: if reports.is_empty() {
+                0.0
+            } else {
+                by_status[&ReportStatus::Spam.to_string()] as f64 / reports.len() as f64
+            },
+            by_type,
+            by_status,
+            median_resolution,
+        }
+    }
+
+    /// The spam ratio as a percentage (ex: `12.5%`)
+    pub fn spam_percentage(&self) -> String {
+        format!("{:.1}%", self.spam_ratio * 100.0)
+    }
+
+    /// The median resolution time in a readable form (ex: `2d 4h`)
+    pub fn median_resolution_text(&self) -> String {
+        match self.median_resolution {
+            Some(ms) => dates::duration_text(ms),
+            None => String::from("n/a"),
+        }
+    }
+}
+
+/// Calculate the [`ReportStats`] of a [`Period`]
+pub async fn get_stats(
+    db: &crate::db::Database,
+    period: Period,
+) -> DefaultReturn<Option<ReportStats>> {
+    let res = db.get_reports_in_period(period.from, period.to).await;
+
+    match res.payload {
+        Some(reports) => DefaultReturn {
+            success: true,
+            message: String::from("Calculated statistics"),
+            payload: Some(ReportStats::calculate(period, &reports)),
+        },
+        None => DefaultReturn {
+            success: false,
+            message: res.message,
+            payload: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_periods() {
+        let period = |label: &str| Period::parse(label).map(|p| (p.label, p.from, p.to));
+
+        assert_eq!(
+            period("2024"),
+            Some((String::from("2024"), 1704067200000, 1735689600000))
+        );
+        assert_eq!(
+            period("2024-q3"),
+            Some((String::from("2024-Q3"), 1719792000000, 1727740800000))
+        );
+        assert_eq!(
+            period("2024-Q4").map(|p| p.2),
+            Some(1735689600000),
+            "the last quarter ends with the year"
+        );
+        assert_eq!(
+            period(" 2024-07 "),
+            Some((String::from("2024-07"), 1719792000000, 1722470400000))
+        );
+
+        for invalid in [
+            "", "24", "1969", "2024-Q", "2024-Q0", "2024-Q5", "2024-00", "2024-13", "2024-7-1",
+            "abcd",
+        ] {
+            assert_eq!(Period::parse(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn median_resolution() {
+        let period = Period::parse("2024").unwrap();
+        let report = |status: ReportStatus, hours: u128| Report {
+            status,
+            timestamp: period.from,
+            status_timestamp: if hours > 0 {
+                period.from + hours * dates::HOUR
+            } else {
+                0
+            },
+            ..Default::default()
+        };
+
+        // only archived reports count
+        let mut reports = vec![
+            report(ReportStatus::Archived, 4),
+            report(ReportStatus::Archived, 1),
+            report(ReportStatus::Spam, 100),
+            report(ReportStatus::Active, 0),
+        ];
+        let stats = ReportStats::calculate(period.clone(), &reports);
+        assert_eq!(stats.median_resolution, Some(5 * dates::HOUR / 2));
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.by_status["Archived"], 2);
+        assert_eq!(stats.spam_percentage(), "25.0%");
+
+        reports.push(report(ReportStatus::Archived, 10));
+        let stats = ReportStats::calculate(period.clone(), &reports);
+        assert_eq!(stats.median_resolution, Some(4 * dates::HOUR));
+
+        let stats = ReportStats::calculate(period, &[]);
+        assert_eq!(stats.median_resolution, None);
+        assert_eq!(stats.spam_ratio, 0.0);
+    }
+}
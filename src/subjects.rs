@@ -0,0 +1,265 @@
+//! Reported subjects
+//!
+//! Every report is about a few subjects parsed from its address: the site (`host:example.com`),
+//! the page (`path:example.com/a/b`) and, when a configured pattern matches, the reported user
+//! (`user:example.com/alice`). Reports are linked to their subjects so repeat offenders can be
+//! found across every report ever filed.
+use actix_web::web;
+use serde::{Deserialize, Serialize};
+
+use crate::config::SubjectPattern;
+use crate::db::{AppData, Database, Report, ReportStatus};
+use dorsal::DefaultReturn;
+
+/// The kind of a subject (the part of its key before the first `:`)
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum SubjectKind {
+    /// A whole site
+    #[default]
+    Host,
+    /// A single page of a site
+    Path,
+    /// A user of a site
+    User,
+}
+
+impl std::fmt::Display for SubjectKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl SubjectKind {
+    /// The prefix of keys of this kind
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            SubjectKind::Host => "host",
+            SubjectKind::Path => "path",
+            SubjectKind::User => "user",
+        }
+    }
+
+    /// Get the kind and name of a subject from its key (`None` if the key is invalid)
+    pub fn parse(key: &str) -> Option<(SubjectKind, &str)> {
+        let (prefix, name) = key.split_once(':')?;
+
+        if name.is_empty() {
+            return None;
+        }
+
+        [SubjectKind::Host, SubjectKind::Path, SubjectKind::User]
+            .into_iter()
+            .find(|k| k.prefix() == prefix)
+            .map(|k| (k, name))
+    }
+
+    /// Get the key of a subject of this kind
+    pub fn key(&self, name: &str) -> String {
+        format!("{}:{name}", self.prefix())
+    }
+}
+
+/// Every report about a subject and their outcomes
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Subject {
+    pub key: String,
+    /// The key without its kind (ex: `example.com/alice`)
+    pub name: String,
+    pub kind: SubjectKind,
+    /// The number of reports still waiting for a decision
+    pub active: usize,
+    /// The number of resolved reports
+    pub resolved: usize,
+    /// The number of reports marked as spam
+    pub spam: usize,
+    /// When the subject was first reported
+    pub first: u128,
+    /// When the subject was last reported
+    pub last: u128,
+    /// Every report about the subject (newest first)
+    pub reports: Vec<Report>,
+}
+
+/// Get the keys of every subject of an address
+///
+/// # Arguments:
+/// * `address` - the reported address
+/// * `patterns` - the [`SubjectPattern`]s finding reported users (patterns which weren't compiled
+///   by [`crate::config::SubjectConfig::compile`] are skipped)
+pub fn parse(address: &str, patterns: &[SubjectPattern]) -> Vec<String> {
+    let url = match url::Url::parse(address) {
+        Ok(u) => u,
+        Err(_) => return Vec::new(),
+    };
+
+    let host = match url.host_str() {
+        Some(h) => h.to_lowercase(),
+        None => return Vec::new(),
+    };
+
+    let mut out: Vec<String> = vec![SubjectKind::Host.key(&host)];
+
+    let path = url.path().trim_end_matches('/');
+    if !path.is_empty() {
+        out.push(SubjectKind::Path.key(&format!("{host}{path}")));
+    }
+
+    for pattern in patterns
+        .iter()
+        .filter(|p| p.host.trim().to_lowercase() == host)
+    {
+        let regex = match &pattern.regex {
+            Some(r) => r,
+            None => continue,
+        };
+
+        if let Some(user) = regex
+            .captures(url.path())
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().to_lowercase())
+            .filter(|u| !u.is_empty())
+        {
+            out.push(SubjectKind::User.key(&format!("{host}/{user}")));
+            break;
+        }
+    }
+
+    out
+}
+
+/// Link a new [`Report`] to its subjects
+///
+/// # Arguments:
+/// * `data` - [`AppData`]
+/// * `report` - [`Report`]
+pub async fn record(data: &web::Data<AppData>, report: &Report) {
+    let subjects = parse(&report.canonical_address, &data.config.subjects.patterns);
+
+    if let Err(e) = data.db.set_report_subjects(&report.id, &subjects).await {
+        eprintln!("Failed to store subjects of {}: {e}", report.id);
+    }
+}
+
+/// Link every existing report to its subjects again (after patterns change), returning the
+/// number of reports
+///
+/// # Arguments:
+/// * `db` - [`Database`]
+/// * `patterns` - the [`SubjectPattern`]s finding reported users
+pub async fn rebuild(db: &Database, patterns: &[SubjectPattern]) -> Result<usize, String> {
+    let res = db
+        .get_every_report_by_filter(&crate::db::ReportFilter::default())
+        .await;
+
+    let reports = match res.payload {
+        Some(r) => r,
+        None => return Err(res.message),
+    };
+
+    for report in &reports {
+        db.set_report_subjects(&report.id, &parse(&report.canonical_address, patterns))
+            .await?;
+    }
+
+    Ok(reports.len())
+}
+
+/// Get a [`Subject`] by its key
+///
+/// # Arguments:
+/// * `db` - [`Database`]
+/// * `key` - the key of the subject (ex: `host:example.com`)
+pub async fn get(db: &Database, key: &str) -> DefaultReturn<Option<Subject>> {
+    let (kind, name) = match SubjectKind::parse(key) {
+        Some(k) => k,
+        None => {
+            return DefaultReturn {
+                success: false,
+                message: String::from("Subject is invalid"),
+                payload: None,
+            }
+        }
+    };
+
+    let res = db.get_reports_by_subject(key).await;
+    let reports = match res.payload {
+        Some(r) => r,
+        None => {
+            return DefaultReturn {
+                success: false,
+                message: res.message,
+                payload: None,
+            }
+        }
+    };
+
+    let count = |status: ReportStatus| reports.iter().filter(|r| r.status == status).count();
+
+    DefaultReturn {
+        success: true,
+        message: format!("Found {} report(s)", reports.len()),
+        payload: Some(Subject {
+            key: key.to_string(),
+            name: name.to_string(),
+            kind,
+            active: count(ReportStatus::Active),
+            resolved: count(ReportStatus::Archived),
+            spam: count(ReportStatus::Spam),
+            first: reports.iter().map(|r| r.timestamp).min().unwrap_or(0),
+            last: reports.iter().map(|r| r.timestamp).max().unwrap_or(0),
+            reports,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SubjectConfig;
+
+    #[test]
+    fn parse_subjects() {
+        let mut config = SubjectConfig {
+            patterns: vec![SubjectPattern {
+                host: String::from("example.com"),
+                pattern: String::from("^/@?([^/]+)/?$"),
+                regex: None,
+            }],
+        };
+
+        // uncompiled patterns never match
+        assert_eq!(
+            parse("https://example.com/@Alice", &config.patterns),
+            vec!["host:example.com", "path:example.com/@Alice"]
+        );
+
+        config.compile().unwrap();
+
+        assert_eq!(
+            parse("https://Example.com/@Alice", &config.patterns),
+            vec![
+                "host:example.com",
+                "path:example.com/@Alice",
+                "user:example.com/alice"
+            ]
+        );
+        assert_eq!(
+            parse("https://example.org/@alice", &config.patterns),
+            vec!["host:example.org", "path:example.org/@alice"]
+        );
+        assert!(parse("not an address", &config.patterns).is_empty());
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        let mut config = SubjectConfig {
+            patterns: vec![SubjectPattern {
+                host: String::from("example.com"),
+                pattern: String::from("("),
+                regex: None,
+            }],
+        };
+
+        assert!(config.compile().is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Chat webhook integrations (Matrix, Discord and Slack-compatible)
+use crate::config::{WebhookConfig, WebhookKind};
+use crate::notify::ReportEvent;
+
+/// The most characters of a report's content included in a message
+pub const EXCERPT_LENGTH: usize = 200;
+
+/// Get the first [`EXCERPT_LENGTH`] characters of the given content
+pub fn excerpt(content: &str) -> String {
+    let content = content.trim().replace('\n', " ");
+
+    if content.chars().count() > EXCERPT_LENGTH {
+        format!(
+            "{}…",
+            content.chars().take(EXCERPT_LENGTH).collect::<String>()
+        )
+    } else {
+        content
+    }
+}
+
+/// Percent-encode a single URL path segment
+fn encode_segment(input: &str) -> String {
+    let mut out = String::new();
+
+    for byte in input.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!:".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+
+    out
+}
+
+/// Build the webhook request body for an event
+///
+/// # Arguments:
+/// * `kind` - [`WebhookKind`]
+/// * `base_url` - the public URL of this server, used for links
+/// * `event` - [`ReportEvent`]
+pub fn payload(kind: &WebhookKind, base_url: &str, event: &ReportEvent) -> serde_json::Value {
+    let report = event.report();
+    let link = format!("{base_url}/report/{}", report.id);
+    let excerpt = excerpt(&report.content);
+
+    let title = match event {
+        ReportEvent::StatusChanged(_) => {
+            format!("{} report marked as {}", report.report_type, report.status)
+        }
+        _ => format!("{} report {}", report.report_type, event.action()),
+    };
+
+    let reason = if event.reason().is_empty() {
+        String::new()
+    } else {
+        format!("\nReason: {}", event.reason())
+    };
+
+    match kind {
+        WebhookKind::Matrix => serde_json::json!({
+            "msgtype": "m.notice",
+            "body": format!("{title}\nAddress: {}{reason}\n> {excerpt}\n{link}", report.address),
+            "format": "org.matrix.custom.html",
+            "formatted_body": format!(
+                "<b>{}</b><br>Address: {}{}<blockquote>{}</blockquote><a href=\"{}\">View report</a>",
+                escape_html(&title),
+                escape_html(&report.address),
+                escape_html(&reason).replace('\n', "<br>"),
+                escape_html(&excerpt),
+                escape_html(&link)
+            )
+        }),
+        WebhookKind::Discord => serde_json::json!({
+            "content": format!(
+                "**{title}**\nAddress: <{}>{reason}\n> {excerpt}\n[View report](<{link}>)",
+                report.address
+            ),
+            "allowed_mentions": { "parse": [] }
+        }),
+        WebhookKind::Slack => serde_json::json!({
+            "text": format!(
+                "*{}*\nAddress: {}{}\n> {}\n<{}|View report>",
+                escape_slack(&title),
+                escape_slack(&report.address),
+                escape_slack(&reason),
+                escape_slack(&excerpt),
+                link
+            )
+        }),
+    }
+}
+
+fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_slack(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Post a [`ReportEvent`] to every webhook routed to the report's type
+///
+/// # Arguments:
+/// * `client` - the client used to send requests
+/// * `base_url` - the public URL of this server, used for links
+/// * `webhooks` - every configured [`WebhookConfig`]
+/// * `event` - [`ReportEvent`]
+pub async fn post_event(
+    client: &awc::Client,
+    base_url: &str,
+    webhooks: &[WebhookConfig],
+    event: &ReportEvent,
+) {
+    for hook in webhooks {
+        if !hook.categories.is_empty() && !hook.categories.contains(&event.report().report_type) {
+            continue;
+        }
+
+        let body = payload(&hook.kind, base_url, event);
+        let res = match hook.kind {
+            WebhookKind::Matrix => {
+                client
+                    .put(format!(
+                        "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+                        hook.url.trim_end_matches('/'),
+                        encode_segment(&hook.room),
+                        dorsal::utility::uuid()
+                    ))
+                    .insert_header(("Authorization", format!("Bearer {}", hook.access_token)))
+                    .send_json(&body)
+                    .await
+            }
+            _ => client.post(&hook.url).send_json(&body).await,
+        };
+
+        match res {
+            Ok(res) if !res.status().is_success() => {
+                eprintln!("Webhook returned {}: {}", res.status(), hook.url)
+            }
+            Err(e) => eprintln!("Failed to post webhook: {e}"),
+            _ => (),
+        }
+    }
+}
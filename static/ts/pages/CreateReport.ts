@@ -1,3 +1,51 @@
+// embed protocol (see the `embed` module of the server docs)
+const PROTOCOL = "deducktive";
+const VERSION = parseInt(document.documentElement.dataset.protocolVersion!);
+
+// the origins messages are accepted from (any origin if empty)
+const origins = (document.documentElement.dataset.origins || "")
+    .split(",")
+    .filter((o) => o !== "");
+
+// the origin of the host page, known after its first accepted message
+let host_origin: string | null = null;
+
+const state = {
+    address: "",
+    as_user: "",
+};
+
+function origin_allowed(origin: string): boolean {
+    if (host_origin) {
+        return origin === host_origin;
+    }
+
+    return origins.length === 0 || origins.includes(origin);
+}
+
+// post a message to the host page (if the embed is in a frame)
+function post(type: string, data: { [key: string]: any } = {}) {
+    if (window.parent === window) {
+        return;
+    }
+
+    const message = { protocol: PROTOCOL, version: VERSION, type, ...data };
+
+    if (host_origin) {
+        window.parent.postMessage(message, host_origin);
+        return;
+    }
+
+    // the host page isn't known yet, only post to its referrer if it's allowed
+    if (document.referrer) {
+        const origin = new URL(document.referrer).origin;
+
+        if (origin_allowed(origin)) {
+            window.parent.postMessage(message, origin);
+        }
+    }
+}
+
 const report_form = document.getElementById(
     "report_page",
 ) as HTMLFormElement | null;
@@ -10,9 +58,112 @@ if (report_form && warning) {
         warning.remove();
     });
 
+    for (const button of Array.from(document.querySelectorAll(".cancel"))) {
+        button.addEventListener("click", () => post("cancelled"));
+    }
+
+    // only show the custom fields of the selected report type
+    const custom_fields = Array.from(
+        report_form.querySelectorAll(".custom-field"),
+    ) as HTMLDivElement[];
+
+    function update_fields() {
+        const report_type = report_form!.report_type.value;
+
+        for (const field of custom_fields) {
+            const types = field.dataset.reportTypes!.split(",");
+            const shown = types[0] === "" || types.includes(report_type);
+
+            field.style.display = shown ? "flex" : "none";
+            for (const input of Array.from(
+                field.querySelectorAll("[data-field]"),
+            ) as HTMLInputElement[]) {
+                input.disabled = !shown;
+            }
+        }
+    }
+
+    report_form.report_type.addEventListener("change", update_fields);
+    update_fields();
+
+    // prefill the form with the properties of an `init` message
+    function prefill(data: { [key: string]: any }) {
+        if (typeof data.address === "string") {
+            state.address = data.address;
+        }
+
+        if (typeof data.as_user === "string") {
+            state.as_user = data.as_user;
+        }
+
+        if (
+            typeof data.report_type === "string" &&
+            Array.from(report_form!.report_type.options).some(
+                (o: any) => o.value === data.report_type,
+            )
+        ) {
+            report_form!.report_type.value = data.report_type;
+            update_fields();
+        }
+
+        if (typeof data.content === "string") {
+            report_form!.content.value = data.content;
+        }
+
+        if (typeof data.fields === "object" && data.fields !== null) {
+            for (const input of Array.from(
+                report_form!.querySelectorAll("[data-field]"),
+            ) as HTMLInputElement[]) {
+                const value = data.fields[input.dataset.field!];
+
+                if (typeof value === "string") {
+                    input.value = value;
+                }
+            }
+        }
+    }
+
+    window.addEventListener("message", (event) => {
+        if (
+            event.source !== window.parent ||
+            typeof event.data !== "object" ||
+            event.data === null ||
+            !origin_allowed(event.origin)
+        ) {
+            return;
+        }
+
+        const data = event.data;
+
+        if (data.protocol === PROTOCOL) {
+            if (data.version !== VERSION || data.type !== "init") {
+                return;
+            }
+
+            host_origin = event.origin;
+            prefill(data);
+        } else if (data.assign === "REAL_HREF") {
+            // version 0
+            host_origin = event.origin;
+            prefill({ address: data.value });
+        } else if (data.assign === "REPORT_AS_USER") {
+            host_origin = event.origin;
+            prefill({ as_user: data.value });
+        }
+    });
+
     report_form.addEventListener("submit", async (event) => {
         event.preventDefault();
 
+        const fields: { [name: string]: string } = {};
+        for (const input of Array.from(
+            report_form.querySelectorAll("[data-field]"),
+        ) as HTMLInputElement[]) {
+            if (!input.disabled) {
+                fields[input.dataset.field!] = input.value;
+            }
+        }
+
         const res = await fetch("/api/v1/reports", {
             method: "POST",
             headers: {
@@ -24,13 +175,90 @@ if (report_form && warning) {
                         report_form.report_type.selectedIndex
                     ].value,
                 content: report_form.content.value,
-                address: (window as any).REAL_HREF,
-                // get current user username
-                as_user: (window as any).REPORT_AS_USER,
+                address: state.address,
+                as_user: state.as_user,
+                fields,
             }),
         });
 
         const json = await res.json();
-        document.body.innerHTML = `<p>${json.message} -- Please exit this form.</p>`;
+        const files: File[] = report_form.attachments
+            ? Array.from(report_form.attachments.files)
+            : [];
+
+        // upload evidence
+        const failed: string[] = [];
+
+        if (json.payload && json.payload.tracking) {
+            for (const file of files) {
+                const upload = await fetch(
+                    `/api/v1/track/${json.payload.tracking}/attachments?name=${encodeURIComponent(file.name)}`,
+                    {
+                        method: "POST",
+                        body: file,
+                    },
+                );
+
+                const upload_json = await upload.json();
+
+                if (upload_json.success === false) {
+                    failed.push(`${file.name}: ${upload_json.message}`);
+                }
+            }
+        }
+
+        const message = document.createElement("p");
+        message.innerText = `${json.message} -- ${report_form.dataset.exit}`;
+        document.body.replaceChildren(message);
+
+        if (failed.length > 0) {
+            const failed_message = document.createElement("p");
+            failed_message.innerText = `${report_form.dataset.attachmentsFailed} ${failed.join(", ")}`;
+            document.body.append(failed_message);
+        }
+
+        if (json.payload && json.payload.tracking) {
+            // any page can embed the form when no origins are configured, so the token is only
+            // shared with configured origins
+            post(
+                "submitted",
+                origins.length === 0 ? {} : { tracking: json.payload.tracking },
+            );
+
+            // show tracking link
+            const tracking = document.createElement("p");
+            const link = document.createElement("a");
+
+            link.href = `/track/${json.payload.tracking}`;
+            link.target = "_blank";
+            link.innerText = report_form.dataset.track!;
+
+            tracking.append(link);
+            document.body.append(tracking);
+        }
     });
 }
+
+// tell the host page the embed is ready for `init`
+if (window.parent !== window) {
+    const message = { protocol: PROTOCOL, version: VERSION, type: "ready" };
+
+    if (origins.length === 0) {
+        window.parent.postMessage(message, "*");
+    } else {
+        for (const origin of origins) {
+            window.parent.postMessage(message, origin);
+        }
+    }
+
+    // keep the host's frame as tall as the form
+    let height = 0;
+    new ResizeObserver(() => {
+        const new_height = document.documentElement.scrollHeight;
+
+        if (new_height !== height) {
+            height = new_height;
+            post("resize", { height });
+        }
+    }).observe(document.body);
+}
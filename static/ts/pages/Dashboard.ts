@@ -0,0 +1,145 @@
+const error: HTMLElement = document.getElementById("error")!;
+
+const feed_button: HTMLButtonElement | null = document.getElementById(
+    "feed",
+) as HTMLButtonElement | null;
+
+const feed_url: HTMLInputElement | null = document.getElementById(
+    "feed_url",
+) as HTMLInputElement | null;
+
+if (feed_button && feed_url) {
+    feed_button.addEventListener("click", async (e) => {
+        e.preventDefault();
+
+        if (
+            !confirm(
+                "This will create a new feed URL, your old feed URL will stop working. Continue?",
+            )
+        ) {
+            return;
+        }
+
+        const res = await fetch("/api/v1/feed/token", {
+            method: "POST",
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            feed_url.style.display = "block";
+            feed_url.value = `${window.location.origin}/api/v1/reports/feed.atom?token=${json.payload}${feed_button.getAttribute("data-filter")}`;
+            feed_url.select();
+        }
+    });
+}
+
+// bulk actions
+const success: HTMLElement = document.getElementById("success")!;
+
+const bulk_form: HTMLFormElement | null = document.getElementById(
+    "bulk",
+) as HTMLFormElement | null;
+
+const bulk_all: HTMLInputElement | null = document.getElementById(
+    "bulk-all",
+) as HTMLInputElement | null;
+
+const bulk_checkboxes = (): HTMLInputElement[] =>
+    Array.from(document.querySelectorAll("input.bulk-select"));
+
+if (bulk_all) {
+    bulk_all.addEventListener("change", () => {
+        for (const checkbox of bulk_checkboxes()) {
+            checkbox.checked = bulk_all.checked;
+        }
+    });
+}
+
+if (bulk_form) {
+    bulk_form.addEventListener("submit", async (e) => {
+        e.preventDefault();
+
+        const scope = ((e as SubmitEvent).submitter as HTMLButtonElement | null)
+            ?.getAttribute("data-scope");
+
+        const action_value: string = bulk_form.bulk_action.value;
+        const value: string = bulk_form.bulk_value.value;
+        const action =
+            action_value === "assign"
+                ? { Assign: value }
+                : action_value === "tag"
+                  ? { Tag: value }
+                  : action_value === "redact"
+                    ? "Redact"
+                    : action_value === "delete"
+                      ? "Delete"
+                      : { Status: action_value };
+
+        if (
+            (action === "Delete" || action === "Redact") &&
+            !confirm("This cannot be undone. Continue?")
+        ) {
+            return;
+        }
+
+        let body: { ids?: string[]; filter?: object; action: object | string };
+
+        if (scope === "filter") {
+            const status = bulk_form.getAttribute("data-status");
+            const report_type = bulk_form.getAttribute("data-type");
+            const tag = bulk_form.getAttribute("data-tag");
+
+            if (
+                !confirm(
+                    "This will change every report matching the current filter. Continue?",
+                )
+            ) {
+                return;
+            }
+
+            body = {
+                filter: {
+                    status: status ? status : null,
+                    report_type: report_type ? report_type : null,
+                    tag: tag ? tag : null,
+                },
+                action,
+            };
+        } else {
+            const ids = bulk_checkboxes()
+                .filter((checkbox) => checkbox.checked)
+                .map((checkbox) => checkbox.value);
+
+            if (ids.length === 0) {
+                error.style.display = "block";
+                error.innerHTML = `<div class="mdnote-title">No reports selected</div>`;
+                return;
+            }
+
+            body = { ids, action };
+        }
+
+        const res = await fetch("/api/v1/reports/bulk", {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify(body),
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            success.style.display = "block";
+            success.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+            window.setTimeout(() => window.location.reload(), 1000);
+        }
+    });
+}
@@ -0,0 +1,143 @@
+// host SDK, opens the report form of a Deducktive server in a modal
+// (see the `embed` module of the server docs for the message protocol)
+const PROTOCOL = "deducktive";
+const VERSION = 1;
+
+export type EmbedOptions = {
+    // the Deducktive server (defaults to the server this script is served from)
+    server?: string;
+    // the reported address (defaults to the current page)
+    address?: string;
+    // the username of the reporter
+    as_user?: string;
+    // the preselected report type
+    report_type?: string;
+    // the prefilled report content
+    content?: string;
+    // the prefilled custom field values by name
+    fields?: { [name: string]: string };
+    // the locale of the form (negotiated from the browser if missing)
+    lang?: string;
+    // called once a report is created, with its tracking token (only if the server has sites
+    // configured)
+    on_submitted?: (event: { tracking?: string }) => void;
+    // called when the form is closed without a report being created
+    on_cancelled?: () => void;
+};
+
+export type Embed = {
+    // close the modal
+    close: () => void;
+};
+
+// open the report form in a modal
+export function open(options: EmbedOptions = {}): Embed {
+    const server = new URL(options.server || new URL(import.meta.url).origin)
+        .origin;
+
+    const url = new URL("/api/v1/reports/embed", server);
+    url.searchParams.set("site", window.location.origin);
+
+    if (options.lang) {
+        url.searchParams.set("lang", options.lang);
+    }
+
+    // modal
+    const backdrop = document.createElement("div");
+    backdrop.setAttribute(
+        "style",
+        "position: fixed; inset: 0; z-index: 2147483647; display: flex; align-items: center; justify-content: center; background: rgba(0, 0, 0, 0.5);",
+    );
+
+    const frame = document.createElement("iframe");
+    frame.src = url.toString();
+    frame.title = "Report";
+    frame.setAttribute(
+        "style",
+        "width: min(600px, 100vw); height: 400px; max-height: 100vh; border: 0; border-radius: 8px; background: white;",
+    );
+
+    backdrop.append(frame);
+    document.body.append(backdrop);
+
+    let submitted = false;
+    let closed = false;
+
+    function close() {
+        if (closed) {
+            return;
+        }
+
+        closed = true;
+        window.removeEventListener("message", listener);
+        window.removeEventListener("keydown", on_key);
+        backdrop.remove();
+
+        if (!submitted && options.on_cancelled) {
+            options.on_cancelled();
+        }
+    }
+
+    function listener(event: MessageEvent) {
+        if (
+            event.origin !== server ||
+            event.source !== frame.contentWindow ||
+            typeof event.data !== "object" ||
+            event.data === null ||
+            event.data.protocol !== PROTOCOL ||
+            event.data.version !== VERSION
+        ) {
+            return;
+        }
+
+        switch (event.data.type) {
+            case "ready":
+                frame.contentWindow!.postMessage(
+                    {
+                        protocol: PROTOCOL,
+                        version: VERSION,
+                        type: "init",
+                        address: options.address || window.location.href,
+                        as_user: options.as_user,
+                        report_type: options.report_type,
+                        content: options.content,
+                        fields: options.fields,
+                    },
+                    server,
+                );
+                break;
+            case "resize":
+                frame.style.height = `${event.data.height}px`;
+                break;
+            case "submitted":
+                submitted = true;
+
+                if (options.on_submitted) {
+                    options.on_submitted({ tracking: event.data.tracking });
+                }
+                break;
+            case "cancelled":
+                close();
+                break;
+        }
+    }
+
+    function on_key(event: KeyboardEvent) {
+        if (event.key === "Escape") {
+            close();
+        }
+    }
+
+    window.addEventListener("message", listener);
+    window.addEventListener("keydown", on_key);
+
+    backdrop.addEventListener("click", (event) => {
+        if (event.target === backdrop) {
+            close();
+        }
+    });
+
+    return { close };
+}
+
+(window as any).Deducktive = { open };
@@ -1,5 +1,8 @@
 const error: HTMLElement = document.getElementById("error")!;
 const success: HTMLElement = document.getElementById("success")!;
+const resolution: HTMLTextAreaElement = document.getElementById(
+    "resolution",
+) as HTMLTextAreaElement;
 
 const resolve_button: HTMLButtonElement | null = document.getElementById(
     "mark-as-resolved",
@@ -15,6 +18,7 @@ if (resolve_button) {
             },
             body: JSON.stringify({
                 status: "Archived",
+                resolution: resolution.value,
             }),
         });
 
@@ -44,6 +48,237 @@ if (spam_button) {
             },
             body: JSON.stringify({
                 status: "Spam",
+                resolution: resolution.value,
+            }),
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            success.style.display = "block";
+            success.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        }
+    });
+}
+
+const escalate_button: HTMLButtonElement | null = document.getElementById(
+    "escalate",
+) as HTMLButtonElement | null;
+
+if (escalate_button) {
+    escalate_button.addEventListener("click", async (e) => {
+        e.preventDefault();
+        const reason = prompt("Reason for escalation:");
+
+        if (reason === null) {
+            return;
+        }
+
+        const res = await fetch(escalate_button.getAttribute("data-endpoint")!, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                reason,
+            }),
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            success.style.display = "block";
+            success.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        }
+    });
+}
+
+const forward_form: HTMLFormElement | null = document.getElementById(
+    "forward",
+) as HTMLFormElement | null;
+
+if (forward_form) {
+    forward_form.addEventListener("submit", async (e) => {
+        e.preventDefault();
+        const res = await fetch(forward_form.getAttribute("data-endpoint")!, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                inbox: forward_form.inbox.value,
+            }),
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            success.style.display = "block";
+            success.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        }
+    });
+}
+
+const add_tag_form: HTMLFormElement | null = document.getElementById(
+    "add-tag",
+) as HTMLFormElement | null;
+
+if (add_tag_form) {
+    add_tag_form.addEventListener("submit", async (e) => {
+        e.preventDefault();
+        const res = await fetch(add_tag_form.getAttribute("data-endpoint")!, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                tag: add_tag_form.tag.value,
+            }),
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            window.location.reload();
+        }
+    });
+}
+
+for (const remove_tag of Array.from(
+    document.querySelectorAll("a.remove-tag"),
+) as HTMLAnchorElement[]) {
+    remove_tag.addEventListener("click", async (e) => {
+        e.preventDefault();
+        const res = await fetch(remove_tag.getAttribute("data-endpoint")!, {
+            method: "DELETE",
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            window.location.reload();
+        }
+    });
+}
+
+const attach_form: HTMLFormElement | null = document.getElementById(
+    "attach",
+) as HTMLFormElement | null;
+
+if (attach_form) {
+    attach_form.addEventListener("submit", async (e) => {
+        e.preventDefault();
+        const file: File = attach_form.file.files[0];
+
+        const res = await fetch(
+            `${attach_form.getAttribute("data-endpoint")!}?name=${encodeURIComponent(file.name)}`,
+            {
+                method: "POST",
+                body: file,
+            },
+        );
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            window.location.reload();
+        }
+    });
+}
+
+// data removal
+const redact_button: HTMLButtonElement | null = document.getElementById(
+    "redact",
+) as HTMLButtonElement | null;
+
+const delete_button: HTMLButtonElement | null = document.getElementById(
+    "delete",
+) as HTMLButtonElement | null;
+
+const erase_button: HTMLButtonElement | null = document.getElementById(
+    "erase-author",
+) as HTMLButtonElement | null;
+
+if (redact_button) {
+    redact_button.addEventListener("click", async (e) => {
+        e.preventDefault();
+
+        if (!confirm("Remove the author and content of this report?")) {
+            return;
+        }
+
+        const res = await fetch(redact_button.getAttribute("data-endpoint")!, {
+            method: "POST",
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            window.location.reload();
+        }
+    });
+}
+
+if (delete_button) {
+    delete_button.addEventListener("click", async (e) => {
+        e.preventDefault();
+
+        if (!confirm("Permanently delete this report?")) {
+            return;
+        }
+
+        const res = await fetch(delete_button.getAttribute("data-endpoint")!, {
+            method: "DELETE",
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            error.style.display = "block";
+            error.innerHTML = `<div class="mdnote-title">${json.message}</div>`;
+        } else {
+            window.location.href = "/";
+        }
+    });
+}
+
+if (erase_button) {
+    erase_button.addEventListener("click", async (e) => {
+        e.preventDefault();
+        const author = erase_button.getAttribute("data-author")!;
+
+        if (!confirm(`Redact every report created by "${author}"?`)) {
+            return;
+        }
+
+        const res = await fetch(erase_button.getAttribute("data-endpoint")!, {
+            method: "POST",
+            headers: {
+                "Content-Type": "application/json",
+            },
+            body: JSON.stringify({
+                author,
             }),
         });
 
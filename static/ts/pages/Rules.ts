@@ -0,0 +1,144 @@
+const error: HTMLElement = document.getElementById("error")!;
+const success: HTMLElement = document.getElementById("success")!;
+
+const editor: HTMLFormElement = document.getElementById(
+    "rule-editor",
+) as HTMLFormElement;
+
+const editor_title: HTMLElement = document.getElementById("editor-title")!;
+const simulation: HTMLTableElement = document.getElementById(
+    "simulation",
+) as HTMLTableElement;
+
+const default_rule: string = editor.rule.value;
+
+const show_error = (message: string) => {
+    success.style.display = "none";
+    error.style.display = "block";
+    error.innerHTML = `<div class="mdnote-title">${message}</div>`;
+};
+
+const show_success = (message: string) => {
+    error.style.display = "none";
+    success.style.display = "block";
+    success.innerHTML = `<div class="mdnote-title">${message}</div>`;
+};
+
+// load rules into the editor
+for (const button of Array.from(
+    document.querySelectorAll("button.rule-edit"),
+) as HTMLButtonElement[]) {
+    button.addEventListener("click", () => {
+        const rule = JSON.parse(button.getAttribute("data-rule")!);
+
+        editor.setAttribute("data-id", rule.id);
+        editor_title.innerText = `Edit "${rule.name}"`;
+
+        delete rule.id;
+        delete rule.timestamp;
+        editor.rule.value = JSON.stringify(rule, null, 4);
+        editor.scrollIntoView();
+    });
+}
+
+for (const button of Array.from(
+    document.querySelectorAll("button.rule-delete"),
+) as HTMLButtonElement[]) {
+    button.addEventListener("click", async () => {
+        if (!confirm("This rule will be deleted. Continue?")) {
+            return;
+        }
+
+        const res = await fetch(button.getAttribute("data-endpoint")!, {
+            method: "DELETE",
+        });
+
+        const json = await res.json();
+
+        if (json.success === false) {
+            show_error(json.message);
+        } else {
+            window.location.reload();
+        }
+    });
+}
+
+// save or simulate
+editor.addEventListener("submit", async (e) => {
+    e.preventDefault();
+
+    const action = ((e as SubmitEvent).submitter as HTMLButtonElement | null)
+        ?.getAttribute("data-action");
+
+    if (action === "new") {
+        editor.setAttribute("data-id", "");
+        editor_title.innerText = "New Rule";
+        editor.rule.value = default_rule;
+        simulation.style.display = "none";
+        return;
+    }
+
+    let rule: object;
+
+    try {
+        rule = JSON.parse(editor.rule.value);
+    } catch (err) {
+        show_error(`Rule is not valid JSON: ${err}`);
+        return;
+    }
+
+    const id = editor.getAttribute("data-id");
+    const endpoint =
+        action === "simulate"
+            ? "/api/v1/rules/simulate"
+            : id
+              ? `/api/v1/rules/${id}`
+              : "/api/v1/rules";
+
+    const res = await fetch(endpoint, {
+        method: "POST",
+        headers: {
+            "Content-Type": "application/json",
+        },
+        body: JSON.stringify(rule),
+    });
+
+    const json = await res.json();
+
+    if (json.success === false) {
+        show_error(json.message);
+        return;
+    }
+
+    show_success(json.message);
+
+    if (action !== "simulate") {
+        window.setTimeout(() => window.location.reload(), 1000);
+        return;
+    }
+
+    // show matched reports
+    const body = simulation.querySelector("tbody")!;
+    body.innerHTML = "";
+
+    for (const match of json.payload) {
+        const row = document.createElement("tr");
+
+        const report = document.createElement("td");
+        const link = document.createElement("a");
+        link.href = `/report/${match.report}`;
+        link.innerText = match.report;
+        report.appendChild(link);
+
+        const status = document.createElement("td");
+        status.innerText = match.status;
+
+        const address = document.createElement("td");
+        address.innerText = match.address;
+
+        row.append(report, status, address);
+        body.appendChild(row);
+    }
+
+    simulation.style.display = "table";
+});
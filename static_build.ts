@@ -12,6 +12,9 @@ const output = await build({
         "./static/ts/pages/Footer.ts",
         "./static/ts/pages/ReportView.ts",
         "./static/ts/pages/CreateReport.ts",
+        "./static/ts/pages/Dashboard.ts",
+        "./static/ts/pages/Rules.ts",
+        "./static/ts/pages/Embed.ts",
     ],
     minify: {
         identifiers: true,